@@ -0,0 +1,54 @@
+//! Compares the serialization of maps, whose entries need to be sorted canonically.
+//!
+//! Run with `cargo bench --bench collect_map`.
+use std::collections::{BTreeMap, HashMap};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use serde_ipld_dagcbor::to_vec;
+
+const ENTRIES: u64 = 50_000;
+const ROUNDS: u32 = 20;
+
+/// Returns the average time the function takes.
+fn measure<F: FnMut()>(mut f: F) -> Duration {
+    f();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        f();
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn main() {
+    // Keys of the same length are already in canonical order in a `BTreeMap`.
+    let same_length: BTreeMap<String, u64> =
+        (0..ENTRIES).map(|i| (format!("{:08}", i), i)).collect();
+    // Keys of varying length are not.
+    let varying_length: BTreeMap<String, u64> = (0..ENTRIES).map(|i| (i.to_string(), i)).collect();
+    let unordered: HashMap<String, u64> = varying_length.clone().into_iter().collect();
+
+    let results = [
+        (
+            "BTreeMap, keys of the same length",
+            measure(|| {
+                black_box(to_vec(&same_length).unwrap());
+            }),
+        ),
+        (
+            "BTreeMap, keys of varying length",
+            measure(|| {
+                black_box(to_vec(&varying_length).unwrap());
+            }),
+        ),
+        (
+            "HashMap",
+            measure(|| {
+                black_box(to_vec(&unordered).unwrap());
+            }),
+        ),
+    ];
+    for (name, duration) in results {
+        println!("{:<35} {:>10.3?} for {} entries", name, duration, ENTRIES);
+    }
+}
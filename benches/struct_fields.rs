@@ -0,0 +1,155 @@
+//! Compares the serialization of structs with their fields in declaration order, to the ones with
+//! their fields in canonical order, with and without the cached field order.
+//!
+//! Run with `cargo bench --bench struct_fields`.
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_derive::Serialize;
+use serde_ipld_dagcbor::ser::{to_vec_with_options, EncodeOptions};
+
+const ITEMS: u64 = 10_000;
+const ROUNDS: u32 = 20;
+
+const KEYS: [&str; 7] = [
+    "modified", "created", "parent", "owner", "size", "kind", "id",
+];
+
+/// Fields that are declared in canonical order.
+#[derive(Serialize)]
+struct Canonical {
+    id: u64,
+    kind: u64,
+    size: u64,
+    owner: u64,
+    parent: u64,
+    created: u64,
+    modified: u64,
+}
+
+/// The same fields, declared in another order.
+#[derive(Serialize)]
+struct Unordered {
+    modified: u64,
+    created: u64,
+    parent: u64,
+    owner: u64,
+    size: u64,
+    kind: u64,
+    id: u64,
+}
+
+/// The same fields, serialized starting at another key. The cached order is the one of the first
+/// value, hence values with another start don't match it and their fields are sorted each time.
+struct Rotated {
+    start: usize,
+    value: u64,
+}
+
+impl Serialize for Rotated {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Rotated", KEYS.len())?;
+        for index in 0..KEYS.len() {
+            state.serialize_field(KEYS[(self.start + index) % KEYS.len()], &self.value)?;
+        }
+        state.end()
+    }
+}
+
+/// Returns the average time the function takes.
+fn measure<F: FnMut()>(mut f: F) -> Duration {
+    f();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        f();
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn main() {
+    let declared = EncodeOptions::default();
+    let canonical_fields = EncodeOptions {
+        canonical_struct_fields: true,
+        ..Default::default()
+    };
+
+    let canonical: Vec<_> = (0..ITEMS)
+        .map(|i| Canonical {
+            id: i,
+            kind: i,
+            size: i,
+            owner: i,
+            parent: i,
+            created: i,
+            modified: i,
+        })
+        .collect();
+    let unordered: Vec<_> = (0..ITEMS)
+        .map(|i| Unordered {
+            modified: i,
+            created: i,
+            parent: i,
+            owner: i,
+            size: i,
+            kind: i,
+            id: i,
+        })
+        .collect();
+    // The first value determines the cached order, all others start at another key.
+    to_vec_with_options(&Rotated { start: 0, value: 0 }, &canonical_fields).unwrap();
+    let rotated: Vec<_> = (0..ITEMS)
+        .map(|i| Rotated {
+            start: 1 + i as usize % (KEYS.len() - 1),
+            value: i,
+        })
+        .collect();
+    let maps: Vec<_> = (0..ITEMS)
+        .map(|i| KEYS.iter().map(|key| (*key, i)).collect::<BTreeMap<_, _>>())
+        .collect();
+
+    let results = [
+        (
+            "declaration order (baseline)",
+            measure(|| {
+                black_box(to_vec_with_options(&unordered, &declared).unwrap());
+            }),
+        ),
+        (
+            "canonical order, declared canonically",
+            measure(|| {
+                black_box(to_vec_with_options(&canonical, &canonical_fields).unwrap());
+            }),
+        ),
+        (
+            "canonical order, cached",
+            measure(|| {
+                black_box(to_vec_with_options(&unordered, &canonical_fields).unwrap());
+            }),
+        ),
+        (
+            "canonical order, cached, one value per call",
+            measure(|| {
+                for item in &unordered {
+                    black_box(to_vec_with_options(item, &canonical_fields).unwrap());
+                }
+            }),
+        ),
+        (
+            "canonical order, uncached",
+            measure(|| {
+                black_box(to_vec_with_options(&rotated, &canonical_fields).unwrap());
+            }),
+        ),
+        (
+            "map, sorted per map",
+            measure(|| {
+                black_box(to_vec_with_options(&maps, &declared).unwrap());
+            }),
+        ),
+    ];
+    for (name, duration) in results {
+        println!("{:<45} {:>10.3?} for {} items", name, duration, ITEMS);
+    }
+}
@@ -0,0 +1,415 @@
+//! Parsing and checking of the `#[ipld(...)]` attributes.
+use std::collections::HashSet;
+
+use syn::{
+    spanned::Spanned, Attribute, Data, DeriveInput, Error, Fields, Generics, Ident, LitStr, Member,
+    Type,
+};
+
+/// The representation strategy of a type.
+pub enum Repr {
+    /// A struct as map, the default for structs.
+    Map,
+    /// A struct as list of its field values.
+    Tuple,
+    /// A struct as string of its field values joined by a separator.
+    StringJoin(String),
+    /// An enum as single entry map from the variant key to its value, the default for enums.
+    Keyed,
+    /// An enum that is distinguished by the kind of its value.
+    Kinded,
+    /// An enum of unit variants as the integer of their discriminant.
+    Int,
+}
+
+/// The kinds of the IPLD Data Model.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Null,
+    Bool,
+    Int,
+    Float,
+    String,
+    Bytes,
+    List,
+    Map,
+    Link,
+}
+
+impl Kind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "null" => Some(Kind::Null),
+            "bool" => Some(Kind::Bool),
+            "int" => Some(Kind::Int),
+            "float" => Some(Kind::Float),
+            "string" => Some(Kind::String),
+            "bytes" => Some(Kind::Bytes),
+            "list" => Some(Kind::List),
+            "map" => Some(Kind::Map),
+            "link" => Some(Kind::Link),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Null => "null",
+            Kind::Bool => "bool",
+            Kind::Int => "int",
+            Kind::Float => "float",
+            Kind::String => "string",
+            Kind::Bytes => "bytes",
+            Kind::List => "list",
+            Kind::Map => "map",
+            Kind::Link => "link",
+        }
+    }
+}
+
+/// The type the macro is derived for.
+pub struct Container<'a> {
+    pub ident: &'a Ident,
+    pub generics: &'a Generics,
+    pub repr: Repr,
+    pub body: Body<'a>,
+}
+
+pub enum Body<'a> {
+    Struct(Vec<Field>),
+    Enum(Vec<Variant<'a>>),
+}
+
+pub struct Field {
+    pub member: Member,
+    /// The key of the field in the map representation.
+    pub key: String,
+    /// Whether the field is omitted if it's `None`.
+    pub optional: bool,
+}
+
+pub struct Variant<'a> {
+    pub ident: &'a Ident,
+    /// The type of the newtype variant, `None` for unit variants.
+    pub ty: Option<&'a Type>,
+    /// The key of the variant in the keyed representation.
+    pub key: String,
+    /// The kind of the variant in the kinded representation.
+    pub kind: Option<Kind>,
+}
+
+impl<'a> Container<'a> {
+    pub fn from_ast(input: &'a DeriveInput) -> Result<Self, Error> {
+        if let Some(lifetime) = input.generics.lifetimes().next() {
+            return Err(Error::new(
+                lifetime.span(),
+                "lifetime parameters are not supported",
+            ));
+        }
+
+        let mut repr = None;
+        let mut join = None;
+        for attr in ipld_attrs(&input.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("repr") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    repr = Some((value.value(), value.span()));
+                    Ok(())
+                } else if meta.path.is_ident("join") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    join = Some(value.value());
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown attribute, expected `repr` or `join`"))
+                }
+            })?;
+        }
+
+        let is_struct = matches!(input.data, Data::Struct(_));
+        let repr =
+            match repr {
+                None if is_struct => Repr::Map,
+                None => Repr::Keyed,
+                Some((name, span)) => match (name.as_str(), is_struct) {
+                    ("map", true) => Repr::Map,
+                    ("tuple", true) => Repr::Tuple,
+                    ("stringjoin", true) => match join.take() {
+                        Some(join) if !join.is_empty() => Repr::StringJoin(join),
+                        _ => return Err(Error::new(
+                            span,
+                            "the `stringjoin` representation needs a non-empty `join` attribute",
+                        )),
+                    },
+                    ("keyed", false) => Repr::Keyed,
+                    ("kinded", false) => Repr::Kinded,
+                    ("int", false) => Repr::Int,
+                    ("map" | "tuple" | "stringjoin", false) => {
+                        return Err(Error::new(
+                            span,
+                            "this representation is only valid for structs",
+                        ))
+                    }
+                    ("keyed" | "kinded" | "int", true) => {
+                        return Err(Error::new(
+                            span,
+                            "this representation is only valid for enums",
+                        ))
+                    }
+                    _ => return Err(Error::new(
+                        span,
+                        "unknown representation, expected `map`, `tuple`, `stringjoin`, `keyed`, \
+                         `kinded` or `int`",
+                    )),
+                },
+            };
+        if join.is_some() {
+            return Err(Error::new(
+                input.ident.span(),
+                "the `join` attribute is only valid with the `stringjoin` representation",
+            ));
+        }
+
+        let body = match &input.data {
+            Data::Struct(data) => Body::Struct(fields(&data.fields, &repr)?),
+            Data::Enum(data) => Body::Enum(
+                data.variants
+                    .iter()
+                    .map(|variant| Variant::from_ast(variant, &repr))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Data::Union(_) => {
+                return Err(Error::new(
+                    input.ident.span(),
+                    "unions are not supported, use an enum instead",
+                ))
+            }
+        };
+        if let Body::Enum(variants) = &body {
+            check_variants(variants, &repr, input)?;
+        }
+
+        Ok(Container {
+            ident: &input.ident,
+            generics: &input.generics,
+            repr,
+            body,
+        })
+    }
+}
+
+fn fields(fields: &Fields, repr: &Repr) -> Result<Vec<Field>, Error> {
+    match (fields, repr) {
+        (Fields::Unit, _) => {
+            return Err(Error::new(fields.span(), "unit structs are not supported"))
+        }
+        (Fields::Unnamed(_), Repr::Map) => {
+            return Err(Error::new(
+                fields.span(),
+                "tuple structs need the `tuple` or `stringjoin` representation",
+            ))
+        }
+        _ => {}
+    }
+
+    let mut result = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(index.into()),
+        };
+        let mut key = field.ident.as_ref().map(|ident| {
+            let name = ident.to_string();
+            name.strip_prefix("r#").map(str::to_string).unwrap_or(name)
+        });
+        let mut optional = false;
+        for attr in ipld_attrs(&field.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    key = Some(value.value());
+                    Ok(())
+                } else if meta.path.is_ident("optional") {
+                    optional = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown attribute, expected `rename` or `optional`"))
+                }
+            })?;
+        }
+        if optional {
+            if !is_option(&field.ty) {
+                return Err(Error::new(
+                    field.ty.span(),
+                    "optional fields must be of type `Option`",
+                ));
+            }
+            if let Repr::StringJoin(_) = repr {
+                return Err(Error::new(
+                    field.span(),
+                    "the `stringjoin` representation doesn't support optional fields",
+                ));
+            }
+        }
+        result.push(Field {
+            member,
+            key: key.unwrap_or_default(),
+            optional,
+        });
+    }
+
+    match repr {
+        Repr::Map => {
+            let mut keys = HashSet::new();
+            for (field, ast) in result.iter().zip(fields) {
+                if !keys.insert(&field.key) {
+                    return Err(Error::new(ast.span(), "duplicate key"));
+                }
+            }
+        }
+        Repr::Tuple => {
+            if let Some(last_required) = result.iter().rposition(|field| !field.optional) {
+                if let Some((_, ast)) = result[..last_required]
+                    .iter()
+                    .zip(fields)
+                    .find(|(field, _)| field.optional)
+                {
+                    return Err(Error::new(
+                        ast.span(),
+                        "only trailing fields may be optional in the `tuple` representation",
+                    ));
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(result)
+}
+
+impl<'a> Variant<'a> {
+    fn from_ast(variant: &'a syn::Variant, repr: &Repr) -> Result<Self, Error> {
+        let mut key = variant.ident.to_string();
+        let mut kind = None;
+        for attr in ipld_attrs(&variant.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    key = value.value();
+                    Ok(())
+                } else if meta.path.is_ident("kind") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    kind = Some(Kind::from_name(&value.value()).ok_or_else(|| {
+                        Error::new(
+                            value.span(),
+                            "unknown kind, expected `null`, `bool`, `int`, `float`, `string`, \
+                             `bytes`, `list`, `map` or `link`",
+                        )
+                    })?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown attribute, expected `rename` or `kind`"))
+                }
+            })?;
+        }
+
+        let ty = match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => Some(&fields.unnamed[0].ty),
+            Fields::Unit => None,
+            _ => {
+                return Err(Error::new(
+                    variant.span(),
+                    "only newtype variants and unit variants of kind null are supported",
+                ))
+            }
+        };
+        match repr {
+            Repr::Keyed if ty.is_none() => {
+                return Err(Error::new(
+                    variant.span(),
+                    "the `keyed` representation only supports newtype variants",
+                ))
+            }
+            Repr::Int if ty.is_some() => {
+                return Err(Error::new(
+                    variant.span(),
+                    "the `int` representation only supports unit variants",
+                ))
+            }
+            Repr::Kinded => match kind {
+                None => {
+                    return Err(Error::new(
+                        variant.span(),
+                        "variants of the `kinded` representation need a `kind` attribute",
+                    ))
+                }
+                Some(Kind::Null) if ty.is_some() => {
+                    return Err(Error::new(
+                        variant.span(),
+                        "variants of kind null must be unit variants",
+                    ))
+                }
+                Some(kind) if kind != Kind::Null && ty.is_none() => {
+                    return Err(Error::new(
+                        variant.span(),
+                        "only variants of kind null may be unit variants",
+                    ))
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+
+        Ok(Variant {
+            ident: &variant.ident,
+            ty,
+            key,
+            kind,
+        })
+    }
+}
+
+fn check_variants(variants: &[Variant], repr: &Repr, input: &DeriveInput) -> Result<(), Error> {
+    if variants.is_empty() {
+        return Err(Error::new(
+            input.ident.span(),
+            "enums without variants are not supported",
+        ));
+    }
+    let mut seen = HashSet::new();
+    for variant in variants {
+        let unique = match repr {
+            Repr::Kinded => seen.insert(variant.kind.map(Kind::name).unwrap_or_default()),
+            _ => seen.insert(variant.key.as_str()),
+        };
+        if !unique {
+            return Err(Error::new(
+                variant.ident.span(),
+                match repr {
+                    Repr::Kinded => "duplicate kind",
+                    _ => "duplicate key",
+                },
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn ipld_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("ipld"))
+}
+
+/// Returns whether the type is an `Option`, judged by its name.
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// Sorts map keys like DAG-CBOR, shorter keys first, then bytewise.
+pub fn canonical_order(a: &str, b: &str) -> std::cmp::Ordering {
+    (a.len(), a).cmp(&(b.len(), b))
+}
@@ -0,0 +1,362 @@
+//! Generation of `serde::Deserialize` implementations.
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{parse_quote, GenericParam, Generics, Ident, Lifetime, LifetimeParam};
+
+use crate::attr::{Body, Container, Field, Kind, Repr, Variant};
+
+pub fn expand(container: &Container) -> TokenStream {
+    let ident = container.ident;
+    let (_, ty_generics, _) = container.generics.split_for_impl();
+    let mut generics = container.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(serde::Deserialize<'de>));
+    }
+    let lifetime = LifetimeParam::new(Lifetime::new("'de", Span::call_site()));
+    generics.params.insert(0, GenericParam::Lifetime(lifetime));
+
+    let body = match (&container.body, &container.repr) {
+        (Body::Struct(fields), Repr::StringJoin(join)) => string_join(ident, fields, join),
+        (body, repr) => {
+            let (expecting, method, methods) = match (body, repr) {
+                (Body::Struct(fields), Repr::Map) => map(ident, fields),
+                (Body::Struct(fields), Repr::Tuple) => tuple(ident, fields),
+                (Body::Enum(variants), Repr::Keyed) => keyed(ident, variants),
+                (Body::Enum(variants), Repr::Kinded) => kinded(ident, variants),
+                (Body::Enum(variants), Repr::Int) => int(ident, variants),
+                _ => unreachable!("the representation is checked to match the type"),
+            };
+            visitor(container, &generics, &expecting, &method, methods)
+        }
+    };
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    quote! {
+        #[allow(unused_imports)]
+        const _: () = {
+            use ::core::option::Option::{None, Some};
+            use ::core::result::Result::{self, Err, Ok};
+            use ::serde_ipld_dagcbor::__private::serde;
+
+            impl #impl_generics serde::Deserialize<'de> for #ident #ty_generics #where_clause {
+                fn deserialize<__D>(__deserializer: __D) -> Result<Self, __D::Error>
+                where
+                    __D: serde::Deserializer<'de>,
+                {
+                    #body
+                }
+            }
+        };
+    }
+}
+
+/// Returns a visitor for the type that is driven by the given deserializer method.
+fn visitor(
+    container: &Container,
+    generics: &Generics,
+    expecting: &str,
+    method: &Ident,
+    methods: TokenStream,
+) -> TokenStream {
+    let ident = container.ident;
+    let (_, ty_generics, _) = container.generics.split_for_impl();
+    let (impl_generics, visitor_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        struct __Visitor #impl_generics #where_clause {
+            marker: ::core::marker::PhantomData<fn() -> (#ident #ty_generics, &'de ())>,
+        }
+
+        impl #impl_generics serde::de::Visitor<'de> for __Visitor #visitor_generics #where_clause {
+            type Value = #ident #ty_generics;
+
+            fn expecting(&self, __formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                __formatter.write_str(#expecting)
+            }
+
+            #methods
+        }
+
+        __deserializer.#method(__Visitor {
+            marker: ::core::marker::PhantomData,
+        })
+    }
+}
+
+/// Returns the struct expression built from the variables `__field0`, `__field1` etc.
+fn construct(ident: &Ident, fields: &[Field]) -> TokenStream {
+    let members = fields.iter().map(|field| &field.member);
+    let values = (0..fields.len()).map(|index| format_ident!("__field{}", index));
+    quote!(#ident { #(#members: #values),* })
+}
+
+fn map(ident: &Ident, fields: &[Field]) -> (String, Ident, TokenStream) {
+    let variables: Vec<_> = (0..fields.len())
+        .map(|index| format_ident!("__field{}", index))
+        .collect();
+    let keys: Vec<_> = fields.iter().map(|field| &field.key).collect();
+    let arms = variables.iter().zip(&keys).map(|(variable, key)| {
+        quote! {
+            #key => {
+                if #variable.is_some() {
+                    return Err(serde::de::Error::duplicate_field(#key));
+                }
+                #variable = Some(__map.next_value()?);
+            }
+        }
+    });
+    let checks = fields
+        .iter()
+        .zip(&variables)
+        .filter(|(field, _)| !field.optional)
+        .map(|(field, variable)| {
+            let key = &field.key;
+            quote! {
+                let #variable = #variable.ok_or_else(|| serde::de::Error::missing_field(#key))?;
+            }
+        });
+    let construct = construct(ident, fields);
+
+    let methods = quote! {
+        fn visit_map<__A>(self, mut __map: __A) -> Result<Self::Value, __A::Error>
+        where
+            __A: serde::de::MapAccess<'de>,
+        {
+            const __FIELDS: &[&str] = &[#(#keys),*];
+            #(let mut #variables = None;)*
+            while let Some(__key) = __map.next_key::<::serde_ipld_dagcbor::__private::String>()? {
+                match __key.as_str() {
+                    #(#arms)*
+                    _ => return Err(serde::de::Error::unknown_field(&__key, __FIELDS)),
+                }
+            }
+            #(#checks)*
+            Ok(#construct)
+        }
+    };
+    (
+        format!("struct {}", ident),
+        format_ident!("deserialize_map"),
+        methods,
+    )
+}
+
+fn tuple(ident: &Ident, fields: &[Field]) -> (String, Ident, TokenStream) {
+    let required = fields.iter().filter(|field| !field.optional).count();
+    let elements = fields.iter().enumerate().map(|(index, field)| {
+        let variable = format_ident!("__field{}", index);
+        if field.optional {
+            quote!(let #variable = __seq.next_element()?;)
+        } else {
+            quote! {
+                let #variable = __seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(#index, &self))?;
+            }
+        }
+    });
+    let extra = fields.len() + 1;
+    let construct = construct(ident, fields);
+
+    let methods = quote! {
+        fn visit_seq<__A>(self, mut __seq: __A) -> Result<Self::Value, __A::Error>
+        where
+            __A: serde::de::SeqAccess<'de>,
+        {
+            #(#elements)*
+            if __seq.next_element::<serde::de::IgnoredAny>()?.is_some() {
+                return Err(serde::de::Error::invalid_length(#extra, &self));
+            }
+            Ok(#construct)
+        }
+    };
+    let expecting = if required == fields.len() {
+        format!("a list of {} elements", required)
+    } else {
+        format!("a list of {} to {} elements", required, fields.len())
+    };
+    (expecting, format_ident!("deserialize_seq"), methods)
+}
+
+fn string_join(ident: &Ident, fields: &[Field], join: &str) -> TokenStream {
+    let len = fields.len();
+    let expecting = format!("{} values joined by `{}`", len, join);
+    let parts = (0..len).map(|index| {
+        let variable = format_ident!("__field{}", index);
+        quote! {
+            let #variable = __parts
+                .next()
+                .unwrap_or_default()
+                .parse()
+                .map_err(<__D::Error as serde::de::Error>::custom)?;
+        }
+    });
+    let construct = construct(ident, fields);
+    quote! {
+        let __string: ::serde_ipld_dagcbor::__private::String =
+            serde::Deserialize::deserialize(__deserializer)?;
+        let __count = __string.split(#join).count();
+        if __count != #len {
+            return Err(serde::de::Error::invalid_length(__count, &#expecting));
+        }
+        let mut __parts = __string.split(#join);
+        #(#parts)*
+        Ok(#construct)
+    }
+}
+
+fn keyed(ident: &Ident, variants: &[Variant]) -> (String, Ident, TokenStream) {
+    let keys: Vec<_> = variants.iter().map(|variant| &variant.key).collect();
+    let arms = variants.iter().map(|variant| {
+        let variant_ident = variant.ident;
+        let key = &variant.key;
+        quote!(#key => #ident::#variant_ident(__map.next_value()?),)
+    });
+
+    let methods = quote! {
+        fn visit_map<__A>(self, mut __map: __A) -> Result<Self::Value, __A::Error>
+        where
+            __A: serde::de::MapAccess<'de>,
+        {
+            const __VARIANTS: &[&str] = &[#(#keys),*];
+            let __key = match __map.next_key::<::serde_ipld_dagcbor::__private::String>()? {
+                Some(__key) => __key,
+                None => return Err(serde::de::Error::invalid_length(0, &self)),
+            };
+            let __value = match __key.as_str() {
+                #(#arms)*
+                _ => return Err(serde::de::Error::unknown_variant(&__key, __VARIANTS)),
+            };
+            if __map.next_key::<serde::de::IgnoredAny>()?.is_some() {
+                return Err(serde::de::Error::invalid_length(2, &self));
+            }
+            Ok(__value)
+        }
+    };
+    (
+        format!("a map with a single entry of union {}", ident),
+        format_ident!("deserialize_map"),
+        methods,
+    )
+}
+
+fn kinded(ident: &Ident, variants: &[Variant]) -> (String, Ident, TokenStream) {
+    let methods = variants.iter().map(|variant| {
+        let variant_ident = variant.ident;
+        // Turns the visited value into a deserializer for the type of the variant.
+        let scalar = |method: &str, ty: TokenStream, deserializer: TokenStream| {
+            let method = format_ident!("{}", method);
+            quote! {
+                fn #method<__E>(self, __value: #ty) -> Result<Self::Value, __E>
+                where
+                    __E: serde::de::Error,
+                {
+                    serde::Deserialize::deserialize(#deserializer).map(#ident::#variant_ident)
+                }
+            }
+        };
+        let into = quote!(serde::de::IntoDeserializer::into_deserializer(__value));
+        match variant.kind.expect("variants of kinded unions have a kind") {
+            Kind::Null => quote! {
+                fn visit_unit<__E>(self) -> Result<Self::Value, __E>
+                where
+                    __E: serde::de::Error,
+                {
+                    Ok(#ident::#variant_ident)
+                }
+
+                fn visit_none<__E>(self) -> Result<Self::Value, __E>
+                where
+                    __E: serde::de::Error,
+                {
+                    Ok(#ident::#variant_ident)
+                }
+            },
+            Kind::Bool => scalar("visit_bool", quote!(bool), into),
+            Kind::Int => {
+                let signed = scalar("visit_i64", quote!(i64), into.clone());
+                let unsigned = scalar("visit_u64", quote!(u64), into);
+                quote!(#signed #unsigned)
+            }
+            Kind::Float => scalar("visit_f64", quote!(f64), into),
+            Kind::String => scalar("visit_str", quote!(&str), into),
+            Kind::Bytes => scalar(
+                "visit_bytes",
+                quote!(&[u8]),
+                quote!(serde::de::value::BytesDeserializer::new(__value)),
+            ),
+            Kind::List => quote! {
+                fn visit_seq<__A>(self, __seq: __A) -> Result<Self::Value, __A::Error>
+                where
+                    __A: serde::de::SeqAccess<'de>,
+                {
+                    let __deserializer = serde::de::value::SeqAccessDeserializer::new(__seq);
+                    serde::Deserialize::deserialize(__deserializer).map(#ident::#variant_ident)
+                }
+            },
+            Kind::Map => quote! {
+                fn visit_map<__A>(self, __map: __A) -> Result<Self::Value, __A::Error>
+                where
+                    __A: serde::de::MapAccess<'de>,
+                {
+                    let __deserializer = serde::de::value::MapAccessDeserializer::new(__map);
+                    serde::Deserialize::deserialize(__deserializer).map(#ident::#variant_ident)
+                }
+            },
+            // Links are passed as newtype struct that contains the CID.
+            Kind::Link => quote! {
+                fn visit_newtype_struct<__D>(self, __deserializer: __D) -> Result<Self::Value, __D::Error>
+                where
+                    __D: serde::Deserializer<'de>,
+                {
+                    serde::Deserialize::deserialize(__deserializer).map(#ident::#variant_ident)
+                }
+            },
+        }
+    });
+    let kinds: Vec<_> = variants
+        .iter()
+        .filter_map(|variant| variant.kind.map(Kind::name))
+        .collect();
+    (
+        format!("a value of kind {}", kinds.join(", ")),
+        format_ident!("deserialize_any"),
+        quote!(#(#methods)*),
+    )
+}
+
+fn int(ident: &Ident, variants: &[Variant]) -> (String, Ident, TokenStream) {
+    let idents: Vec<_> = variants.iter().map(|variant| variant.ident).collect();
+    let unknown = format!("unknown discriminant {{}} of enum {}", ident);
+    let methods = quote! {
+        fn visit_i64<__E>(self, __value: i64) -> Result<Self::Value, __E>
+        where
+            __E: serde::de::Error,
+        {
+            self.visit_i128(i128::from(__value))
+        }
+
+        fn visit_u64<__E>(self, __value: u64) -> Result<Self::Value, __E>
+        where
+            __E: serde::de::Error,
+        {
+            self.visit_i128(i128::from(__value))
+        }
+
+        fn visit_i128<__E>(self, __value: i128) -> Result<Self::Value, __E>
+        where
+            __E: serde::de::Error,
+        {
+            #(
+                if __value == #ident::#idents as i128 {
+                    return Ok(#ident::#idents);
+                }
+            )*
+            Err(serde::de::Error::custom(format_args!(#unknown, __value)))
+        }
+    };
+    (
+        format!("an integer of enum {}", ident),
+        format_ident!("deserialize_any"),
+        methods,
+    )
+}
@@ -0,0 +1,95 @@
+//! Derive macros for IPLD representation strategies.
+//!
+//! `#[derive(DagCborSerialize, DagCborDeserialize)]` implements `serde::Serialize` and
+//! `serde::Deserialize` for types whose DAG-CBOR encoding follows one of the representation
+//! strategies of [IPLD Schemas], many of which can't be expressed with Serde's attributes. The
+//! strategy is selected with `#[ipld(repr = "...")]`:
+//!
+//!  - `map` (default for structs): a map from the field names to their values, in canonical key
+//!    order
+//!  - `tuple`: a list of the field values in declaration order
+//!  - `stringjoin`: the field values formatted with `Display` and joined by the separator given
+//!    with `#[ipld(join = "...")]`, they are parsed back with `FromStr`
+//!  - `keyed` (default for enums): a map with a single entry from the variant key to its value
+//!  - `kinded`: the value of the variant itself, the variant is determined by its kind on
+//!    decoding
+//!  - `int`: the discriminant of a unit variant as integer, e.g. `E::A` of
+//!    `#[repr(u16)] enum E { A = 2 }` as `2`
+//!
+//! Fields support `#[ipld(rename = "...")]` to change their map key and `#[ipld(optional)]` for
+//! `Option` fields that are omitted if they are `None`. In the `tuple` representation only
+//! trailing fields may be optional. Variants of keyed unions support `#[ipld(rename = "...")]`,
+//! variants of kinded unions declare their kind with `#[ipld(kind = "...")]`, which is one of
+//! `null`, `bool`, `int`, `float`, `string`, `bytes`, `list`, `map` or `link`. Variants of kind
+//! `null` are unit variants, all others are newtype variants.
+//!
+//! The generated code is meant to be used with the serializer and deserializer of
+//! `serde_ipld_dagcbor`, where the macros are available with the `derive` feature.
+//!
+//! [IPLD Schemas]: https://ipld.io/docs/schemas/
+//!
+//! # Examples
+//!
+//! ```
+//! use serde_ipld_dagcbor::{from_slice, to_vec};
+//! use serde_ipld_dagcbor_derive::{DagCborDeserialize, DagCborSerialize};
+//!
+//! #[derive(Debug, PartialEq, DagCborSerialize, DagCborDeserialize)]
+//! #[ipld(repr = "tuple")]
+//! struct Message {
+//!     nonce: u64,
+//!     method: String,
+//!     #[ipld(optional)]
+//!     params: Option<Vec<u8>>,
+//! }
+//!
+//! #[derive(Debug, PartialEq, DagCborSerialize, DagCborDeserialize)]
+//! #[ipld(repr = "kinded")]
+//! enum Value {
+//!     #[ipld(kind = "null")]
+//!     Nothing,
+//!     #[ipld(kind = "int")]
+//!     Number(i64),
+//!     #[ipld(kind = "list")]
+//!     Messages(Vec<Message>),
+//! }
+//!
+//! let message = Message {
+//!     nonce: 1,
+//!     method: "send".to_string(),
+//!     params: None,
+//! };
+//! let encoded = to_vec(&message).unwrap();
+//! assert_eq!(encoded, b"\x82\x01\x64send");
+//! assert_eq!(from_slice::<Message>(&encoded).unwrap(), message);
+//!
+//! let value = Value::Messages(vec![message]);
+//! assert_eq!(from_slice::<Value>(&to_vec(&value).unwrap()).unwrap(), value);
+//! assert_eq!(from_slice::<Value>(b"\xf6").unwrap(), Value::Nothing);
+//! ```
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod attr;
+mod de;
+mod ser;
+
+/// Derives `serde::Serialize` according to the `#[ipld(...)]` attributes.
+#[proc_macro_derive(DagCborSerialize, attributes(ipld))]
+pub fn derive_serialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    attr::Container::from_ast(&input)
+        .map(|container| ser::expand(&container))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `serde::Deserialize` according to the `#[ipld(...)]` attributes.
+#[proc_macro_derive(DagCborDeserialize, attributes(ipld))]
+pub fn derive_deserialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    attr::Container::from_ast(&input)
+        .map(|container| de::expand(&container))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,184 @@
+//! Generation of `serde::Serialize` implementations.
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse_quote;
+
+use crate::attr::{canonical_order, Body, Container, Field, Repr, Variant};
+
+pub fn expand(container: &Container) -> TokenStream {
+    let ident = container.ident;
+    let mut generics = container.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(serde::Serialize));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match (&container.body, &container.repr) {
+        (Body::Struct(fields), Repr::Map) => map(fields),
+        (Body::Struct(fields), Repr::Tuple) => tuple(fields),
+        (Body::Struct(fields), Repr::StringJoin(join)) => string_join(fields, join),
+        (Body::Enum(variants), Repr::Keyed) => keyed(variants),
+        (Body::Enum(variants), Repr::Kinded) => kinded(variants),
+        (Body::Enum(variants), Repr::Int) => int(variants),
+        _ => unreachable!("the representation is checked to match the type"),
+    };
+
+    quote! {
+        #[allow(unused_imports)]
+        const _: () = {
+            use ::core::option::Option::{None, Some};
+            use ::core::result::Result::{self, Err, Ok};
+            use ::serde_ipld_dagcbor::__private::serde;
+
+            impl #impl_generics serde::Serialize for #ident #ty_generics #where_clause {
+                fn serialize<__S>(&self, __serializer: __S) -> Result<__S::Ok, __S::Error>
+                where
+                    __S: serde::Serializer,
+                {
+                    #body
+                }
+            }
+        };
+    }
+}
+
+fn map(fields: &[Field]) -> TokenStream {
+    let required = fields.iter().filter(|field| !field.optional).count();
+    let optional = fields
+        .iter()
+        .filter(|field| field.optional)
+        .map(|field| &field.member);
+
+    let mut sorted: Vec<_> = fields.iter().collect();
+    sorted.sort_by(|a, b| canonical_order(&a.key, &b.key));
+    let entries = sorted.iter().map(|field| {
+        let member = &field.member;
+        let key = &field.key;
+        if field.optional {
+            quote! {
+                if let Some(__value) = &self.#member {
+                    __map.serialize_entry(#key, __value)?;
+                }
+            }
+        } else {
+            quote!(__map.serialize_entry(#key, &self.#member)?;)
+        }
+    });
+
+    quote! {
+        use serde::ser::SerializeMap;
+        let __len = #required #(+ usize::from(self.#optional.is_some()))*;
+        let mut __map = __serializer.serialize_map(Some(__len))?;
+        #(#entries)*
+        __map.end()
+    }
+}
+
+fn tuple(fields: &[Field]) -> TokenStream {
+    let required = fields.iter().filter(|field| !field.optional).count();
+    // The length is determined by the last optional field that is set.
+    let lengths = fields
+        .iter()
+        .enumerate()
+        .skip(required)
+        .map(|(index, field)| {
+            let member = &field.member;
+            let len = index + 1;
+            quote!(if self.#member.is_some() { __len = #len; })
+        });
+    let elements = fields.iter().enumerate().map(|(index, field)| {
+        let member = &field.member;
+        if field.optional {
+            quote! {
+                if __len > #index {
+                    match &self.#member {
+                        Some(__value) => __seq.serialize_element(__value)?,
+                        None => {
+                            return Err(serde::ser::Error::custom(
+                                "only trailing optional fields may be absent",
+                            ))
+                        }
+                    }
+                }
+            }
+        } else {
+            quote!(__seq.serialize_element(&self.#member)?;)
+        }
+    });
+
+    let len = if required < fields.len() {
+        quote! {
+            let mut __len = #required;
+            #(#lengths)*
+        }
+    } else {
+        quote!(let __len = #required;)
+    };
+    quote! {
+        use serde::ser::SerializeSeq;
+        #len
+        let mut __seq = __serializer.serialize_seq(Some(__len))?;
+        #(#elements)*
+        __seq.end()
+    }
+}
+
+fn string_join(fields: &[Field], join: &str) -> TokenStream {
+    let members = fields.iter().map(|field| &field.member);
+    quote! {
+        let __parts = [
+            #(::serde_ipld_dagcbor::__private::ToString::to_string(&self.#members)),*
+        ];
+        __serializer.serialize_str(&__parts.join(#join))
+    }
+}
+
+fn keyed(variants: &[Variant]) -> TokenStream {
+    let arms = variants.iter().map(|variant| {
+        let ident = variant.ident;
+        let key = &variant.key;
+        quote! {
+            Self::#ident(__value) => __map.serialize_entry(#key, __value)?,
+        }
+    });
+    quote! {
+        use serde::ser::SerializeMap;
+        let mut __map = __serializer.serialize_map(Some(1))?;
+        match self {
+            #(#arms)*
+        }
+        __map.end()
+    }
+}
+
+fn kinded(variants: &[Variant]) -> TokenStream {
+    let arms = variants.iter().map(|variant| {
+        let ident = variant.ident;
+        match variant.ty {
+            Some(_) => quote! {
+                Self::#ident(__value) => serde::Serialize::serialize(__value, __serializer),
+            },
+            None => quote! {
+                Self::#ident => __serializer.serialize_unit(),
+            },
+        }
+    });
+    quote! {
+        match self {
+            #(#arms)*
+        }
+    }
+}
+
+fn int(variants: &[Variant]) -> TokenStream {
+    let idents = variants.iter().map(|variant| variant.ident);
+    quote! {
+        let __value = match self {
+            #(Self::#idents => Self::#idents as i128,)*
+        };
+        match <u64 as ::core::convert::TryFrom<i128>>::try_from(__value) {
+            Ok(__value) => __serializer.serialize_u64(__value),
+            Err(_) => __serializer.serialize_i64(__value as i64),
+        }
+    }
+}
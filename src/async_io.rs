@@ -0,0 +1,389 @@
+//! Asynchronous serialization and deserialization.
+//!
+//! DAG-CBOR values are self-delimiting, hence a value can be read from a stream without knowing its
+//! size upfront. The bytes of a single value are buffered incrementally, only reading as much as
+//! the value needs, so that the stream can be used for reading further data afterwards. As every
+//! missing head is read separately, wrapping unbuffered streams into a buffered reader is
+//! recommended.
+//!
+//! The functions are available for [`futures::io`] with the `futures` feature and for
+//! [`tokio::io`] with the `tokio` feature.
+use std::convert::TryFrom;
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use cbor4ii::core::major;
+
+use serde::{de, Serialize};
+
+use crate::cbor4ii_nonpub::marker;
+use crate::error::{DecodeError, EncodeError};
+
+/// Tracks which parts of a single DAG-CBOR value are still missing.
+#[derive(Debug, Default)]
+struct ValueLength {
+    /// Position of the next item within the buffer.
+    pos: usize,
+    /// The number of items the currently open arrays, maps and tags still need. `None` is used for
+    /// indefinite length items.
+    pending: Vec<Option<u64>>,
+}
+
+impl ValueLength {
+    /// Returns how many bytes need to be appended to `buf` before scanning can continue, or
+    /// `None` in case the buffer contains a full value.
+    ///
+    /// The same buffer needs to be passed in on every call, it may only grow in between calls.
+    fn missing(&mut self, buf: &[u8]) -> Result<Option<usize>, DecodeError<io::Error>> {
+        loop {
+            let byte = match buf.get(self.pos) {
+                Some(byte) => *byte,
+                None => return Ok(Some(1)),
+            };
+
+            if byte == marker::BREAK {
+                match self.pending.last() {
+                    Some(None) => {
+                        self.pending.pop();
+                        self.pos += 1;
+                        if self.complete_item() {
+                            return Ok(None);
+                        }
+                        continue;
+                    }
+                    _ => return Err(DecodeError::Unsupported { byte }),
+                }
+            }
+
+            let major = byte >> 5;
+            let argument_len = match byte & 0x1f {
+                0..=23 => 0,
+                24 => 1,
+                25 => 2,
+                26 => 4,
+                27 => 8,
+                // Indefinite length items.
+                0x1f if matches!(
+                    major,
+                    major::BYTES | major::STRING | major::ARRAY | major::MAP
+                ) =>
+                {
+                    self.pending.push(None);
+                    self.pos += 1;
+                    continue;
+                }
+                _ => return Err(DecodeError::Unsupported { byte }),
+            };
+
+            let head_end = self.pos + 1 + argument_len;
+            if buf.len() < head_end {
+                return Ok(Some(head_end - buf.len()));
+            }
+            let argument = if argument_len == 0 {
+                u64::from(byte & 0x1f)
+            } else {
+                buf[self.pos + 1..head_end]
+                    .iter()
+                    .fold(0, |acc, byte| (acc << 8) | u64::from(*byte))
+            };
+
+            match major {
+                major::BYTES | major::STRING => {
+                    let len = usize::try_from(argument).map_err(DecodeError::CastOverflow)?;
+                    let end = head_end.checked_add(len).ok_or(DecodeError::SizeLimit)?;
+                    if buf.len() < end {
+                        return Ok(Some(end - buf.len()));
+                    }
+                    self.pos = end;
+                }
+                major::ARRAY | major::MAP | major::TAG => {
+                    self.pos = head_end;
+                    let items = match major {
+                        major::ARRAY => argument,
+                        major::MAP => argument.checked_mul(2).ok_or(DecodeError::SizeLimit)?,
+                        _ => 1,
+                    };
+                    if items > 0 {
+                        self.pending.push(Some(items));
+                        continue;
+                    }
+                }
+                _ => self.pos = head_end,
+            }
+
+            if self.complete_item() {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Marks an item as complete. Returns `true` if it completes the whole value.
+    fn complete_item(&mut self) -> bool {
+        loop {
+            match self.pending.last_mut() {
+                None => return true,
+                Some(Some(items)) => {
+                    *items -= 1;
+                    if *items == 0 {
+                        self.pending.pop();
+                    } else {
+                        return false;
+                    }
+                }
+                Some(None) => return false,
+            }
+        }
+    }
+}
+
+/// Returns the new size of the buffer if it doesn't exceed the maximum size.
+fn grow(len: usize, missing: usize, max_size: usize) -> Result<usize, DecodeError<io::Error>> {
+    len.checked_add(missing)
+        .filter(|end| *end <= max_size)
+        .ok_or(DecodeError::SizeLimit)
+}
+
+/// Converts an unexpected end of the stream into the corresponding decoding error.
+fn read_error(error: io::Error) -> DecodeError<io::Error> {
+    if error.kind() == io::ErrorKind::UnexpectedEof {
+        DecodeError::Eof
+    } else {
+        DecodeError::Read(error)
+    }
+}
+
+/// The subset of an asynchronous reader that is needed for decoding.
+///
+/// It papers over the differences between the reading traits of the supported runtimes.
+trait PollRead: Unpin {
+    /// Attempts to read into `buf`, returning the number of bytes read.
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>;
+}
+
+/// The subset of an asynchronous writer that is needed for encoding.
+///
+/// It papers over the differences between the writing traits of the supported runtimes.
+trait PollWrite: Unpin {
+    /// Attempts to write `buf`, returning the number of bytes written.
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>>;
+
+    /// Attempts to flush the buffered data to its destination.
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>>;
+}
+
+/// Fills the whole buffer from the reader.
+async fn read_exact<R: PollRead>(reader: &mut R, buf: &mut [u8]) -> io::Result<()> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let read = poll_fn(|cx| Pin::new(&mut *reader).poll_read(cx, &mut buf[filled..])).await?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        filled += read;
+    }
+    Ok(())
+}
+
+/// Writes the whole buffer to the writer.
+async fn write_all<W: PollWrite>(writer: &mut W, buf: &[u8]) -> io::Result<()> {
+    let mut written = 0;
+    while written < buf.len() {
+        let write = poll_fn(|cx| Pin::new(&mut *writer).poll_write(cx, &buf[written..])).await?;
+        if write == 0 {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        written += write;
+    }
+    Ok(())
+}
+
+/// Reads the bytes of a single value from the reader and decodes them.
+async fn from_reader<T, R>(mut reader: R, max_size: usize) -> Result<T, DecodeError<io::Error>>
+where
+    T: de::DeserializeOwned,
+    R: PollRead,
+{
+    let mut buf = Vec::new();
+    let mut length = ValueLength::default();
+    while let Some(missing) = length.missing(&buf)? {
+        let start = buf.len();
+        buf.resize(grow(start, missing, max_size)?, 0);
+        read_exact(&mut reader, &mut buf[start..])
+            .await
+            .map_err(read_error)?;
+    }
+    crate::from_reader(&buf[..])
+}
+
+/// Encodes the value into memory, writes the result to the writer and flushes it.
+async fn to_writer<W, T>(mut writer: W, value: &T) -> Result<(), EncodeError<io::Error>>
+where
+    W: PollWrite,
+    T: Serialize,
+{
+    let mut buf = Vec::new();
+    crate::to_writer(&mut buf, value)?;
+    write_all(&mut writer, &buf).await?;
+    poll_fn(|cx| Pin::new(&mut writer).poll_flush(cx)).await?;
+    Ok(())
+}
+
+/// Support for [`futures::io`].
+#[cfg(feature = "futures")]
+pub mod futures {
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use ::futures::io::{AsyncRead, AsyncWrite};
+    use serde::{de, Serialize};
+
+    use super::{PollRead, PollWrite};
+    use crate::error::{DecodeError, EncodeError};
+
+    /// Adapts the [`futures::io`] traits to the ones used internally.
+    struct Compat<T>(T);
+
+    impl<R: AsyncRead + Unpin> PollRead for Compat<R> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.0).poll_read(cx, buf)
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin> PollWrite for Compat<W> {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.0).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_flush(cx)
+        }
+    }
+
+    /// Decodes a value from DAG-CBOR data in an asynchronous reader.
+    ///
+    /// Only the bytes of a single value are read from the stream. If the value is larger than
+    /// `max_size` bytes, a [`DecodeError::SizeLimit`] is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use serde_ipld_dagcbor::async_io::futures::from_async_reader;
+    /// let v: Vec<u8> = vec![0x66, 0x66, 0x6f, 0x6f, 0x62, 0x61, 0x72];
+    /// let value: String = futures::executor::block_on(from_async_reader(&v[..], 1024)).unwrap();
+    /// assert_eq!(value, "foobar");
+    /// ```
+    pub async fn from_async_reader<T, R>(
+        reader: R,
+        max_size: usize,
+    ) -> Result<T, DecodeError<io::Error>>
+    where
+        T: de::DeserializeOwned,
+        R: AsyncRead + Unpin,
+    {
+        super::from_reader(Compat(reader), max_size).await
+    }
+
+    /// Serializes a value to an asynchronous writer.
+    ///
+    /// The value is encoded into a `Vec` first, which is then copied to the writer. The writer is
+    /// flushed afterwards.
+    pub async fn to_async_writer<W, T>(writer: W, value: &T) -> Result<(), EncodeError<io::Error>>
+    where
+        W: AsyncWrite + Unpin,
+        T: Serialize,
+    {
+        super::to_writer(Compat(writer), value).await
+    }
+}
+
+/// Support for [`tokio::io`].
+#[cfg(feature = "tokio")]
+pub mod tokio {
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use ::tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+    use serde::{de, Serialize};
+
+    use super::{PollRead, PollWrite};
+    use crate::error::{DecodeError, EncodeError};
+
+    /// Adapts the [`tokio::io`] traits to the ones used internally.
+    struct Compat<T>(T);
+
+    impl<R: AsyncRead + Unpin> PollRead for Compat<R> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            let mut buf = ReadBuf::new(buf);
+            match Pin::new(&mut self.0).poll_read(cx, &mut buf) {
+                Poll::Ready(Ok(())) => Poll::Ready(Ok(buf.filled().len())),
+                Poll::Ready(Err(error)) => Poll::Ready(Err(error)),
+                Poll::Pending => Poll::Pending,
+            }
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin> PollWrite for Compat<W> {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.0).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_flush(cx)
+        }
+    }
+
+    /// Decodes a value from DAG-CBOR data in an asynchronous reader.
+    ///
+    /// Only the bytes of a single value are read from the stream. If the value is larger than
+    /// `max_size` bytes, a [`DecodeError::SizeLimit`] is returned.
+    pub async fn from_async_reader<T, R>(
+        reader: R,
+        max_size: usize,
+    ) -> Result<T, DecodeError<io::Error>>
+    where
+        T: de::DeserializeOwned,
+        R: AsyncRead + Unpin,
+    {
+        super::from_reader(Compat(reader), max_size).await
+    }
+
+    /// Serializes a value to an asynchronous writer.
+    ///
+    /// The value is encoded into a `Vec` first, which is then copied to the writer. The writer is
+    /// flushed afterwards.
+    pub async fn to_async_writer<W, T>(writer: W, value: &T) -> Result<(), EncodeError<io::Error>>
+    where
+        W: AsyncWrite + Unpin,
+        T: Serialize,
+    {
+        super::to_writer(Compat(writer), value).await
+    }
+}
@@ -0,0 +1,342 @@
+//! Command-line tool for inspecting and converting DAG-CBOR data.
+//!
+//! Run `dagcbor help` for the available commands.
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read, Seek, Write};
+use std::process;
+
+use cid::Cid;
+use multihash::{Code, MultihashDigest};
+use serde_ipld_dagcbor::car::{CarReader, CarV2Writer, CarWriter, IndexCodec, IndexedCarReader};
+use serde_ipld_dagcbor::diag::{to_diag_with_options, DiagOptions};
+use serde_ipld_dagcbor::explain::explain;
+use serde_ipld_dagcbor::transcode::{transcode_from_dag_json, transcode_to_dag_json};
+use serde_ipld_dagcbor::{block, CarError, DAG_CBOR_CODEC};
+
+const USAGE: &str = "\
+Usage: dagcbor <command> [options] [file]
+
+Reads from the given file or from stdin if it's omitted or `-`.
+
+Commands:
+  inspect             Print an annotated hex dump
+  diag [--cid-links]  Print CBOR diagnostic notation, optionally with links as CIDs
+  to-json             Convert DAG-CBOR to DAG-JSON
+  from-json           Convert DAG-JSON to DAG-CBOR
+  validate            Check the data strictly and list violations with their byte offset
+  cid [--hash <code>] Print the CID of the data, the hash is one of sha2-256 (default),
+                      sha2-512, blake2b-256 or blake2b-512
+  links               List the CIDs the data links to
+  car <command>       Work with CAR files, see below
+  help                Print this message
+
+CAR commands:
+  car ls                List the CIDs and sizes of the blocks
+  car extract <cid>     Print the data of a block
+  car create [--root <file>]... [--hash <code>] [--v2] [file]...
+                        Create a CAR file from DAG-CBOR encoded blocks, the blocks given with
+                        `--root` are the roots. With `--v2` a CARv2 file with an index is created
+  car verify            Check that all blocks match their CID and that the roots are contained
+  car index [--codec <codec>]
+                        Convert to a CARv2 file with an index, the codec is one of
+                        multihash-index-sorted (default) or index-sorted
+";
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// The parsed command-line arguments of a command.
+struct Args {
+    /// Options like `--cid-links` or `--hash sha2-256`.
+    options: Vec<(String, Option<String>)>,
+    positional: Vec<String>,
+}
+
+impl Args {
+    /// Parses the arguments, `flags` are options without and `valued` options with a value.
+    fn parse(args: &[String], flags: &[&str], valued: &[&str]) -> Result<Self> {
+        let mut options = Vec::new();
+        let mut positional = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if flags.contains(&name) => options.push((name.to_string(), None)),
+                Some(name) if valued.contains(&name) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("missing value for option `{}`", arg))?;
+                    options.push((name.to_string(), Some(value.clone())));
+                }
+                Some(_) => return Err(format!("unknown option `{}`", arg).into()),
+                None => positional.push(arg.clone()),
+            }
+        }
+        Ok(Args {
+            options,
+            positional,
+        })
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    fn value<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        self.values(name).last()
+    }
+
+    /// Returns the values of an option that may be given several times.
+    fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.options
+            .iter()
+            .filter(move |(option, _)| option == name)
+            .filter_map(|(_, value)| value.as_deref())
+    }
+
+    /// Returns the input file, `None` means stdin.
+    fn input(&self) -> Result<Option<&str>> {
+        match self.positional.as_slice() {
+            [] => Ok(None),
+            [path] if path == "-" => Ok(None),
+            [path] => Ok(Some(path)),
+            _ => Err("too many arguments".into()),
+        }
+    }
+
+    fn reader(&self) -> Result<Box<dyn BufRead>> {
+        Ok(match self.input()? {
+            Some(path) => Box::new(BufReader::new(File::open(path)?)),
+            None => Box::new(BufReader::new(io::stdin())),
+        })
+    }
+
+    fn read(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.reader()?.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    /// Returns a seekable reader, stdin is read into memory.
+    fn seekable_reader(&self) -> Result<Box<dyn ReadSeek>> {
+        Ok(match self.input()? {
+            Some(path) => Box::new(BufReader::new(File::open(path)?)),
+            None => Box::new(Cursor::new(self.read()?)),
+        })
+    }
+}
+
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+fn parse_index_codec(name: &str) -> Result<IndexCodec> {
+    match name {
+        "index-sorted" => Ok(IndexCodec::IndexSorted),
+        "multihash-index-sorted" => Ok(IndexCodec::MultihashIndexSorted),
+        _ => Err(format!("unsupported index codec `{}`", name).into()),
+    }
+}
+
+fn parse_hash(name: &str) -> Result<Code> {
+    match name {
+        "sha2-256" => Ok(Code::Sha2_256),
+        "sha2-512" => Ok(Code::Sha2_512),
+        "blake2b-256" => Ok(Code::Blake2b256),
+        "blake2b-512" => Ok(Code::Blake2b512),
+        _ => Err(format!("unsupported hash `{}`", name).into()),
+    }
+}
+
+/// Runs a command. Returns false if the data is invalid.
+fn run(command: &str, args: &[String]) -> Result<bool> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match command {
+        "inspect" => {
+            let args = Args::parse(args, &[], &[])?;
+            let explanation = explain(&args.read()?);
+            write!(out, "{}", explanation)?;
+            Ok(explanation.error.is_none())
+        }
+        "diag" => {
+            let args = Args::parse(args, &["cid-links"], &[])?;
+            let options = DiagOptions {
+                cid_links: args.flag("cid-links"),
+            };
+            writeln!(out, "{}", to_diag_with_options(&args.read()?, &options)?)?;
+            Ok(true)
+        }
+        "to-json" => {
+            let args = Args::parse(args, &[], &[])?;
+            transcode_to_dag_json(&args.read()?, &mut out)?;
+            writeln!(out)?;
+            Ok(true)
+        }
+        "from-json" => {
+            let args = Args::parse(args, &[], &[])?;
+            transcode_from_dag_json(&args.read()?, &mut out)?;
+            Ok(true)
+        }
+        "validate" => {
+            let args = Args::parse(args, &[], &[])?;
+            let explanation = explain(&args.read()?);
+            for (offset, violation) in explanation.violations() {
+                writeln!(out, "{}: {}", offset, violation)?;
+            }
+            if let Some((offset, error)) = &explanation.error {
+                writeln!(out, "{}: {}", offset, error)?;
+            }
+            let valid = explanation.is_valid();
+            if valid {
+                writeln!(out, "valid")?;
+            }
+            Ok(valid)
+        }
+        "cid" => {
+            let args = Args::parse(args, &[], &["hash"])?;
+            let hash = parse_hash(args.value("hash").unwrap_or("sha2-256"))?;
+            let cid = Cid::new_v1(DAG_CBOR_CODEC, hash.digest(&args.read()?));
+            writeln!(out, "{}", cid)?;
+            Ok(true)
+        }
+        "links" => {
+            let args = Args::parse(args, &[], &[])?;
+            for cid in block::links(&args.read()?)? {
+                writeln!(out, "{}", cid)?;
+            }
+            Ok(true)
+        }
+        "car" => match args.split_first() {
+            Some((command, args)) => car(command, args, &mut out),
+            None => Err("missing CAR command, see `dagcbor help`".into()),
+        },
+        "help" | "--help" | "-h" => {
+            write!(out, "{}", USAGE)?;
+            Ok(true)
+        }
+        _ => Err(format!("unknown command `{}`, see `dagcbor help`", command).into()),
+    }
+}
+
+/// Runs a CAR command. Returns false if the file is invalid.
+fn car(command: &str, args: &[String], out: &mut impl Write) -> Result<bool> {
+    match command {
+        "ls" => {
+            let args = Args::parse(args, &[], &[])?;
+            for block in CarReader::new(args.reader()?)? {
+                let (cid, data) = block?;
+                writeln!(out, "{} {}", cid, data.len())?;
+            }
+            Ok(true)
+        }
+        "extract" => {
+            let mut args = Args::parse(args, &[], &[])?;
+            if args.positional.is_empty() {
+                return Err("missing CID".into());
+            }
+            let cid = Cid::try_from(args.positional.remove(0).as_str())?;
+            let mut reader = IndexedCarReader::new(args.seekable_reader()?)?;
+            let data = reader
+                .get(&cid)?
+                .ok_or_else(|| format!("block `{}` not found", cid))?;
+            out.write_all(&data)?;
+            Ok(true)
+        }
+        "create" => {
+            let args = Args::parse(args, &["v2"], &["root", "hash"])?;
+            let hash = parse_hash(args.value("hash").unwrap_or("sha2-256"))?;
+            let mut roots = Vec::new();
+            let mut blocks = Vec::new();
+            let root_count = args.values("root").count();
+            let paths = args
+                .values("root")
+                .chain(args.positional.iter().map(String::as_str));
+            for (ii, path) in paths.enumerate() {
+                let data = std::fs::read(path).map_err(|error| format!("{}: {}", path, error))?;
+                let cid = Cid::new_v1(DAG_CBOR_CODEC, hash.digest(&data));
+                if ii < root_count {
+                    roots.push(cid);
+                }
+                blocks.push((cid, data));
+            }
+            if args.flag("v2") {
+                let mut writer = CarV2Writer::new(
+                    Cursor::new(Vec::new()),
+                    roots,
+                    IndexCodec::MultihashIndexSorted,
+                )?;
+                for (cid, data) in &blocks {
+                    writer.write(cid, data)?;
+                }
+                out.write_all(&writer.finish()?.into_inner())?;
+            } else {
+                let mut writer = CarWriter::new(out, roots)?;
+                for (cid, data) in &blocks {
+                    writer.write(cid, data)?;
+                }
+            }
+            Ok(true)
+        }
+        "verify" => {
+            let args = Args::parse(args, &[], &[])?;
+            let mut reader = CarReader::new(args.reader()?)?;
+            reader.set_verify(true);
+            let mut roots = reader.header().roots.clone();
+            let mut count = 0;
+            loop {
+                match reader.next_block() {
+                    Ok(Some((cid, _))) => {
+                        roots.retain(|root| *root != cid);
+                        count += 1;
+                    }
+                    Ok(None) => break,
+                    Err(CarError::Verify(cid, error)) => {
+                        writeln!(out, "{}: {}", cid, error)?;
+                        return Ok(false);
+                    }
+                    Err(error) => return Err(error.into()),
+                }
+            }
+            for root in &roots {
+                writeln!(out, "{}: root not found", root)?;
+            }
+            if roots.is_empty() {
+                writeln!(out, "{} blocks verified", count)?;
+            }
+            Ok(roots.is_empty())
+        }
+        "index" => {
+            let args = Args::parse(args, &[], &["codec"])?;
+            let codec = parse_index_codec(args.value("codec").unwrap_or("multihash-index-sorted"))?;
+            let mut reader = CarReader::new(args.reader()?)?;
+            let roots = reader.header().roots.clone();
+            let mut writer = CarV2Writer::new(Cursor::new(Vec::new()), roots, codec)?;
+            while let Some((cid, data)) = reader.next_block()? {
+                writer.write(&cid, &data)?;
+            }
+            out.write_all(&writer.finish()?.into_inner())?;
+            Ok(true)
+        }
+        _ => Err(format!("unknown CAR command `{}`, see `dagcbor help`", command).into()),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, args) = match args.split_first() {
+        Some((command, args)) => (command.as_str(), args),
+        None => {
+            eprint!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    match run(command, args) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(error) => {
+            eprintln!("error: {}", error);
+            process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! DAG-CBOR encoded data together with its CID.
+#[cfg(not(feature = "std"))]
+use alloc::borrow::Cow;
+#[cfg(all(not(feature = "std"), feature = "hash"))]
+use alloc::collections::TryReserveError;
+#[cfg(not(feature = "std"))]
+use alloc::format;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::convert::Infallible;
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+#[cfg(all(feature = "std", feature = "hash"))]
+use std::collections::TryReserveError;
+
+use core::convert::TryFrom;
+
+use cbor4ii::core::dec::{self, Decode};
+use cbor4ii::core::utils::SliceReader;
+use cbor4ii::core::{major, types};
+use cid::Cid;
+#[cfg(feature = "hash")]
+use multihash::{Blake2b256, Blake2b512, Code, MultihashDigest, Sha2_256, Sha2_512};
+use serde::de;
+#[cfg(feature = "hash")]
+use serde::Serialize;
+
+use crate::cbor4ii_nonpub::{marker, peek_one, pull_one};
+use crate::error::DecodeError;
+#[cfg(feature = "hash")]
+use crate::error::{EncodeError, VerifyError};
+#[cfg(feature = "hash")]
+use crate::hash::verify_hash;
+use crate::CBOR_TAGS_CID;
+#[cfg(feature = "hash")]
+use crate::DAG_CBOR_CODEC;
+
+/// A CID together with the DAG-CBOR encoded data it identifies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    cid: Cid,
+    data: Vec<u8>,
+}
+
+impl Block {
+    /// Creates a block from a CID and its data.
+    ///
+    /// The data is not checked against the CID, use [`Block::verify`] for that.
+    pub fn new(cid: Cid, data: Vec<u8>) -> Self {
+        Block { cid, data }
+    }
+
+    /// Serializes a value and hashes it with the given hash function.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cid::multihash::Code;
+    /// use serde_ipld_dagcbor::block::Block;
+    ///
+    /// let block = Block::encode(&vec![1, 2, 3], Code::Sha2_256).unwrap();
+    /// assert!(block.verify().is_ok());
+    /// let value: Vec<u8> = block.decode().unwrap();
+    /// assert_eq!(value, [1, 2, 3]);
+    /// ```
+    #[cfg(feature = "hash")]
+    pub fn encode<T>(value: &T, hash_code: Code) -> Result<Self, EncodeError<TryReserveError>>
+    where
+        T: Serialize + ?Sized,
+    {
+        // The hash functions of this crate hash the data while it is serialized.
+        let (data, cid) = match hash_code {
+            Code::Sha2_256 => crate::to_vec_with_cid::<Sha2_256, _>(value)?,
+            Code::Sha2_512 => crate::to_vec_with_cid::<Sha2_512, _>(value)?,
+            Code::Blake2b256 => crate::to_vec_with_cid::<Blake2b256, _>(value)?,
+            Code::Blake2b512 => crate::to_vec_with_cid::<Blake2b512, _>(value)?,
+            // Other hash functions might be enabled by other crates.
+            #[allow(unreachable_patterns)]
+            code => {
+                let data = crate::to_vec(value)?;
+                let cid = Cid::new_v1(DAG_CBOR_CODEC, code.digest(&data));
+                (data, cid)
+            }
+        };
+        Ok(Block { cid, data })
+    }
+
+    /// Deserializes the data of the block.
+    pub fn decode<'a, T>(&'a self) -> Result<T, DecodeError<Infallible>>
+    where
+        T: de::Deserialize<'a>,
+    {
+        crate::from_slice(&self.data)
+    }
+
+    /// Checks whether the CID is a DAG-CBOR CID that matches the hash of the data.
+    #[cfg(feature = "hash")]
+    pub fn verify(&self) -> Result<(), VerifyError> {
+        if self.cid.codec() != DAG_CBOR_CODEC {
+            return Err(VerifyError::Codec(self.cid.codec()));
+        }
+        verify_hash(&self.cid, &self.data)
+    }
+
+    /// Returns the CIDs the data links to, in the order they appear.
+    pub fn links(&self) -> Result<Vec<Cid>, DecodeError<Infallible>> {
+        links(&self.data)
+    }
+
+    /// Returns the CID of the block.
+    pub fn cid(&self) -> &Cid {
+        &self.cid
+    }
+
+    /// Returns the DAG-CBOR encoded data of the block.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the CID and the data of the block.
+    pub fn into_parts(self) -> (Cid, Vec<u8>) {
+        (self.cid, self.data)
+    }
+}
+
+/// Returns the CIDs that DAG-CBOR encoded data links to, in the order they appear.
+///
+/// The data isn't deserialized into a value, only the links are decoded.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+///
+/// use cid::Cid;
+/// use serde_ipld_dagcbor::block::links;
+///
+/// let cid = Cid::try_from("bafyreih4jspvxgh5xrp3wboocaajx5ym4ky7ok3gs5dsdkizgnegtanfxu").unwrap();
+/// let data = serde_ipld_dagcbor::to_vec(&vec![Some(cid), None]).unwrap();
+/// assert_eq!(links(&data).unwrap(), [cid]);
+/// ```
+pub fn links(data: &[u8]) -> Result<Vec<Cid>, DecodeError<Infallible>> {
+    let mut reader = SliceReader::new(data);
+    let mut links = Vec::new();
+    collect_links(&mut reader, &mut links)?;
+    match peek_one(&mut reader) {
+        Ok(_) => Err(DecodeError::TrailingData),
+        Err(DecodeError::Eof) => Ok(links),
+        Err(error) => Err(error),
+    }
+}
+
+fn collect_links<'de, R: dec::Read<'de>>(
+    reader: &mut R,
+    links: &mut Vec<Cid>,
+) -> Result<(), DecodeError<R::Error>> {
+    if !reader.step_in() {
+        return Err(DecodeError::DepthLimit);
+    }
+    match dec::if_major(peek_one(reader)?) {
+        major::ARRAY => {
+            let len = dec::ArrayStart::decode(reader)?.0;
+            let mut count = 0;
+            while has_next(reader, len, count)? {
+                collect_links(reader, links)?;
+                count += 1;
+            }
+        }
+        major::MAP => {
+            let len = dec::MapStart::decode(reader)?.0;
+            let mut count = 0;
+            while has_next(reader, len, count)? {
+                collect_links(reader, links)?;
+                collect_links(reader, links)?;
+                count += 1;
+            }
+        }
+        major::TAG => match dec::TagStart::decode(reader)?.0 {
+            CBOR_TAGS_CID => {
+                let bytes = <types::Bytes<Cow<[u8]>>>::decode(reader)?.0;
+                // CBOR encoded CIDs have a zero byte prefix we have to remove.
+                let cid = match bytes.split_first() {
+                    Some((0, cid)) => Cid::try_from(cid).ok(),
+                    _ => None,
+                }
+                .ok_or_else(|| DecodeError::Msg("Invalid CID".into()))?;
+                links.push(cid);
+            }
+            tag => return Err(DecodeError::Msg(format!("Unsupported CBOR tag {}", tag))),
+        },
+        _ => {
+            dec::IgnoredAny::decode(reader)?;
+        }
+    }
+    reader.step_out();
+    Ok(())
+}
+
+/// Returns whether a list or map of the given length has another element, also in case it's of
+/// indefinite length.
+pub(crate) fn has_next<'de, R: dec::Read<'de>>(
+    reader: &mut R,
+    len: Option<usize>,
+    count: usize,
+) -> Result<bool, DecodeError<R::Error>> {
+    match len {
+        Some(len) => Ok(count < len),
+        None if peek_one(reader)? == marker::BREAK => {
+            pull_one(reader)?;
+            Ok(false)
+        }
+        None => Ok(true),
+    }
+}
@@ -0,0 +1,181 @@
+//! Storage of blocks keyed by their CID.
+//!
+//! The [`Blockstore`] trait is a minimal interface for storing blocks. Besides the raw operations
+//! it has typed helpers to store and load values that are (de)serialized as DAG-CBOR.
+//!
+//! # Examples
+//!
+//! ```
+//! use serde_ipld_dagcbor::blockstore::{Blockstore, MemoryBlockstore};
+//!
+//! let mut store = MemoryBlockstore::new();
+//! let cid = store.put_value(&vec![1, 2, 3]).unwrap();
+//! assert!(store.has(&cid).unwrap());
+//! let value: Vec<u8> = store.get_value(&cid).unwrap();
+//! assert_eq!(value, [1, 2, 3]);
+//! ```
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use cid::multibase::Base;
+use cid::Cid;
+use multihash::Code;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::block::Block;
+use crate::error::BlockstoreError;
+
+/// A store of blocks keyed by their CID.
+pub trait Blockstore {
+    /// Returns the data of the block with the given CID, `None` if it isn't stored.
+    fn get(&self, cid: &Cid) -> Result<Option<Vec<u8>>, BlockstoreError>;
+
+    /// Stores a block. The data is not verified against the CID.
+    fn put(&mut self, cid: &Cid, data: &[u8]) -> Result<(), BlockstoreError>;
+
+    /// Returns whether the block with the given CID is stored.
+    fn has(&self, cid: &Cid) -> Result<bool, BlockstoreError>;
+
+    /// Removes the block with the given CID. Removing a block that isn't stored is not an error.
+    fn delete(&mut self, cid: &Cid) -> Result<(), BlockstoreError>;
+
+    /// Serializes a value as DAG-CBOR and stores it. The CID is hashed with SHA2-256.
+    fn put_value<T>(&mut self, value: &T) -> Result<Cid, BlockstoreError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let block = Block::encode(value, Code::Sha2_256)?;
+        self.put(block.cid(), block.data())?;
+        Ok(*block.cid())
+    }
+
+    /// Loads the block with the given CID and deserializes it from DAG-CBOR.
+    fn get_value<T>(&self, cid: &Cid) -> Result<T, BlockstoreError>
+    where
+        T: DeserializeOwned,
+    {
+        let data = self.get(cid)?.ok_or(BlockstoreError::NotFound(*cid))?;
+        Ok(crate::from_slice(&data)?)
+    }
+}
+
+/// A blockstore that keeps all blocks in memory.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryBlockstore {
+    blocks: HashMap<Cid, Vec<u8>>,
+}
+
+impl MemoryBlockstore {
+    /// Creates an empty blockstore.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of stored blocks.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns true if no blocks are stored.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
+
+impl Blockstore for MemoryBlockstore {
+    fn get(&self, cid: &Cid) -> Result<Option<Vec<u8>>, BlockstoreError> {
+        Ok(self.blocks.get(cid).cloned())
+    }
+
+    fn put(&mut self, cid: &Cid, data: &[u8]) -> Result<(), BlockstoreError> {
+        self.blocks.insert(*cid, data.to_vec());
+        Ok(())
+    }
+
+    fn has(&self, cid: &Cid) -> Result<bool, BlockstoreError> {
+        Ok(self.blocks.contains_key(cid))
+    }
+
+    fn delete(&mut self, cid: &Cid) -> Result<(), BlockstoreError> {
+        self.blocks.remove(cid);
+        Ok(())
+    }
+}
+
+/// Makes the names of temporary files unique within the process, so that concurrent writes of
+/// the same block don't share a temporary file.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A blockstore that stores each block in a separate file.
+///
+/// The files are named after the base32 encoded multihash of the CID, hence blocks with the same
+/// hash, but a different codec share the same file. They are sharded into directories named after
+/// the next-to-last two characters of the file name, the same layout as the `next-to-last/2`
+/// sharding of go-ipfs.
+#[derive(Clone, Debug)]
+pub struct FsBlockstore {
+    root: PathBuf,
+}
+
+impl FsBlockstore {
+    /// Opens a blockstore in the given directory, it is created if it doesn't exist.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Result<Self, BlockstoreError> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+        Ok(FsBlockstore { root })
+    }
+
+    /// Returns the root directory of the blockstore.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the path of the file the block with the given CID is stored in.
+    pub fn path(&self, cid: &Cid) -> PathBuf {
+        let key = Base::Base32Upper.encode(cid.hash().to_bytes());
+        // Multihashes are at least two bytes long, hence the key has more than three characters.
+        let shard = &key[key.len() - 3..key.len() - 1];
+        self.root.join(shard).join(key + ".data")
+    }
+}
+
+impl Blockstore for FsBlockstore {
+    fn get(&self, cid: &Cid) -> Result<Option<Vec<u8>>, BlockstoreError> {
+        match fs::read(self.path(cid)) {
+            Ok(data) => Ok(Some(data)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn put(&mut self, cid: &Cid, data: &[u8]) -> Result<(), BlockstoreError> {
+        let path = self.path(cid);
+        let dir = path
+            .parent()
+            .expect("block files are within a shard directory");
+        fs::create_dir_all(dir)?;
+        // Write to a temporary file first, so that a block is never partially visible.
+        let temp = path.with_extension(format!(
+            "{}.{}.tmp",
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&temp, data)?;
+        fs::rename(&temp, &path)?;
+        Ok(())
+    }
+
+    fn has(&self, cid: &Cid) -> Result<bool, BlockstoreError> {
+        Ok(self.path(cid).is_file())
+    }
+
+    fn delete(&mut self, cid: &Cid) -> Result<(), BlockstoreError> {
+        match fs::remove_file(self.path(cid)) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! CARv2 indexes.
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+
+use cid::multihash::Multihash;
+
+use super::{read_exact, read_varint, write_varint, CarReader};
+use crate::error::CarError;
+
+/// The format of a CARv2 index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexCodec {
+    /// Sorted by digest, grouped by digest length. The hash functions are not recorded.
+    IndexSorted,
+    /// Sorted by digest, grouped by hash function and digest length.
+    MultihashIndexSorted,
+}
+
+impl IndexCodec {
+    /// Returns the multicodec code of the index format.
+    pub fn code(self) -> u64 {
+        match self {
+            IndexCodec::IndexSorted => 0x0400,
+            IndexCodec::MultihashIndexSorted => 0x0401,
+        }
+    }
+
+    /// Returns the index format of a multicodec code.
+    pub fn from_code(code: u64) -> Option<Self> {
+        match code {
+            0x0400 => Some(IndexCodec::IndexSorted),
+            0x0401 => Some(IndexCodec::MultihashIndexSorted),
+            _ => None,
+        }
+    }
+}
+
+/// Digests mapped to offsets of sections relative to the start of the CARv1 payload.
+type Digests = BTreeMap<Vec<u8>, u64>;
+
+/// An index of the sections of a CARv1 payload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Index {
+    codec: IndexCodec,
+    /// The digests grouped by the multihash code. For [`IndexCodec::IndexSorted`] all digests are
+    /// stored with code 0, as the hash functions aren't known.
+    entries: BTreeMap<u64, Digests>,
+}
+
+impl Index {
+    /// Creates an empty index.
+    pub fn new(codec: IndexCodec) -> Self {
+        Index {
+            codec,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Creates an index of a CARv1 or CARv2 file by reading all of its sections.
+    pub fn generate<R: Read>(reader: R, codec: IndexCodec) -> Result<Self, CarError> {
+        let mut reader = CarReader::new(reader)?;
+        let mut index = Index::new(codec);
+        while let Some((cid, _, offset)) = reader.next_section()? {
+            index.insert(cid.hash(), offset);
+        }
+        Ok(index)
+    }
+
+    /// Returns the format of the index.
+    pub fn codec(&self) -> IndexCodec {
+        self.codec
+    }
+
+    /// Adds the offset of the section with the given multihash. If the multihash is already
+    /// contained, the first offset is kept.
+    pub fn insert(&mut self, hash: &Multihash, offset: u64) {
+        self.entries
+            .entry(self.key(hash))
+            .or_default()
+            .entry(hash.digest().to_vec())
+            .or_insert(offset);
+    }
+
+    /// Returns the offset of the section with the given multihash.
+    pub fn get(&self, hash: &Multihash) -> Option<u64> {
+        self.entries
+            .get(&self.key(hash))
+            .and_then(|digests| digests.get(hash.digest()))
+            .copied()
+    }
+
+    /// Returns the number of indexed sections.
+    pub fn len(&self) -> usize {
+        self.entries.values().map(BTreeMap::len).sum()
+    }
+
+    /// Returns `true` if no section is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn key(&self, hash: &Multihash) -> u64 {
+        match self.codec {
+            IndexCodec::IndexSorted => 0,
+            IndexCodec::MultihashIndexSorted => hash.code(),
+        }
+    }
+
+    /// Reads an index including its multicodec prefix.
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, CarError> {
+        let code = read_varint(reader)?.ok_or(CarError::InvalidIndex)?;
+        let codec = IndexCodec::from_code(code).ok_or(CarError::UnsupportedIndex(code))?;
+        let mut entries = BTreeMap::new();
+        match codec {
+            IndexCodec::IndexSorted => {
+                entries.insert(0, read_sorted(reader)?);
+            }
+            IndexCodec::MultihashIndexSorted => {
+                let count = read_count(reader)?;
+                for _ in 0..count {
+                    let code = read_u64(reader)?;
+                    entries.insert(code, read_sorted(reader)?);
+                }
+            }
+        }
+        Ok(Index { codec, entries })
+    }
+
+    /// Writes an index including its multicodec prefix. Returns the number of bytes written.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<u64, CarError> {
+        let mut len = write_varint(writer, self.codec.code())? as u64;
+        match self.codec {
+            IndexCodec::IndexSorted => {
+                let empty = BTreeMap::new();
+                len += write_sorted(writer, self.entries.get(&0).unwrap_or(&empty))?;
+            }
+            IndexCodec::MultihashIndexSorted => {
+                writer.write_all(&(self.entries.len() as i32).to_le_bytes())?;
+                len += 4;
+                for (code, digests) in &self.entries {
+                    writer.write_all(&code.to_le_bytes())?;
+                    len += 8 + write_sorted(writer, digests)?;
+                }
+            }
+        }
+        Ok(len)
+    }
+}
+
+/// Reads the digests grouped by their width, the length of the digest plus 8 bytes for the offset.
+fn read_sorted<R: Read>(reader: &mut R) -> Result<Digests, CarError> {
+    let mut digests = BTreeMap::new();
+    let buckets = read_count(reader)?;
+    for _ in 0..buckets {
+        let mut width = [0; 4];
+        reader.read_exact(&mut width)?;
+        let width = u64::from(u32::from_le_bytes(width));
+        let len = read_u64(reader)?;
+        if width <= 8 || len % width != 0 {
+            return Err(CarError::InvalidIndex);
+        }
+        let bytes = read_exact(reader, len)?;
+        for entry in bytes.chunks(width as usize) {
+            let (digest, offset) = entry.split_at(entry.len() - 8);
+            let mut offset_bytes = [0; 8];
+            offset_bytes.copy_from_slice(offset);
+            digests.insert(digest.to_vec(), u64::from_le_bytes(offset_bytes));
+        }
+    }
+    Ok(digests)
+}
+
+/// Writes the digests grouped by their width. Returns the number of bytes written.
+fn write_sorted<W: Write>(writer: &mut W, digests: &Digests) -> Result<u64, CarError> {
+    let mut buckets: BTreeMap<usize, Vec<(&Vec<u8>, &u64)>> = BTreeMap::new();
+    for entry in digests {
+        buckets.entry(entry.0.len() + 8).or_default().push(entry);
+    }
+
+    writer.write_all(&(buckets.len() as i32).to_le_bytes())?;
+    let mut len = 4;
+    for (width, entries) in buckets {
+        let bucket_len = (width * entries.len()) as u64;
+        writer.write_all(&(width as u32).to_le_bytes())?;
+        writer.write_all(&bucket_len.to_le_bytes())?;
+        // The entries are already sorted by their digest, as they come from a `BTreeMap`.
+        for (digest, offset) in entries {
+            writer.write_all(digest)?;
+            writer.write_all(&offset.to_le_bytes())?;
+        }
+        len += 12 + bucket_len;
+    }
+    Ok(len)
+}
+
+fn read_count<R: Read>(reader: &mut R) -> Result<u32, CarError> {
+    let mut count = [0; 4];
+    reader.read_exact(&mut count)?;
+    let count = i32::from_le_bytes(count);
+    if count < 0 {
+        return Err(CarError::InvalidIndex);
+    }
+    Ok(count as u32)
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut value = [0; 8];
+    reader.read_exact(&mut value)?;
+    Ok(u64::from_le_bytes(value))
+}
@@ -0,0 +1,338 @@
+//! Reading and writing of CAR (Content Addressable aRchive) files.
+//!
+//! A CARv1 file consists of a DAG-CBOR encoded header followed by a sequence of sections. Each
+//! section contains a CID and the data of the block it identifies. The header as well as the
+//! sections are prefixed with their length as unsigned varint.
+//!
+//! A CARv2 file wraps a CARv1 payload. It starts with a fixed pragma and header, which points to
+//! the payload and an optional index of the sections. The index allows random access to blocks by
+//! their CID, see [`IndexedCarReader`].
+//!
+//! # Examples
+//!
+//! ```
+//! use cid::multihash::Code;
+//! use serde_ipld_dagcbor::car::{CarReader, CarWriter};
+//! use serde_ipld_dagcbor::Block;
+//!
+//! let block = Block::encode(&"hello", Code::Sha2_256).unwrap();
+//!
+//! let mut writer = CarWriter::new(Vec::new(), vec![*block.cid()]).unwrap();
+//! writer.write(block.cid(), block.data()).unwrap();
+//! let car = writer.into_inner();
+//!
+//! let mut reader = CarReader::new(&car[..]).unwrap();
+//! assert_eq!(reader.header().roots, [*block.cid()]);
+//! let (cid, data) = reader.next().unwrap().unwrap();
+//! assert_eq!(&cid, block.cid());
+//! assert_eq!(data, block.data());
+//! assert!(reader.next().is_none());
+//! ```
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use cid::Cid;
+use serde::de::{self, MapAccess, Visitor};
+use serde::ser::{self, SerializeStruct};
+
+use crate::error::CarError;
+use crate::hash::verify_hash;
+
+mod index;
+mod v2;
+
+pub use index::{Index, IndexCodec};
+pub use v2::{CarV2Header, CarV2Writer, IndexedCarReader, PRAGMA};
+
+/// The header of a CAR file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CarHeader {
+    /// The version of the CAR format.
+    pub version: u64,
+    /// The root CIDs of the DAGs contained in the file.
+    pub roots: Vec<Cid>,
+}
+
+impl CarHeader {
+    /// Creates a new CARv1 header.
+    pub fn new(roots: Vec<Cid>) -> Self {
+        CarHeader { version: 1, roots }
+    }
+}
+
+impl ser::Serialize for CarHeader {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // The fields are in DAG-CBOR map key order.
+        let mut header = serializer.serialize_struct("CarHeader", 2)?;
+        header.serialize_field("roots", &self.roots)?;
+        header.serialize_field("version", &self.version)?;
+        header.end()
+    }
+}
+
+impl<'de> de::Deserialize<'de> for CarHeader {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct HeaderVisitor;
+
+        impl<'de> Visitor<'de> for HeaderVisitor {
+            type Value = CarHeader;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a CAR header")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut version = None;
+                let mut roots = None;
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "version" => version = Some(map.next_value()?),
+                        "roots" => roots = Some(map.next_value()?),
+                        _ => {
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(CarHeader {
+                    version: version.ok_or_else(|| de::Error::missing_field("version"))?,
+                    roots: roots.unwrap_or_default(),
+                })
+            }
+        }
+
+        deserializer.deserialize_map(HeaderVisitor)
+    }
+}
+
+/// Reads a CAR file section by section.
+///
+/// Both CARv1 and CARv2 files are supported, for CARv2 files the sections of the inner CARv1
+/// payload are read. It is an iterator over the CIDs and the data of the blocks.
+#[derive(Debug)]
+pub struct CarReader<R> {
+    reader: io::Take<R>,
+    header: CarHeader,
+    v2_header: Option<CarV2Header>,
+    /// The offset of the next section relative to the start of the CARv1 payload.
+    offset: u64,
+    verify: bool,
+}
+
+impl<R: Read> CarReader<R> {
+    /// Creates a new reader and reads the header.
+    pub fn new(mut reader: R) -> Result<Self, CarError> {
+        let (header, header_len) = read_header(&mut reader)?;
+        match header.version {
+            1 => Ok(CarReader {
+                reader: reader.take(u64::MAX),
+                header,
+                v2_header: None,
+                offset: header_len,
+                verify: false,
+            }),
+            2 => {
+                let v2_header = CarV2Header::read(&mut reader)?;
+                // Skip the padding between the header and the payload.
+                let padding = v2_header
+                    .data_offset
+                    .checked_sub(PRAGMA.len() as u64 + CarV2Header::LEN)
+                    .ok_or(CarError::InvalidHeader)?;
+                io::copy(&mut (&mut reader).take(padding), &mut io::sink())?;
+
+                let mut reader = reader.take(v2_header.data_size);
+                let (header, header_len) = read_header(&mut reader)?;
+                if header.version != 1 {
+                    return Err(CarError::UnsupportedVersion(header.version));
+                }
+                Ok(CarReader {
+                    reader,
+                    header,
+                    v2_header: Some(v2_header),
+                    offset: header_len,
+                    verify: false,
+                })
+            }
+            version => Err(CarError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// Returns the header of the CARv1 payload.
+    pub fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    /// Returns the CARv2 header in case it is a CARv2 file.
+    pub fn v2_header(&self) -> Option<&CarV2Header> {
+        self.v2_header.as_ref()
+    }
+
+    /// Sets whether the data of each section is verified against its CID while reading.
+    ///
+    /// It is disabled by default.
+    pub fn set_verify(&mut self, verify: bool) {
+        self.verify = verify;
+    }
+
+    /// Reads the next section. Returns `None` at the end of the file.
+    pub fn next_block(&mut self) -> Result<Option<(Cid, Vec<u8>)>, CarError> {
+        match self.next_section()? {
+            Some((cid, data, _)) => Ok(Some((cid, data))),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the next section, also returning its offset relative to the start of the CARv1
+    /// payload.
+    pub(crate) fn next_section(&mut self) -> Result<Option<(Cid, Vec<u8>, u64)>, CarError> {
+        let (cid, data, len) = match read_section(&mut self.reader)? {
+            Some(section) => section,
+            None => return Ok(None),
+        };
+        if self.verify {
+            verify_hash(&cid, &data).map_err(|error| CarError::Verify(cid, error))?;
+        }
+        let offset = self.offset;
+        self.offset += len;
+        Ok(Some((cid, data, offset)))
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+impl<R: Read> Iterator for CarReader<R> {
+    type Item = Result<(Cid, Vec<u8>), CarError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().transpose()
+    }
+}
+
+/// Writes a CARv1 file.
+#[derive(Debug)]
+pub struct CarWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> CarWriter<W> {
+    /// Creates a new writer and writes the header with the given roots.
+    pub fn new(mut writer: W, roots: Vec<Cid>) -> Result<Self, CarError> {
+        write_header(&mut writer, &CarHeader::new(roots))?;
+        Ok(CarWriter { writer })
+    }
+
+    /// Writes a section containing a block.
+    pub fn write(&mut self, cid: &Cid, data: &[u8]) -> Result<(), CarError> {
+        write_section(&mut self.writer, cid, data)?;
+        Ok(())
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads a length prefixed DAG-CBOR encoded header. Also returns the number of bytes read.
+fn read_header<R: Read>(reader: &mut R) -> Result<(CarHeader, u64), CarError> {
+    let len = read_varint(reader)?.ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+    let bytes = read_exact(reader, len)?;
+    Ok((crate::from_slice(&bytes)?, varint_len(len) + len))
+}
+
+/// Writes a length prefixed DAG-CBOR encoded header. Returns the number of bytes written.
+fn write_header<W: Write>(writer: &mut W, header: &CarHeader) -> Result<u64, CarError> {
+    let bytes = crate::to_vec(header)?;
+    let len = write_varint(writer, bytes.len() as u64)?;
+    writer.write_all(&bytes)?;
+    Ok((len + bytes.len()) as u64)
+}
+
+/// Reads a section, returns `None` if the reader is at its end. Also returns the number of bytes
+/// read.
+fn read_section<R: Read>(reader: &mut R) -> Result<Option<(Cid, Vec<u8>, u64)>, CarError> {
+    let len = match read_varint(reader)? {
+        Some(len) => len,
+        None => return Ok(None),
+    };
+    if len == 0 {
+        return Err(CarError::InvalidSectionLength);
+    }
+    let mut section = reader.take(len);
+    let cid = Cid::read_bytes(&mut section)?;
+    let data_len = section.limit();
+    let data = read_exact(&mut section, data_len)?;
+    Ok(Some((cid, data, varint_len(len) + len)))
+}
+
+/// Writes a section. Returns the number of bytes written.
+fn write_section<W: Write>(writer: &mut W, cid: &Cid, data: &[u8]) -> Result<u64, CarError> {
+    let cid_bytes = cid.to_bytes();
+    let len = write_varint(writer, (cid_bytes.len() + data.len()) as u64)?;
+    writer.write_all(&cid_bytes)?;
+    writer.write_all(data)?;
+    Ok((len + cid_bytes.len() + data.len()) as u64)
+}
+
+/// Reads exactly `len` bytes, without allocating them upfront.
+fn read_exact<R: Read>(reader: &mut R, len: u64) -> Result<Vec<u8>, CarError> {
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(bytes)
+}
+
+/// Reads an unsigned varint. Returns `None` if the reader is at its end.
+fn read_varint<R: Read>(reader: &mut R) -> Result<Option<u64>, CarError> {
+    let mut value = 0u64;
+    for index in 0..10 {
+        let mut byte = [0u8];
+        if reader.read(&mut byte)? == 0 {
+            return if index == 0 {
+                Ok(None)
+            } else {
+                Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+            };
+        }
+        let bits = u64::from(byte[0] & 0x7f);
+        value |= bits
+            .checked_shl(7 * index)
+            .filter(|shifted| shifted >> (7 * index) == bits)
+            .ok_or(CarError::InvalidVarint)?;
+        if byte[0] & 0x80 == 0 {
+            // Varints must be minimally encoded.
+            if byte[0] == 0 && index > 0 {
+                return Err(CarError::InvalidVarint);
+            }
+            return Ok(Some(value));
+        }
+    }
+    Err(CarError::InvalidVarint)
+}
+
+/// Writes an unsigned varint. Returns the number of bytes written.
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<usize> {
+    let mut buf = [0u8; 10];
+    let mut len = 0;
+    loop {
+        buf[len] = (value & 0x7f) as u8;
+        value >>= 7;
+        len += 1;
+        if value == 0 {
+            break;
+        }
+        buf[len - 1] |= 0x80;
+    }
+    writer.write_all(&buf[..len])?;
+    Ok(len)
+}
+
+/// Returns the number of bytes of a minimally encoded unsigned varint.
+fn varint_len(value: u64) -> u64 {
+    let bits = 64 - u64::from(value.leading_zeros());
+    std::cmp::max(1, bits.div_ceil(7))
+}
@@ -0,0 +1,199 @@
+//! CARv2 files.
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use cid::Cid;
+
+use super::{read_section, write_header, write_section, CarHeader, CarReader, Index, IndexCodec};
+use crate::error::CarError;
+
+/// The bytes every CARv2 file starts with. It is a CARv1 header with version 2 and no roots.
+pub const PRAGMA: [u8; 11] = [
+    0x0a, 0xa1, 0x67, 0x76, 0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e, 0x02,
+];
+
+/// The fixed size header of a CARv2 file, following the pragma.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CarV2Header {
+    /// Bitfield of characteristics of the payload.
+    pub characteristics: [u8; 16],
+    /// Offset of the CARv1 payload from the start of the file.
+    pub data_offset: u64,
+    /// Size of the CARv1 payload in bytes.
+    pub data_size: u64,
+    /// Offset of the index from the start of the file, 0 if there is no index.
+    pub index_offset: u64,
+}
+
+impl CarV2Header {
+    /// The size of the header in bytes.
+    pub const LEN: u64 = 40;
+
+    /// Returns whether the index contains all sections, including identity CIDs.
+    pub fn is_fully_indexed(&self) -> bool {
+        // It is the most significant bit of the first, little-endian encoded, 64-bit word.
+        self.characteristics[7] & 0x80 != 0
+    }
+
+    pub(crate) fn read<R: Read>(reader: &mut R) -> Result<Self, CarError> {
+        let mut bytes = [0; Self::LEN as usize];
+        reader.read_exact(&mut bytes)?;
+        let mut characteristics = [0; 16];
+        characteristics.copy_from_slice(&bytes[..16]);
+        let word = |start: usize| {
+            let mut word = [0; 8];
+            word.copy_from_slice(&bytes[start..start + 8]);
+            u64::from_le_bytes(word)
+        };
+        Ok(CarV2Header {
+            characteristics,
+            data_offset: word(16),
+            data_size: word(24),
+            index_offset: word(32),
+        })
+    }
+
+    pub(crate) fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.characteristics)?;
+        writer.write_all(&self.data_offset.to_le_bytes())?;
+        writer.write_all(&self.data_size.to_le_bytes())?;
+        writer.write_all(&self.index_offset.to_le_bytes())
+    }
+}
+
+/// Writes a CARv2 file together with an index.
+///
+/// The header is written once all blocks are written and the size of the payload is known, hence
+/// the writer needs to be seekable.
+#[derive(Debug)]
+pub struct CarV2Writer<W> {
+    writer: W,
+    /// The start of the file within the writer.
+    start: u64,
+    /// The size of the CARv1 payload written so far.
+    data_size: u64,
+    index: Index,
+}
+
+impl<W: Write + Seek> CarV2Writer<W> {
+    /// Creates a new writer. The index will be written in the given format.
+    pub fn new(mut writer: W, roots: Vec<Cid>, codec: IndexCodec) -> Result<Self, CarError> {
+        let start = writer.stream_position()?;
+        writer.write_all(&PRAGMA)?;
+        // Placeholder, the actual header is written on `finish()`.
+        writer.write_all(&[0; CarV2Header::LEN as usize])?;
+        let data_size = write_header(&mut writer, &CarHeader::new(roots))?;
+        Ok(CarV2Writer {
+            writer,
+            start,
+            data_size,
+            index: Index::new(codec),
+        })
+    }
+
+    /// Writes a section containing a block and adds it to the index.
+    pub fn write(&mut self, cid: &Cid, data: &[u8]) -> Result<(), CarError> {
+        self.index.insert(cid.hash(), self.data_size);
+        self.data_size += write_section(&mut self.writer, cid, data)?;
+        Ok(())
+    }
+
+    /// Writes the index and the header. Returns the underlying writer, positioned at the end of
+    /// the file.
+    pub fn finish(mut self) -> Result<W, CarError> {
+        let data_offset = PRAGMA.len() as u64 + CarV2Header::LEN;
+        let header = CarV2Header {
+            characteristics: [0; 16],
+            data_offset,
+            data_size: self.data_size,
+            index_offset: data_offset + self.data_size,
+        };
+        self.index.write(&mut self.writer)?;
+        let end = self.writer.stream_position()?;
+        self.writer
+            .seek(SeekFrom::Start(self.start + PRAGMA.len() as u64))?;
+        header.write(&mut self.writer)?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        Ok(self.writer)
+    }
+}
+
+/// Random access to the blocks of a CAR file by their CID.
+///
+/// For CARv2 files with an index, the index is used. For other files, an index is generated by
+/// reading all sections once.
+#[derive(Debug)]
+pub struct IndexedCarReader<R> {
+    reader: R,
+    header: CarHeader,
+    v2_header: Option<CarV2Header>,
+    /// The start of the CARv1 payload within the reader.
+    data_offset: u64,
+    index: Index,
+}
+
+impl<R: Read + Seek> IndexedCarReader<R> {
+    /// Creates a new reader and reads the index.
+    pub fn new(mut reader: R) -> Result<Self, CarError> {
+        let start = reader.stream_position()?;
+        let car = CarReader::new(&mut reader)?;
+        let header = car.header().clone();
+        let v2_header = car.v2_header().cloned();
+
+        let (data_offset, index) = match &v2_header {
+            Some(v2_header) if v2_header.index_offset != 0 => {
+                reader.seek(SeekFrom::Start(start + v2_header.index_offset))?;
+                (v2_header.data_offset, Index::read(&mut reader)?)
+            }
+            _ => {
+                reader.seek(SeekFrom::Start(start))?;
+                let index = Index::generate(&mut reader, IndexCodec::MultihashIndexSorted)?;
+                (v2_header.as_ref().map_or(0, |h| h.data_offset), index)
+            }
+        };
+
+        Ok(IndexedCarReader {
+            reader,
+            header,
+            v2_header,
+            data_offset: start + data_offset,
+            index,
+        })
+    }
+
+    /// Returns the header of the CARv1 payload.
+    pub fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    /// Returns the CARv2 header in case it is a CARv2 file.
+    pub fn v2_header(&self) -> Option<&CarV2Header> {
+        self.v2_header.as_ref()
+    }
+
+    /// Returns the index.
+    pub fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// Returns the data of the block with the given CID.
+    ///
+    /// Blocks are looked up by their multihash, hence the data of a block with the same hash, but
+    /// a different codec is returned as well.
+    pub fn get(&mut self, cid: &Cid) -> Result<Option<Vec<u8>>, CarError> {
+        let offset = match self.index.get(cid.hash()) {
+            Some(offset) => offset,
+            None => return Ok(None),
+        };
+        self.reader
+            .seek(SeekFrom::Start(self.data_offset + offset))?;
+        match read_section(&mut self.reader)? {
+            Some((section_cid, data, _)) if section_cid.hash() == cid.hash() => Ok(Some(data)),
+            _ => Err(CarError::InvalidIndex),
+        }
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
@@ -3,7 +3,7 @@
 //! Some things needed for the Serde implementation are not public in the cbor4ii crate. Those are
 //! copied into this file.
 
-use cbor4ii::core::dec;
+use cbor4ii::core::{dec, enc};
 
 use crate::error::DecodeError;
 
@@ -28,7 +28,7 @@ pub(crate) fn peek_one<'a, R: dec::Read<'a>>(reader: &mut R) -> Result<u8, Decod
         dec::Reference::Long(buf) => buf,
         dec::Reference::Short(buf) => buf,
     };
-    let byte = buf.get(0).copied().ok_or(DecodeError::Eof)?;
+    let byte = buf.first().copied().ok_or(DecodeError::Eof)?;
     Ok(byte)
 }
 
@@ -39,3 +39,29 @@ pub(crate) fn pull_one<'a, R: dec::Read<'a>>(reader: &mut R) -> Result<u8, Decod
     reader.advance(1);
     Ok(byte)
 }
+
+// Copy from cbor4ii/core/enc.rs, it's the encoding of `TypeNum`.
+#[inline]
+pub(crate) fn encode_head<W: enc::Write>(
+    writer: &mut W,
+    major: u8,
+    value: u64,
+) -> Result<(), W::Error> {
+    let type_ = major << 5;
+    match value {
+        x @ 0x00..=0x17 => writer.push(&[type_ | x as u8]),
+        x if x <= u64::from(u8::MAX) => writer.push(&[type_ | 0x18, x as u8]),
+        x if x <= u64::from(u16::MAX) => {
+            let [x0, x1] = (x as u16).to_be_bytes();
+            writer.push(&[type_ | 0x19, x0, x1])
+        }
+        x if x <= u64::from(u32::MAX) => {
+            let [x0, x1, x2, x3] = (x as u32).to_be_bytes();
+            writer.push(&[type_ | 0x1a, x0, x1, x2, x3])
+        }
+        x => {
+            let [x0, x1, x2, x3, x4, x5, x6, x7] = x.to_be_bytes();
+            writer.push(&[type_ | 0x1b, x0, x1, x2, x3, x4, x5, x6, x7])
+        }
+    }
+}
@@ -1,17 +1,21 @@
 //! Deserialization.
 #[cfg(not(feature = "std"))]
-use alloc::borrow::Cow;
-use core::convert::Infallible;
+use alloc::{borrow::Cow, vec, vec::Vec};
+use core::convert::{Infallible, TryFrom};
 #[cfg(feature = "std")]
 use std::borrow::Cow;
 
-use cbor4ii::core::dec::{self, Decode};
-use cbor4ii::core::{major, types, utils::SliceReader};
+use cbor4ii::core::dec::{self, Decode, Read};
+use cbor4ii::core::enc::Encode;
+use cbor4ii::core::utils::{BufWriter, SliceReader};
+use cbor4ii::core::{major, types};
 use cid::serde::CID_SERDE_PRIVATE_IDENTIFIER;
 use serde::de::{self, Visitor};
 
+use crate::block::has_next;
 use crate::cbor4ii_nonpub::{marker, peek_one, pull_one};
 use crate::error::DecodeError;
+use crate::ser::EnumRepresentation;
 use crate::CBOR_TAGS_CID;
 #[cfg(feature = "std")]
 use cbor4ii::core::utils::IoReader;
@@ -41,11 +45,38 @@ pub fn from_slice<'a, T>(buf: &'a [u8]) -> Result<T, DecodeError<Infallible>>
 where
     T: de::Deserialize<'a>,
 {
-    let reader = SliceReader::new(buf);
-    let mut deserializer = Deserializer::from_reader(reader);
-    let value = serde::Deserialize::deserialize(&mut deserializer)?;
-    deserializer.end()?;
-    Ok(value)
+    from_slice_with_options(buf, &DecodeOptions::default())
+}
+
+/// Decodes a value from CBOR data in a slice with the given options.
+///
+/// # Examples
+///
+/// ```
+/// use serde_derive::Deserialize;
+/// use serde_ipld_dagcbor::de::{from_slice_with_options, DecodeOptions};
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// struct Point {
+///     x: u8,
+///     y: u8,
+/// }
+///
+/// let options = DecodeOptions {
+///     struct_tuples: true,
+///     ..Default::default()
+/// };
+/// let point: Point = from_slice_with_options(&[0x82, 0x01, 0x02], &options).unwrap();
+/// assert_eq!(point, Point { x: 1, y: 2 });
+/// ```
+pub fn from_slice_with_options<'a, T>(
+    buf: &'a [u8],
+    options: &DecodeOptions,
+) -> Result<T, DecodeError<Infallible>>
+where
+    T: de::Deserialize<'a>,
+{
+    deserialize(|| SliceReader::new(buf), options)
 }
 
 /// Decodes a value from CBOR data in a reader.
@@ -75,26 +106,255 @@ where
     T: de::DeserializeOwned,
     R: std::io::BufRead,
 {
-    let reader = IoReader::new(reader);
-    let mut deserializer = Deserializer::from_reader(reader);
-    let value = serde::Deserialize::deserialize(&mut deserializer)?;
-    deserializer.end()?;
-    Ok(value)
+    from_reader_with_options(reader, &DecodeOptions::default())
+}
+
+/// Decodes a value from CBOR data in a reader with the given options.
+#[cfg(feature = "std")]
+pub fn from_reader_with_options<T, R>(
+    mut reader: R,
+    options: &DecodeOptions,
+) -> Result<T, DecodeError<std::io::Error>>
+where
+    T: de::DeserializeOwned,
+    R: std::io::BufRead,
+{
+    if options.missing_fields_as_none {
+        // The data is read again if a struct needs another attempt.
+        let mut data = Vec::new();
+        std::io::Read::read_to_end(&mut reader, &mut data)?;
+        deserialize(|| IoReader::new(&data[..]), options)
+    } else {
+        let mut reader = Some(reader);
+        deserialize(
+            || IoReader::new(reader.take().expect("only read once")),
+            options,
+        )
+    }
+}
+
+/// Deserializes a value from the reader the function returns.
+///
+/// With [`DecodeOptions::missing_fields_as_none`], a required struct field that is missing makes
+/// the attempt fail. The value is then decoded again from a new reader, with the field being null.
+fn deserialize<'de, T, R, F>(
+    mut reader: F,
+    options: &DecodeOptions,
+) -> Result<T, DecodeError<R::Error>>
+where
+    T: de::Deserialize<'de>,
+    R: dec::Read<'de>,
+    F: FnMut() -> R,
+{
+    let mut missing_fields = Vec::new();
+    loop {
+        let mut deserializer = Deserializer::with_options(reader(), *options);
+        deserializer.missing_fields = missing_fields;
+        let result = serde::Deserialize::deserialize(&mut deserializer).and_then(|value| {
+            deserializer.end()?;
+            Ok(value)
+        });
+        match (result, deserializer.missing_field) {
+            (Err(_), Some(field)) => {
+                missing_fields = deserializer.missing_fields;
+                missing_fields.push(field);
+            }
+            (result, _) => return result,
+        }
+    }
+}
+
+/// Options for decoding.
+///
+/// They need to match the [`EncodeOptions`](crate::ser::EncodeOptions) the data was encoded with.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DecodeOptions {
+    /// Decode structs from lists of their field values in declaration order, instead of maps from
+    /// the field names to the values. The number of values must match the number of fields.
+    pub struct_tuples: bool,
+    /// How enum variants are represented.
+    pub enum_representation: EnumRepresentation,
+    /// Decode struct fields that are missing from the map as if they were null, so that they
+    /// become `None`. This matches [`EncodeOptions::omit_null_fields`] for types that don't treat
+    /// missing fields as `None` themselves. Fields that are missing are left to the type first,
+    /// so that defaults and aliases apply. Only if it reports a required field as missing, the
+    /// value is decoded again with that field being null. [`from_reader_with_options`] reads all
+    /// data into memory for that.
+    ///
+    /// [`EncodeOptions::omit_null_fields`]: crate::ser::EncodeOptions::omit_null_fields
+    pub missing_fields_as_none: bool,
 }
 
 /// A Serde `Deserialize`r of DAG-CBOR data.
 #[derive(Debug)]
 struct Deserializer<R> {
-    reader: R,
+    reader: Replay<R>,
+    options: DecodeOptions,
+    /// The struct fields that are decoded as null if they are missing, identified by the fields
+    /// of their struct and their name.
+    missing_fields: Vec<(&'static [&'static str], &'static str)>,
+    /// A struct field that was reported as missing and isn't decoded as null yet.
+    missing_field: Option<(&'static [&'static str], &'static str)>,
+    /// Whether a struct failed to decode, the error is only attributed to the innermost one.
+    struct_failed: bool,
 }
 
 impl<R> Deserializer<R> {
-    /// Constructs a `Deserializer` which reads from a `Read`er.
-    pub fn from_reader(reader: R) -> Deserializer<R> {
-        Deserializer { reader }
+    /// Constructs a `Deserializer` which reads from a `Read`er with the given options.
+    pub fn with_options(reader: R, options: DecodeOptions) -> Deserializer<R> {
+        Deserializer {
+            reader: Replay {
+                buffer: Vec::new(),
+                pos: 0,
+                reader,
+            },
+            options,
+            missing_fields: Vec::new(),
+            missing_field: None,
+            struct_failed: false,
+        }
+    }
+}
+
+/// A reader that returns data that was read ahead again, before it continues with the inner
+/// reader.
+///
+/// The tag of an internally tagged enum can be at any position within its map, the entries before
+/// it are copied, so that they can be read again once the variant is known.
+#[derive(Debug)]
+struct Replay<R> {
+    buffer: Vec<u8>,
+    /// The position of the data that wasn't read yet within the buffer.
+    pos: usize,
+    reader: R,
+}
+
+impl<R> Replay<R> {
+    /// Reads the given data again before the data that wasn't read yet.
+    fn replay(&mut self, mut data: Vec<u8>) {
+        data.extend_from_slice(&self.buffer[self.pos..]);
+        self.buffer = data;
+        self.pos = 0;
     }
 }
 
+impl<'de, R: dec::Read<'de>> dec::Read<'de> for Replay<R> {
+    type Error = R::Error;
+
+    #[inline]
+    fn fill<'b>(&'b mut self, want: usize) -> Result<dec::Reference<'de, 'b>, Self::Error> {
+        if self.pos < self.buffer.len() {
+            // The replayed data only consists of whole data items, hence it's never read
+            // together with the data of the inner reader.
+            let end = core::cmp::min(self.buffer.len(), self.pos + want);
+            Ok(dec::Reference::Short(&self.buffer[self.pos..end]))
+        } else {
+            self.reader.fill(want)
+        }
+    }
+
+    #[inline]
+    fn advance(&mut self, n: usize) {
+        if self.pos < self.buffer.len() {
+            self.pos += n;
+            if self.pos >= self.buffer.len() {
+                self.buffer.clear();
+                self.pos = 0;
+            }
+        } else {
+            self.reader.advance(n)
+        }
+    }
+
+    #[inline]
+    fn step_in(&mut self) -> bool {
+        self.reader.step_in()
+    }
+
+    #[inline]
+    fn step_out(&mut self) {
+        self.reader.step_out()
+    }
+}
+
+/// Copies the encoding of a single data item from the reader.
+fn copy_item<'de, R: dec::Read<'de>>(
+    reader: &mut R,
+    out: &mut Vec<u8>,
+) -> Result<(), DecodeError<R::Error>> {
+    if !reader.step_in() {
+        return Err(DecodeError::DepthLimit);
+    }
+    let byte = pull_one(reader)?;
+    out.push(byte);
+    let info = byte & 0x1f;
+    let value = match info {
+        0..=23 => Some(u64::from(info)),
+        24..=27 => {
+            let start = out.len();
+            copy_bytes(reader, 1 << (info - 24), out)?;
+            Some(
+                out[start..]
+                    .iter()
+                    .fold(0u64, |value, byte| value << 8 | u64::from(*byte)),
+            )
+        }
+        31 => None,
+        _ => return Err(DecodeError::Unsupported { byte }),
+    };
+    match (dec::if_major(byte), value) {
+        (major::BYTES | major::STRING, Some(len)) => {
+            let len = usize::try_from(len).map_err(DecodeError::CastOverflow)?;
+            copy_bytes(reader, len, out)?;
+        }
+        (major::ARRAY, Some(len)) => {
+            for _ in 0..len {
+                copy_item(reader, out)?;
+            }
+        }
+        (major::MAP, Some(len)) => {
+            for _ in 0..len {
+                copy_item(reader, out)?;
+                copy_item(reader, out)?;
+            }
+        }
+        (major::TAG, Some(_)) => copy_item(reader, out)?,
+        (major::BYTES | major::STRING | major::ARRAY | major::MAP, None) => loop {
+            if peek_one(reader)? == marker::BREAK {
+                out.push(pull_one(reader)?);
+                break;
+            }
+            copy_item(reader, out)?;
+        },
+        (_, None) => return Err(DecodeError::Unsupported { byte }),
+        _ => {}
+    }
+    reader.step_out();
+    Ok(())
+}
+
+/// Copies the given number of bytes from the reader.
+fn copy_bytes<'de, R: dec::Read<'de>>(
+    reader: &mut R,
+    mut len: usize,
+    out: &mut Vec<u8>,
+) -> Result<(), DecodeError<R::Error>> {
+    while len > 0 {
+        let buf = match reader.fill(len)? {
+            dec::Reference::Long(buf) => buf,
+            dec::Reference::Short(buf) => buf,
+        };
+        if buf.is_empty() {
+            return Err(DecodeError::Eof);
+        }
+        let read = core::cmp::min(buf.len(), len);
+        out.extend_from_slice(&buf[..read]);
+        reader.advance(read);
+        len -= read;
+    }
+    Ok(())
+}
+
 impl<'de, R: dec::Read<'de>> Deserializer<R> {
     #[allow(clippy::type_complexity)]
     #[inline]
@@ -125,6 +385,45 @@ impl<'de, R: dec::Read<'de>> Deserializer<R> {
         }
     }
 
+    /// Visits the remaining entries of a map as struct, the fields that were reported as missing
+    /// before are decoded as null.
+    fn visit_struct<V>(
+        &mut self,
+        len: Option<usize>,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DecodeError<R::Error>>
+    where
+        V: Visitor<'de>,
+    {
+        let missing: Vec<_> = self
+            .missing_fields
+            .iter()
+            .filter(|(struct_fields, _)| *struct_fields == fields)
+            .map(|(_, field)| *field)
+            .collect();
+        let map = Accessor { de: self, len };
+        let result = if missing.is_empty() {
+            visitor.visit_map(map)
+        } else {
+            visitor.visit_map(StructAccessor::new(map, missing))
+        };
+        if result.is_err() && !self.struct_failed {
+            self.struct_failed = true;
+            if let Err(DecodeError::Msg(msg)) = &result {
+                let name = msg
+                    .strip_prefix("missing field `")
+                    .and_then(|name| name.strip_suffix('`'));
+                if let Some(field) = fields.iter().find(|field| Some(**field) == name) {
+                    if !self.missing_fields.contains(&(fields, *field)) {
+                        self.missing_field = Some((fields, field));
+                    }
+                }
+            }
+        }
+        result
+    }
+
     /// This method should be called after a value has been deserialized to ensure there is no
     /// trailing data in the input source.
     pub fn end(&mut self) -> Result<(), DecodeError<R::Error>> {
@@ -153,7 +452,7 @@ macro_rules! deserialize_type {
     };
 }
 
-impl<'de, 'a, R: dec::Read<'de>> serde::Deserializer<'de> for &'a mut Deserializer<R> {
+impl<'de, R: dec::Read<'de>> serde::Deserializer<'de> for &mut Deserializer<R> {
     type Error = DecodeError<R::Error>;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -377,27 +676,35 @@ impl<'de, 'a, R: dec::Read<'de>> serde::Deserializer<'de> for &'a mut Deserializ
     fn deserialize_struct<V>(
         self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_map(visitor)
+        if self.options.struct_tuples {
+            self.deserialize_tuple(fields.len(), visitor)
+        } else if self.options.missing_fields_as_none {
+            let mut de = self.try_step()?;
+            let len = dec::MapStart::decode(&mut de.reader)?.0;
+            de.visit_struct(len, fields, visitor)
+        } else {
+            self.deserialize_map(visitor)
+        }
     }
 
     #[inline]
     fn deserialize_enum<V>(
         self,
         _name: &'static str,
-        _variants: &'static [&'static str],
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
         let mut de = self.try_step()?;
-        let accessor = EnumAccessor::enum_(&mut de)?;
+        let accessor = EnumAccessor::enum_(&mut de, variants)?;
         visitor.visit_enum(accessor)
     }
 
@@ -539,26 +846,210 @@ impl<'de, 'a, R: dec::Read<'de>> de::MapAccess<'de> for Accessor<'a, R> {
     }
 }
 
+/// Accesses the entries of a struct encoded as map, followed by null values for the given fields
+/// if they are missing from it.
+struct StructAccessor<'a, R> {
+    map: Accessor<'a, R>,
+    missing: Vec<&'static str>,
+    /// Whether each of the missing fields was seen already.
+    seen: Vec<bool>,
+    /// Whether all entries of the map were read.
+    exhausted: bool,
+    /// The missing field whose value is accessed next.
+    field: &'static str,
+}
+
+impl<'a, R> StructAccessor<'a, R> {
+    fn new(map: Accessor<'a, R>, missing: Vec<&'static str>) -> Self {
+        StructAccessor {
+            map,
+            seen: vec![false; missing.len()],
+            missing,
+            exhausted: false,
+            field: "",
+        }
+    }
+}
+
+impl<'de, 'a, R: dec::Read<'de>> de::MapAccess<'de> for StructAccessor<'a, R> {
+    type Error = DecodeError<R::Error>;
+
+    #[inline]
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        if !self.exhausted {
+            self.exhausted = match self.map.len {
+                Some(len) => len == 0,
+                None if peek_one(&mut self.map.de.reader)? == marker::BREAK => {
+                    self.map.de.reader.advance(1);
+                    true
+                }
+                None => false,
+            };
+        }
+        if !self.exhausted {
+            return self.map.next_key_seed(FieldSeed {
+                seed,
+                fields: &self.missing,
+                seen: &mut self.seen,
+            });
+        }
+        match self.seen.iter().position(|seen| !seen) {
+            Some(index) => {
+                self.seen[index] = true;
+                self.field = self.missing[index];
+                let key = de::value::StrDeserializer::<Self::Error>::new(self.field);
+                Ok(Some(seed.deserialize(key)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    #[inline]
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        if self.exhausted {
+            seed.deserialize(MissingField(self.field, core::marker::PhantomData))
+        } else {
+            self.map.next_value_seed(seed)
+        }
+    }
+}
+
+/// Deserializes a map key and marks the field of the same name as seen.
+struct FieldSeed<'b, S> {
+    seed: S,
+    fields: &'b [&'static str],
+    seen: &'b mut [bool],
+}
+
+impl<'de, 'b, S: de::DeserializeSeed<'de>> de::DeserializeSeed<'de> for FieldSeed<'b, S> {
+    type Value = S::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let key: Cow<str> = de::Deserialize::deserialize(deserializer)?;
+        if let Some(index) = self.fields.iter().position(|field| *field == key) {
+            self.seen[index] = true;
+        }
+        self.seed.deserialize(de::value::StrDeserializer::new(&key))
+    }
+}
+
+/// The value of a struct field that is missing, it's null if an option is expected.
+struct MissingField<E>(&'static str, core::marker::PhantomData<E>);
+
+impl<'de, E: de::Error> de::Deserializer<'de> for MissingField<E> {
+    type Error = E;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::missing_field(self.0))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_none()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// The form an enum variant is encoded in.
+enum VariantForm {
+    /// The variant, followed by its value if it's not a unit variant.
+    Value,
+    /// A unit variant, encoded as its index.
+    Index(&'static str),
+    /// The variant as entry of a map with the given number of remaining entries.
+    Tagged(Option<usize>),
+}
+
 struct EnumAccessor<'a, R> {
     de: &'a mut Deserializer<R>,
+    form: VariantForm,
 }
 
 impl<'de, 'a, R: dec::Read<'de>> EnumAccessor<'a, R> {
     #[inline]
     pub fn enum_(
         de: &'a mut Deserializer<R>,
+        variants: &'static [&'static str],
     ) -> Result<EnumAccessor<'a, R>, DecodeError<R::Error>> {
         let byte = peek_one(&mut de.reader)?;
-        match dec::if_major(byte) {
+        let form = match (dec::if_major(byte), de.options.enum_representation) {
             // string
-            major::STRING => Ok(EnumAccessor { de }),
+            (major::STRING, EnumRepresentation::External)
+            | (major::STRING, EnumRepresentation::IndexedUnit)
+            | (major::STRING, EnumRepresentation::List) => VariantForm::Value,
+            // integer, the index of a unit variant
+            (major::UNSIGNED, EnumRepresentation::IndexedUnit) => {
+                let index = u64::decode(&mut de.reader)?;
+                let variant = usize::try_from(index)
+                    .ok()
+                    .and_then(|index| variants.get(index))
+                    .ok_or_else(|| {
+                        <DecodeError<R::Error> as de::Error>::invalid_value(
+                            de::Unexpected::Unsigned(index),
+                            &"variant index",
+                        )
+                    })?;
+                VariantForm::Index(variant)
+            }
             // 1 length map
-            major::MAP if byte == (major::MAP << 5) | 1 => {
+            (major::MAP, EnumRepresentation::External)
+            | (major::MAP, EnumRepresentation::IndexedUnit)
+                if byte == (major::MAP << 5) | 1 =>
+            {
                 de.reader.advance(1);
-                Ok(EnumAccessor { de })
+                VariantForm::Value
             }
-            _ => Err(DecodeError::TypeMismatch { name: "enum", byte }),
-        }
+            // 2 length array
+            (major::ARRAY, EnumRepresentation::List) if byte == (major::ARRAY << 5) | 2 => {
+                de.reader.advance(1);
+                VariantForm::Value
+            }
+            // map containing the tag
+            (major::MAP, EnumRepresentation::InternallyTagged(tag)) => {
+                let len = dec::MapStart::decode(&mut de.reader)?.0;
+                let mut expected = BufWriter::new(Vec::new());
+                tag.encode(&mut expected)
+                    .map_err(|_| DecodeError::Msg("Tag cannot be encoded.".into()))?;
+                // The entries before the tag, they are read again as part of the variant.
+                let mut ahead = Vec::new();
+                let mut count = 0;
+                loop {
+                    if !has_next(&mut de.reader, len, count)? {
+                        return Err(de::Error::missing_field(tag));
+                    }
+                    count += 1;
+                    let key_start = ahead.len();
+                    copy_item(&mut de.reader, &mut ahead)?;
+                    if ahead[key_start..] == *expected.buffer() {
+                        ahead.truncate(key_start);
+                        break;
+                    }
+                    copy_item(&mut de.reader, &mut ahead)?;
+                }
+                if !ahead.is_empty() {
+                    // The variant is read first, then the entries before the tag.
+                    let mut variant = Vec::new();
+                    copy_item(&mut de.reader, &mut variant)?;
+                    variant.extend_from_slice(&ahead);
+                    de.reader.replay(variant);
+                }
+                VariantForm::Tagged(len.map(|len| len - 1))
+            }
+            _ => return Err(DecodeError::TypeMismatch { name: "enum", byte }),
+        };
+        Ok(EnumAccessor { de, form })
     }
 }
 
@@ -574,7 +1065,12 @@ where
     where
         V: de::DeserializeSeed<'de>,
     {
-        let variant = seed.deserialize(&mut *self.de)?;
+        let variant = match self.form {
+            VariantForm::Index(variant) => {
+                seed.deserialize(de::value::StrDeserializer::<Self::Error>::new(variant))?
+            }
+            _ => seed.deserialize(&mut *self.de)?,
+        };
         Ok((variant, self))
     }
 }
@@ -587,7 +1083,18 @@ where
 
     #[inline]
     fn unit_variant(self) -> Result<(), Self::Error> {
-        Ok(())
+        match self.form {
+            VariantForm::Tagged(Some(0)) => Ok(()),
+            VariantForm::Tagged(None) if peek_one(&mut self.de.reader)? == marker::BREAK => {
+                self.de.reader.advance(1);
+                Ok(())
+            }
+            VariantForm::Tagged(_) => Err(de::Error::invalid_type(
+                de::Unexpected::Map,
+                &"unit variant",
+            )),
+            _ => Ok(()),
+        }
     }
 
     #[inline]
@@ -595,7 +1102,16 @@ where
     where
         T: de::DeserializeSeed<'de>,
     {
-        seed.deserialize(&mut *self.de)
+        match self.form {
+            VariantForm::Value => seed.deserialize(&mut *self.de),
+            VariantForm::Index(_) => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+            VariantForm::Tagged(len) => {
+                seed.deserialize(TaggedContent(Accessor { de: self.de, len }))
+            }
+        }
     }
 
     #[inline]
@@ -605,13 +1121,22 @@ where
     {
         use serde::Deserializer;
 
-        self.de.deserialize_tuple(len, visitor)
+        match self.form {
+            VariantForm::Value => self.de.deserialize_tuple(len, visitor),
+            VariantForm::Index(_) => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+            VariantForm::Tagged(_) => Err(DecodeError::Msg(
+                "Tuple variants cannot be internally tagged.".into(),
+            )),
+        }
     }
 
     #[inline]
     fn struct_variant<V>(
         self,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
@@ -619,7 +1144,37 @@ where
     {
         use serde::Deserializer;
 
-        self.de.deserialize_map(visitor)
+        match self.form {
+            VariantForm::Value => self.de.deserialize_struct("", fields, visitor),
+            VariantForm::Index(_) => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+            VariantForm::Tagged(len) => {
+                if self.de.options.missing_fields_as_none {
+                    self.de.visit_struct(len, fields, visitor)
+                } else {
+                    visitor.visit_map(Accessor { de: self.de, len })
+                }
+            }
+        }
+    }
+}
+
+/// Deserializes the remaining entries of an internally tagged variant as a map.
+struct TaggedContent<'a, R>(Accessor<'a, R>);
+
+impl<'de, 'a, R: dec::Read<'de>> de::Deserializer<'de> for TaggedContent<'a, R> {
+    type Error = DecodeError<R::Error>;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(self.0)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool byte_buf bytes char enum f32 f64 i8 i16 i32 i64 identifier ignored_any map
+        newtype_struct option seq str string struct tuple tuple_struct u8 u16 u32 u64 unit
+        unit_struct
     }
 }
 
@@ -665,7 +1220,9 @@ impl<'de, 'a, R: dec::Read<'de>> de::Deserializer<'de> for &'a mut CidDeserializ
         visitor: V,
     ) -> Result<V::Value, Self::Error> {
         if name == CID_SERDE_PRIVATE_IDENTIFIER {
-            self.deserialize_bytes(visitor)
+            // A `Cid` deserialized from within `Visitor::visit_newtype_struct` expects the newtype
+            // wrapper again.
+            visitor.visit_newtype_struct(self)
         } else {
             Err(de::Error::custom([
                 "This deserializer must not be called on newtype structs other than one named `",
@@ -0,0 +1,724 @@
+//! CBOR diagnostic notation.
+//!
+//! The [diagnostic notation] is a human readable representation of CBOR data, similar to JSON.
+//! It's useful for debugging and for writing test fixtures, as it can be parsed back into bytes.
+//!
+//! ```
+//! use serde_ipld_dagcbor::diag::{from_diag, to_diag};
+//!
+//! let bytes = from_diag(r#"{"a": [1, -2, h'0102'], "b": 1.5}"#).unwrap();
+//! assert_eq!(bytes, b"\xa2\x61a\x83\x01\x21\x42\x01\x02\x61b\xfb\x3f\xf8\0\0\0\0\0\0");
+//! assert_eq!(to_diag(&bytes).unwrap(), r#"{"a": [1, -2, h'0102'], "b": 1.5}"#);
+//! ```
+//!
+//! Besides the values supported by DAG-CBOR, also arbitrary tags, simple values, half and single
+//! precision floats (with the encoding indicators `_1` and `_2`), as well as indefinite length
+//! items (e.g. `[_ 1, 2]`) are supported. Comments are enclosed in slashes, e.g. `/ comment /`.
+//!
+//! Links can be written as `42(<cid>)`, e.g. `42(bafyreih4jspvxgh5xrp3wboocaajx5ym4ky7ok3gs5dsdkizgnegtanfxu)`.
+//! This is not part of the standard, but it's more readable than the bytes of the CID. Use
+//! [`DiagOptions::cid_links`] to print links this way.
+//!
+//! [diagnostic notation]: https://www.rfc-editor.org/rfc/rfc8949#name-diagnostic-notation
+#[cfg(not(feature = "std"))]
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::convert::{Infallible, TryFrom};
+use core::fmt::Write;
+
+use cbor4ii::core::dec::{self, Decode, Read};
+use cbor4ii::core::utils::SliceReader;
+use cbor4ii::core::{enc, major, types};
+use cid::Cid;
+
+use crate::cbor4ii_nonpub::{encode_head, marker, peek_one};
+use crate::error::{DecodeError, DiagError};
+use crate::CBOR_TAGS_CID;
+
+/// The maximum nesting depth of values.
+const MAX_DEPTH: usize = 256;
+
+/// Options for printing diagnostic notation.
+#[derive(Clone, Debug, Default)]
+pub struct DiagOptions {
+    /// Print links as `42(<cid>)` instead of `42(h'00<cid bytes>')`.
+    pub cid_links: bool,
+}
+
+/// Renders CBOR data as diagnostic notation.
+pub fn to_diag(bytes: &[u8]) -> Result<String, DecodeError<Infallible>> {
+    to_diag_with_options(bytes, &DiagOptions::default())
+}
+
+/// Renders CBOR data as diagnostic notation with the given options.
+///
+/// ```
+/// use serde_ipld_dagcbor::diag::{from_diag, to_diag_with_options, DiagOptions};
+///
+/// let link = "42(bafyreih4jspvxgh5xrp3wboocaajx5ym4ky7ok3gs5dsdkizgnegtanfxu)";
+/// let bytes = from_diag(link).unwrap();
+/// let options = DiagOptions { cid_links: true };
+/// assert_eq!(to_diag_with_options(&bytes, &options).unwrap(), link);
+/// ```
+pub fn to_diag_with_options(
+    bytes: &[u8],
+    options: &DiagOptions,
+) -> Result<String, DecodeError<Infallible>> {
+    let mut printer = Printer {
+        reader: SliceReader::new(bytes),
+        options,
+        out: String::new(),
+        depth: 0,
+    };
+    printer.value()?;
+    match peek_one(&mut printer.reader) {
+        Ok(_) => Err(DecodeError::TrailingData),
+        Err(DecodeError::Eof) => Ok(printer.out),
+        Err(error) => Err(error),
+    }
+}
+
+/// Parses diagnostic notation into CBOR data.
+pub fn from_diag(diag: &str) -> Result<Vec<u8>, DiagError> {
+    let mut parser = Parser {
+        input: diag.as_bytes(),
+        pos: 0,
+        out: Vec::new(),
+        depth: 0,
+    };
+    parser.skip_whitespace()?;
+    parser.value()?;
+    parser.skip_whitespace()?;
+    if parser.pos < diag.len() {
+        return Err(parser.syntax_error("end of input"));
+    }
+    Ok(parser.out)
+}
+
+/// Converts a half precision float into a double precision one.
+pub(crate) fn f16_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f64::from(bits & 0x3ff);
+    sign * match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        31 if mantissa == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1024.0 + mantissa) * 2f64.powi(exponent - 25),
+    }
+}
+
+/// Converts a double precision float into a half precision one, if it can be represented
+/// exactly.
+fn f64_to_f16(value: f64) -> Option<u16> {
+    let bits = value.to_bits();
+    let sign = ((bits >> 48) & 0x8000) as u16;
+    if value.is_nan() {
+        return Some(0x7e00);
+    }
+    if value.is_infinite() {
+        return Some(sign | 0x7c00);
+    }
+    if value == 0.0 {
+        return Some(sign);
+    }
+    let exponent = ((bits >> 52) & 0x7ff) as i32 - 1023;
+    let mantissa = bits & 0x000f_ffff_ffff_ffff;
+    match exponent {
+        -14..=15 if mantissa.trailing_zeros() >= 42 => {
+            Some(sign | ((exponent + 15) as u16) << 10 | (mantissa >> 42) as u16)
+        }
+        // Subnormal numbers.
+        -24..=-15 => {
+            let shift = 52 - (exponent + 24);
+            let mantissa = mantissa | 1 << 52;
+            if mantissa.trailing_zeros() >= shift as u32 {
+                Some(sign | (mantissa >> shift) as u16)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+struct Printer<'a> {
+    reader: SliceReader<'a>,
+    options: &'a DiagOptions,
+    out: String,
+    depth: usize,
+}
+
+impl<'a> Printer<'a> {
+    /// Returns true and consumes the break byte if it's next.
+    fn is_break(&mut self) -> Result<bool, DecodeError<Infallible>> {
+        if peek_one(&mut self.reader)? == marker::BREAK {
+            self.reader.advance(1);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn value(&mut self) -> Result<(), DecodeError<Infallible>> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(DecodeError::DepthLimit);
+        }
+
+        let byte = peek_one(&mut self.reader)?;
+        let major = dec::if_major(byte);
+        match byte & 0x1f {
+            // Reserved additional information.
+            28..=30 => return Err(DecodeError::Unsupported { byte }),
+            marker::START
+                if !matches!(
+                    major,
+                    major::BYTES | major::STRING | major::ARRAY | major::MAP
+                ) =>
+            {
+                return Err(DecodeError::Unsupported { byte })
+            }
+            _ => {}
+        }
+
+        match major {
+            major::UNSIGNED => {
+                let _ = write!(self.out, "{}", u64::decode(&mut self.reader)?);
+            }
+            major::NEGATIVE => {
+                let _ = write!(self.out, "{}", i128::decode(&mut self.reader)?);
+            }
+            major::BYTES | major::STRING if byte & 0x1f == marker::START => {
+                self.reader.advance(1);
+                self.out.push_str("(_ ");
+                let mut first = true;
+                while !self.is_break()? {
+                    let chunk = peek_one(&mut self.reader)?;
+                    // The chunks must be definite length items of the same type.
+                    if dec::if_major(chunk) != major || chunk & 0x1f == marker::START {
+                        return Err(DecodeError::Unsupported { byte: chunk });
+                    }
+                    if !first {
+                        self.out.push_str(", ");
+                    }
+                    first = false;
+                    self.value()?;
+                }
+                self.out.push(')');
+            }
+            major::BYTES => {
+                let bytes = <types::Bytes<&[u8]>>::decode(&mut self.reader)?.0;
+                self.bytes(bytes);
+            }
+            major::STRING => {
+                let string = <&str>::decode(&mut self.reader)?;
+                self.string(string);
+            }
+            major::ARRAY => {
+                let len = dec::ArrayStart::decode(&mut self.reader)?.0;
+                self.out.push('[');
+                if len.is_none() {
+                    self.out.push_str("_ ");
+                }
+                let mut count = 0;
+                while !self.is_end(len, count)? {
+                    if count > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.value()?;
+                    count += 1;
+                }
+                self.out.push(']');
+            }
+            major::MAP => {
+                let len = dec::MapStart::decode(&mut self.reader)?.0;
+                self.out.push('{');
+                if len.is_none() {
+                    self.out.push_str("_ ");
+                }
+                let mut count = 0;
+                while !self.is_end(len, count)? {
+                    if count > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.value()?;
+                    self.out.push_str(": ");
+                    self.value()?;
+                    count += 1;
+                }
+                self.out.push('}');
+            }
+            major::TAG => {
+                let tag = dec::TagStart::decode(&mut self.reader)?.0;
+                let _ = write!(self.out, "{}(", tag);
+                self.tagged(tag)?;
+                self.out.push(')');
+            }
+            _ => match byte {
+                marker::FALSE | marker::TRUE => {
+                    let value = bool::decode(&mut self.reader)?;
+                    self.out.push_str(if value { "true" } else { "false" });
+                }
+                marker::NULL => {
+                    self.reader.advance(1);
+                    self.out.push_str("null");
+                }
+                marker::UNDEFINED => {
+                    self.reader.advance(1);
+                    self.out.push_str("undefined");
+                }
+                marker::F16 => {
+                    let bits = types::F16::decode(&mut self.reader)?.0;
+                    self.float(f16_to_f64(bits), "_1");
+                }
+                marker::F32 => {
+                    let value = f32::decode(&mut self.reader)?;
+                    self.float(f64::from(value), "_2");
+                }
+                marker::F64 => {
+                    let value = f64::decode(&mut self.reader)?;
+                    self.float(value, "");
+                }
+                _ => {
+                    let value = types::Simple::decode(&mut self.reader)?.0;
+                    let _ = write!(self.out, "simple({})", value);
+                }
+            },
+        }
+
+        self.depth -= 1;
+        Ok(())
+    }
+
+    /// Returns whether all elements of a list or map were read.
+    fn is_end(
+        &mut self,
+        len: Option<usize>,
+        count: usize,
+    ) -> Result<bool, DecodeError<Infallible>> {
+        match len {
+            Some(len) => Ok(count == len),
+            None => self.is_break(),
+        }
+    }
+
+    /// Prints the content of a tag. Links are printed as CIDs if the options say so.
+    fn tagged(&mut self, tag: u64) -> Result<(), DecodeError<Infallible>> {
+        if tag == CBOR_TAGS_CID && self.options.cid_links {
+            let byte = peek_one(&mut self.reader)?;
+            if dec::if_major(byte) == major::BYTES && byte & 0x1f != marker::START {
+                let bytes = <types::Bytes<&[u8]>>::decode(&mut self.reader)?.0;
+                if let Some((0, cid)) = bytes.split_first() {
+                    if let Ok(cid) = Cid::try_from(cid) {
+                        self.out.push_str(&cid.to_string());
+                        return Ok(());
+                    }
+                }
+                // It's not a valid CID, print it as it is.
+                self.bytes(bytes);
+                return Ok(());
+            }
+        }
+        self.value()
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.out.push_str("h'");
+        for byte in bytes {
+            let _ = write!(self.out, "{:02x}", byte);
+        }
+        self.out.push('\'');
+    }
+
+    fn string(&mut self, string: &str) {
+        // Strings are escaped the same way as in JSON.
+        let escaped = crate::json::to_vec(string).expect("strings can always be encoded");
+        self.out
+            .push_str(core::str::from_utf8(&escaped).expect("escaped strings are valid UTF-8"));
+    }
+
+    fn float(&mut self, value: f64, indicator: &str) {
+        if value.is_nan() {
+            self.out.push_str("NaN");
+        } else if value.is_infinite() {
+            self.out
+                .push_str(if value > 0.0 { "Infinity" } else { "-Infinity" });
+        } else {
+            // The debug representation always contains a decimal point or an exponent.
+            let _ = write!(self.out, "{:?}", value);
+        }
+        self.out.push_str(indicator);
+    }
+}
+
+/// Writes encoded heads into the output of the parser.
+struct VecWriter<'a>(&'a mut Vec<u8>);
+
+impl enc::Write for VecWriter<'_> {
+    type Error = Infallible;
+
+    fn push(&mut self, input: &[u8]) -> Result<(), Self::Error> {
+        self.0.extend_from_slice(input);
+        Ok(())
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    out: Vec<u8>,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn syntax_error(&self, expected: &'static str) -> DiagError {
+        DiagError::Syntax {
+            position: self.pos,
+            expected,
+        }
+    }
+
+    fn invalid(&self, position: usize, reason: &'static str) -> DiagError {
+        DiagError::Invalid { position, reason }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    /// Skips whitespace and comments.
+    fn skip_whitespace(&mut self) -> Result<(), DiagError> {
+        while let Some(byte) = self.peek() {
+            match byte {
+                b' ' | b'\n' | b'\r' | b'\t' => self.pos += 1,
+                b'/' => {
+                    let end = self.input[self.pos + 1..]
+                        .iter()
+                        .position(|byte| *byte == b'/')
+                        .ok_or_else(|| self.syntax_error("end of comment"))?;
+                    self.pos += end + 2;
+                }
+                _ => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Consumes the byte if it's next, skipping whitespace before and after.
+    fn eat(&mut self, expected: u8) -> Result<bool, DiagError> {
+        self.skip_whitespace()?;
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            self.skip_whitespace()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, expected: u8, description: &'static str) -> Result<(), DiagError> {
+        if self.eat(expected)? {
+            Ok(())
+        } else {
+            Err(self.syntax_error(description))
+        }
+    }
+
+    fn write_head(&mut self, major: u8, argument: u64) {
+        encode_head(&mut VecWriter(&mut self.out), major, argument)
+            .unwrap_or_else(|never| match never {});
+    }
+
+    fn identifier(&mut self) -> &str {
+        let start = self.pos;
+        while self.peek().is_some_and(|byte| byte.is_ascii_alphanumeric()) {
+            self.pos += 1;
+        }
+        core::str::from_utf8(&self.input[start..self.pos]).expect("identifiers are ASCII")
+    }
+
+    fn value(&mut self) -> Result<(), DiagError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.invalid(self.pos, "nested too deeply"));
+        }
+
+        match self.peek() {
+            Some(b'[') => {
+                self.pos += 1;
+                self.items(major::ARRAY, b']')?;
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                self.items(major::MAP, b'}')?;
+            }
+            Some(b'"') => self.string()?,
+            Some(b'h') if self.input.get(self.pos + 1) == Some(&b'\'') => self.bytes()?,
+            Some(b'(') => self.chunks()?,
+            Some(b'-' | b'0'..=b'9') => self.number()?,
+            Some(byte) if byte.is_ascii_alphabetic() => {
+                let start = self.pos;
+                match self.identifier() {
+                    "false" => self.out.push(0xf4),
+                    "true" => self.out.push(0xf5),
+                    "null" => self.out.push(0xf6),
+                    "undefined" => self.out.push(0xf7),
+                    "simple" => {
+                        self.expect(b'(', "`(`")?;
+                        let position = self.pos;
+                        let value = self.unsigned()?;
+                        match value {
+                            0..=19 => self.out.push(0xe0 | value as u8),
+                            32..=255 => self.out.extend([0xf8, value as u8]),
+                            _ => return Err(self.invalid(position, "reserved simple value")),
+                        }
+                        self.expect(b')', "`)`")?;
+                    }
+                    "Infinity" | "NaN" => {
+                        self.pos = start;
+                        self.number()?;
+                    }
+                    _ => return Err(self.invalid(start, "unknown identifier")),
+                }
+            }
+            _ => return Err(self.syntax_error("a value")),
+        }
+
+        self.depth -= 1;
+        Ok(())
+    }
+
+    /// Parses the elements of a list or the entries of a map, the opening bracket was already
+    /// consumed.
+    fn items(&mut self, major: u8, close: u8) -> Result<(), DiagError> {
+        let indefinite = self.eat(b'_')?;
+        // The number of items is only known at the end, hence they are parsed into a separate
+        // buffer first.
+        let start = self.out.len();
+        let mut count = 0;
+        while !self.eat(close)? {
+            if count > 0 {
+                self.expect(b',', "`,`")?;
+            }
+            self.value()?;
+            if major == major::MAP {
+                self.expect(b':', "`:`")?;
+                self.value()?;
+            }
+            count += 1;
+        }
+        let items = self.out.split_off(start);
+        if indefinite {
+            self.out.push(major << 5 | marker::START);
+            self.out.extend(items);
+            self.out.push(marker::BREAK);
+        } else {
+            self.write_head(major, count);
+            self.out.extend(items);
+        }
+        Ok(())
+    }
+
+    fn string(&mut self) -> Result<(), DiagError> {
+        let start = self.pos;
+        let mut end = start + 1;
+        loop {
+            match self.input.get(end) {
+                Some(b'"') => break,
+                Some(b'\\') => end += 2,
+                Some(_) => end += 1,
+                None => return Err(self.syntax_error("end of string")),
+            }
+        }
+        self.pos = end + 1;
+        // Strings use the same escape sequences as JSON.
+        let string: String = crate::json::from_slice(&self.input[start..self.pos])
+            .map_err(|_| self.invalid(start, "invalid string"))?;
+        self.write_head(major::STRING, string.len() as u64);
+        self.out.extend(string.as_bytes());
+        Ok(())
+    }
+
+    fn bytes(&mut self) -> Result<(), DiagError> {
+        let start = self.pos;
+        // Skip `h'`.
+        self.pos += 2;
+        let mut bytes = Vec::new();
+        let mut high = None;
+        loop {
+            let byte = self.peek().ok_or_else(|| self.syntax_error("`'`"))?;
+            self.pos += 1;
+            match byte {
+                b'\'' => break,
+                b' ' | b'\n' | b'\r' | b'\t' => {}
+                _ => {
+                    let digit = char::from(byte)
+                        .to_digit(16)
+                        .ok_or_else(|| self.invalid(self.pos - 1, "invalid hex digit"))?
+                        as u8;
+                    match high.take() {
+                        Some(high) => bytes.push(high << 4 | digit),
+                        None => high = Some(digit),
+                    }
+                }
+            }
+        }
+        if high.is_some() {
+            return Err(self.invalid(start, "odd number of hex digits"));
+        }
+        self.write_head(major::BYTES, bytes.len() as u64);
+        self.out.extend(bytes);
+        Ok(())
+    }
+
+    /// Parses an indefinite length byte or text string, e.g. `(_ "a", "b")`.
+    fn chunks(&mut self) -> Result<(), DiagError> {
+        self.pos += 1;
+        self.expect(b'_', "`_`")?;
+        let start = self.out.len();
+        self.out.push(0);
+        let mut chunks_major = None;
+        let mut first = true;
+        while !self.eat(b')')? {
+            if !first {
+                self.expect(b',', "`,`")?;
+            }
+            first = false;
+            let position = self.pos;
+            let chunk_start = self.out.len();
+            match self.peek() {
+                Some(b'"') => self.string()?,
+                Some(b'h') => self.bytes()?,
+                _ => return Err(self.syntax_error("a string")),
+            }
+            let chunk_major = dec::if_major(self.out[chunk_start]);
+            if *chunks_major.get_or_insert(chunk_major) != chunk_major {
+                return Err(self.invalid(position, "chunks of different types"));
+            }
+        }
+        self.out[start] = chunks_major.unwrap_or(major::BYTES) << 5 | marker::START;
+        self.out.push(marker::BREAK);
+        Ok(())
+    }
+
+    fn unsigned(&mut self) -> Result<u64, DiagError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        core::str::from_utf8(&self.input[start..self.pos])
+            .expect("digits are ASCII")
+            .parse()
+            .map_err(|_| self.invalid(start, "invalid unsigned integer"))
+    }
+
+    /// Parses integers, floats and tags.
+    fn number(&mut self) -> Result<(), DiagError> {
+        let start = self.pos;
+        let negative = self.peek() == Some(b'-');
+        if negative {
+            self.pos += 1;
+        }
+        let is_float;
+        if self.peek().is_some_and(|byte| byte.is_ascii_alphabetic()) {
+            is_float = true;
+            if !matches!(self.identifier(), "Infinity" | "NaN") {
+                return Err(self.invalid(start, "invalid number"));
+            }
+        } else {
+            let digits_start = self.pos;
+            while self
+                .peek()
+                .is_some_and(|byte| byte.is_ascii_digit() || b".eE+-".contains(&byte))
+            {
+                self.pos += 1;
+            }
+            is_float = self.input[digits_start..self.pos]
+                .iter()
+                .any(|byte| b".eE".contains(byte));
+        }
+        let number = core::str::from_utf8(&self.input[start..self.pos]).expect("numbers are ASCII");
+
+        if is_float {
+            let value: f64 = number
+                .parse()
+                .map_err(|_| self.invalid(start, "invalid float"))?;
+            self.float(start, value)
+        } else if negative {
+            let value: i128 = number
+                .parse()
+                .map_err(|_| self.invalid(start, "invalid integer"))?;
+            let argument = u64::try_from(-1 - value)
+                .map_err(|_| self.invalid(start, "integer out of range"))?;
+            self.write_head(major::NEGATIVE, argument);
+            Ok(())
+        } else {
+            let value: u64 = number
+                .parse()
+                .map_err(|_| self.invalid(start, "integer out of range"))?;
+            if self.eat(b'(')? {
+                self.write_head(major::TAG, value);
+                self.tagged(value)?;
+                self.expect(b')', "`)`")
+            } else {
+                self.write_head(major::UNSIGNED, value);
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes a float, taking the encoding indicator into account.
+    fn float(&mut self, start: usize, value: f64) -> Result<(), DiagError> {
+        let indicator = if self.peek() == Some(b'_') {
+            self.pos += 1;
+            let indicator = self.peek();
+            self.pos += 1;
+            indicator
+        } else {
+            None
+        };
+        match indicator {
+            Some(b'1') => {
+                let bits = f64_to_f16(value)
+                    .ok_or_else(|| self.invalid(start, "not a half precision float"))?;
+                self.out.push(0xf9);
+                self.out.extend(bits.to_be_bytes());
+            }
+            Some(b'2') => {
+                let single = value as f32;
+                if f64::from(single) != value && !value.is_nan() {
+                    return Err(self.invalid(start, "not a single precision float"));
+                }
+                self.out.push(0xfa);
+                self.out.extend(single.to_bits().to_be_bytes());
+            }
+            None | Some(b'3') => {
+                self.out.push(0xfb);
+                self.out.extend(value.to_bits().to_be_bytes());
+            }
+            _ => return Err(self.invalid(start, "invalid encoding indicator")),
+        }
+        Ok(())
+    }
+
+    /// Parses the content of a tag, which might be a CID.
+    fn tagged(&mut self, tag: u64) -> Result<(), DiagError> {
+        let start = self.pos;
+        if tag == CBOR_TAGS_CID && self.peek().is_some_and(|byte| byte.is_ascii_alphabetic()) {
+            let identifier = self.identifier();
+            if let Ok(cid) = Cid::try_from(identifier) {
+                let bytes = cid.to_bytes();
+                self.write_head(major::BYTES, bytes.len() as u64 + 1);
+                self.out.push(0);
+                self.out.extend(bytes);
+                return Ok(());
+            }
+            // It might be something like `h'...'`.
+            self.pos = start;
+        }
+        self.value()
+    }
+}
@@ -1,5 +1,7 @@
 //! When serializing or deserializing DAG-CBOR goes wrong.
 
+#[cfg(all(feature = "std", feature = "hash"))]
+use core::convert::Infallible;
 use core::fmt;
 use core::num::TryFromIntError;
 
@@ -119,6 +121,8 @@ pub enum DecodeError<E> {
     },
     /// Recursion limit reached.
     DepthLimit,
+    /// Size limit reached.
+    SizeLimit,
     /// Trailing data.
     TrailingData,
 }
@@ -194,3 +198,361 @@ impl<E: fmt::Debug> From<cbor4ii::DecodeError<E>> for DecodeError<E> {
         }
     }
 }
+
+/// A block verification error.
+#[derive(Debug)]
+pub enum VerifyError {
+    /// The CID doesn't use the DAG-CBOR codec.
+    Codec(u64),
+    /// The hash function of the CID is not supported.
+    UnsupportedHash(u64),
+    /// The hash of the data doesn't match the hash of the CID.
+    HashMismatch,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerifyError {}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// An error when reading or writing CAR files.
+#[cfg(all(feature = "std", feature = "hash"))]
+#[derive(Debug)]
+pub enum CarError {
+    /// IO error.
+    Io(std::io::Error),
+    /// The header could not be decoded.
+    Decode(DecodeError<Infallible>),
+    /// The header could not be encoded.
+    Encode(EncodeError<std::collections::TryReserveError>),
+    /// Invalid CID.
+    Cid(cid::Error),
+    /// The version of the CAR file is not supported.
+    UnsupportedVersion(u64),
+    /// Invalid varint.
+    InvalidVarint,
+    /// The section length is invalid.
+    InvalidSectionLength,
+    /// The CARv2 header is invalid.
+    InvalidHeader,
+    /// The CARv2 index is invalid.
+    InvalidIndex,
+    /// The CARv2 index format is not supported.
+    UnsupportedIndex(u64),
+    /// A block doesn't match its CID.
+    Verify(cid::Cid, VerifyError),
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl From<std::io::Error> for CarError {
+    fn from(err: std::io::Error) -> CarError {
+        CarError::Io(err)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl From<DecodeError<Infallible>> for CarError {
+    fn from(err: DecodeError<Infallible>) -> CarError {
+        CarError::Decode(err)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl From<EncodeError<std::collections::TryReserveError>> for CarError {
+    fn from(err: EncodeError<std::collections::TryReserveError>) -> CarError {
+        CarError::Encode(err)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl From<cid::Error> for CarError {
+    fn from(err: cid::Error) -> CarError {
+        CarError::Cid(err)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl std::error::Error for CarError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CarError::Io(err) => Some(err),
+            CarError::Decode(err) => Some(err),
+            CarError::Encode(err) => Some(err),
+            CarError::Cid(err) => Some(err),
+            CarError::Verify(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl fmt::Display for CarError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// An error when storing or loading blocks.
+#[cfg(all(feature = "std", feature = "hash"))]
+#[derive(Debug)]
+pub enum BlockstoreError {
+    /// IO error.
+    Io(std::io::Error),
+    /// A value could not be decoded.
+    Decode(DecodeError<Infallible>),
+    /// A value could not be encoded.
+    Encode(EncodeError<std::collections::TryReserveError>),
+    /// The block is not in the store.
+    NotFound(cid::Cid),
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl From<std::io::Error> for BlockstoreError {
+    fn from(err: std::io::Error) -> BlockstoreError {
+        BlockstoreError::Io(err)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl From<DecodeError<Infallible>> for BlockstoreError {
+    fn from(err: DecodeError<Infallible>) -> BlockstoreError {
+        BlockstoreError::Decode(err)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl From<EncodeError<std::collections::TryReserveError>> for BlockstoreError {
+    fn from(err: EncodeError<std::collections::TryReserveError>) -> BlockstoreError {
+        BlockstoreError::Encode(err)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl std::error::Error for BlockstoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BlockstoreError::Io(err) => Some(err),
+            BlockstoreError::Decode(err) => Some(err),
+            BlockstoreError::Encode(err) => Some(err),
+            BlockstoreError::NotFound(_) => None,
+        }
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl fmt::Display for BlockstoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// An error when resolving paths or evaluating selectors.
+#[cfg(all(feature = "std", feature = "hash"))]
+#[derive(Debug)]
+pub enum TraversalError {
+    /// A block could not be loaded.
+    Blockstore(BlockstoreError),
+    /// A block could not be decoded.
+    Decode(DecodeError<Infallible>),
+    /// The path doesn't exist, it contains the segments up to the first missing one.
+    NotFound(crate::path::Path),
+    /// The selector is not valid.
+    InvalidSelector(String),
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl From<BlockstoreError> for TraversalError {
+    fn from(err: BlockstoreError) -> TraversalError {
+        TraversalError::Blockstore(err)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl From<DecodeError<Infallible>> for TraversalError {
+    fn from(err: DecodeError<Infallible>) -> TraversalError {
+        TraversalError::Decode(err)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl std::error::Error for TraversalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TraversalError::Blockstore(err) => Some(err),
+            TraversalError::Decode(err) => Some(err),
+            TraversalError::NotFound(_) | TraversalError::InvalidSelector(_) => None,
+        }
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl fmt::Display for TraversalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// An error when parsing a schema or validating data against it.
+#[cfg(all(feature = "std", feature = "hash"))]
+#[derive(Debug)]
+pub enum SchemaError {
+    /// The schema DSL is malformed.
+    Syntax {
+        /// The byte offset of the error.
+        position: usize,
+        /// What was expected at that position.
+        expected: &'static str,
+    },
+    /// The schema is not valid, e.g. it references an undefined type.
+    Invalid(String),
+    /// The data could not be decoded.
+    Decode(DecodeError<Infallible>),
+    /// The value could not be encoded.
+    Encode(EncodeError<std::collections::TryReserveError>),
+    /// A linked block could not be loaded.
+    Blockstore(BlockstoreError),
+    /// The data doesn't match the schema.
+    Mismatch {
+        /// The path to the node that doesn't match.
+        path: crate::path::Path,
+        /// Why it doesn't match.
+        reason: String,
+    },
+    /// The schema uses a feature the code generator doesn't support.
+    Unsupported(String),
+    /// A schema or generated file could not be read or written.
+    Io(std::io::Error),
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl From<DecodeError<Infallible>> for SchemaError {
+    fn from(err: DecodeError<Infallible>) -> SchemaError {
+        SchemaError::Decode(err)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl From<EncodeError<std::collections::TryReserveError>> for SchemaError {
+    fn from(err: EncodeError<std::collections::TryReserveError>) -> SchemaError {
+        SchemaError::Encode(err)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl From<BlockstoreError> for SchemaError {
+    fn from(err: BlockstoreError) -> SchemaError {
+        SchemaError::Blockstore(err)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl From<std::io::Error> for SchemaError {
+    fn from(err: std::io::Error) -> SchemaError {
+        SchemaError::Io(err)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl std::error::Error for SchemaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SchemaError::Decode(err) => Some(err),
+            SchemaError::Encode(err) => Some(err),
+            SchemaError::Blockstore(err) => Some(err),
+            SchemaError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// An error when transcoding between DAG-CBOR and DAG-JSON.
+#[derive(Debug)]
+pub enum TranscodeError<R, W> {
+    /// The input could not be decoded.
+    Decode(DecodeError<R>),
+    /// The output could not be encoded.
+    Encode(EncodeError<W>),
+}
+
+impl<R, W> From<DecodeError<R>> for TranscodeError<R, W> {
+    fn from(err: DecodeError<R>) -> TranscodeError<R, W> {
+        TranscodeError::Decode(err)
+    }
+}
+
+impl<R, W> From<EncodeError<W>> for TranscodeError<R, W> {
+    fn from(err: EncodeError<W>) -> TranscodeError<R, W> {
+        TranscodeError::Encode(err)
+    }
+}
+
+impl<R: fmt::Debug, W> From<cbor4ii::DecodeError<R>> for TranscodeError<R, W> {
+    fn from(err: cbor4ii::DecodeError<R>) -> TranscodeError<R, W> {
+        TranscodeError::Decode(err.into())
+    }
+}
+
+impl<R, W: fmt::Debug> From<cbor4ii::EncodeError<W>> for TranscodeError<R, W> {
+    fn from(err: cbor4ii::EncodeError<W>) -> TranscodeError<R, W> {
+        TranscodeError::Encode(err.into())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R, W> std::error::Error for TranscodeError<R, W>
+where
+    R: std::error::Error + 'static,
+    W: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TranscodeError::Decode(err) => Some(err),
+            TranscodeError::Encode(err) => Some(err),
+        }
+    }
+}
+
+impl<R: fmt::Debug, W: fmt::Debug> fmt::Display for TranscodeError<R, W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// An error when parsing diagnostic notation.
+#[derive(Debug)]
+pub enum DiagError {
+    /// Unexpected input at the given byte offset.
+    Syntax {
+        /// The byte offset within the input.
+        position: usize,
+        /// What was expected instead.
+        expected: &'static str,
+    },
+    /// An invalid value at the given byte offset, e.g. an integer that is out of range.
+    Invalid {
+        /// The byte offset within the input.
+        position: usize,
+        /// Why the value is invalid.
+        reason: &'static str,
+    },
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DiagError {}
+
+impl fmt::Display for DiagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
@@ -0,0 +1,470 @@
+//! Annotated hex dumps of DAG-CBOR data.
+//!
+//! Every data item is listed with its byte offset, its raw bytes, its major type and argument, as
+//! well as the decoded value, similar to [cbor.me]. Violations of the [DAG-CBOR spec], e.g. a
+//! length that isn't encoded in the smallest possible way, or map keys that aren't sorted, are
+//! flagged inline. If the data cannot be decoded at all, the dump ends with the error and its
+//! offset.
+//!
+//! ```
+//! use serde_ipld_dagcbor::explain::{explain, Violation};
+//!
+//! // {"b": 1, "a": 2} with the length of the "a" encoded in an additional byte.
+//! let explanation = explain(b"\xa2\x61b\x01\x78\x01a\x02");
+//! let violations: Vec<_> = explanation.violations().collect();
+//! assert_eq!(
+//!     violations,
+//!     [(4, &Violation::NonMinimalArgument), (4, &Violation::UnsortedKey)]
+//! );
+//! assert_eq!(
+//!     explanation.to_string(),
+//!     "\
+//! 00000000  a2        # map(2)
+//! 00000001     61     #   text(1)
+//! 00000002        62  #     \"b\"
+//! 00000003     01     #   unsigned(1)
+//! 00000004     78 01  #   text(1) !! NonMinimalArgument !! UnsortedKey
+//! 00000006        61  #     \"a\"
+//! 00000007     02     #   unsigned(2)
+//! "
+//! );
+//! ```
+//!
+//! [cbor.me]: https://cbor.me
+//! [DAG-CBOR spec]: https://ipld.io/specs/codecs/dag-cbor/spec/
+#[cfg(not(feature = "std"))]
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::convert::{Infallible, TryFrom};
+use core::fmt::{self, Write};
+
+use cbor4ii::core::dec;
+use cbor4ii::core::major;
+use cbor4ii::core::utils::SliceReader;
+use cid::Cid;
+
+use crate::cbor4ii_nonpub::{marker, peek_one, pull_one};
+use crate::diag::f16_to_f64;
+use crate::error::DecodeError;
+use crate::CBOR_TAGS_CID;
+
+/// The additional information of items with indefinite length.
+const INDEFINITE: u8 = 31;
+
+/// A violation of the DAG-CBOR spec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// The argument (e.g. an integer or a length) isn't encoded in the smallest possible number
+    /// of bytes.
+    NonMinimalArgument,
+    /// Strings, lists and maps must have a definite length.
+    IndefiniteLength,
+    /// A text string that isn't valid UTF-8.
+    InvalidUtf8,
+    /// Map keys must be strings.
+    NonStringKey,
+    /// Map keys must be sorted length-first and then bytewise.
+    UnsortedKey,
+    /// Map keys must be unique.
+    DuplicateKey,
+    /// Only tag 42 (CID) is allowed.
+    UnsupportedTag(u64),
+    /// Tag 42 must contain a null byte prefixed CID.
+    InvalidCid,
+    /// Floats must be encoded with double precision.
+    NonDoublePrecisionFloat,
+    /// NaN and infinities are not allowed.
+    NonFiniteFloat,
+    /// Only the simple values `false`, `true` and `null` are allowed.
+    UnsupportedSimpleValue,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// A single line of an annotated dump.
+#[derive(Debug, Clone)]
+pub struct Line {
+    /// The byte offset of the first byte of this line.
+    pub offset: usize,
+    /// The nesting depth.
+    pub depth: usize,
+    /// The raw bytes, i.e. the head of a data item or the content of a string.
+    pub bytes: Vec<u8>,
+    /// A description of the bytes, e.g. `map(2)` or the decoded value.
+    pub description: String,
+    /// The violations of the DAG-CBOR spec.
+    pub violations: Vec<Violation>,
+}
+
+/// An annotated dump of DAG-CBOR data.
+///
+/// The [`Display`](fmt::Display) implementation renders it as an annotated hex dump.
+#[derive(Debug)]
+pub struct Explanation {
+    /// The lines of the dump.
+    pub lines: Vec<Line>,
+    /// The error that stopped the decoding together with its byte offset, if any.
+    pub error: Option<(usize, DecodeError<Infallible>)>,
+}
+
+impl Explanation {
+    /// Returns all violations together with their byte offset.
+    pub fn violations(&self) -> impl Iterator<Item = (usize, &Violation)> {
+        self.lines.iter().flat_map(|line| {
+            line.violations
+                .iter()
+                .map(move |violation| (line.offset, violation))
+        })
+    }
+
+    /// Returns true if the data is valid DAG-CBOR.
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none() && self.violations().next().is_none()
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hex: Vec<String> = self
+            .lines
+            .iter()
+            .map(|line| {
+                let mut hex = " ".repeat(line.depth * 3);
+                for (ii, byte) in line.bytes.iter().enumerate() {
+                    if ii > 0 {
+                        hex.push(' ');
+                    }
+                    let _ = write!(hex, "{:02x}", byte);
+                }
+                hex
+            })
+            .collect();
+        let width = hex.iter().map(String::len).max().unwrap_or(0);
+        for (line, hex) in self.lines.iter().zip(hex) {
+            write!(
+                f,
+                "{:08x}  {:width$}  # {:indent$}{}",
+                line.offset,
+                hex,
+                "",
+                line.description,
+                width = width,
+                indent = line.depth * 2
+            )?;
+            for violation in &line.violations {
+                write!(f, " !! {}", violation)?;
+            }
+            writeln!(f)?;
+        }
+        if let Some((offset, error)) = &self.error {
+            writeln!(f, "{:08x}  !! {}", offset, error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Explains CBOR data in a slice.
+///
+/// Decoding continues after violations of the DAG-CBOR spec, it only stops at errors that make
+/// the rest of the data unreadable.
+pub fn explain(bytes: &[u8]) -> Explanation {
+    let mut explainer = Explainer {
+        reader: SliceReader::new(bytes),
+        pos: 0,
+        lines: Vec::new(),
+    };
+    let result = explainer.item(0).and_then(|_| explainer.end());
+    Explanation {
+        error: result.err().map(|error| (explainer.pos, error)),
+        lines: explainer.lines,
+    }
+}
+
+/// The content of a byte or text string with definite length.
+struct Content {
+    major: u8,
+    bytes: Vec<u8>,
+    /// The index of the line of the content.
+    line: usize,
+}
+
+struct Explainer<R> {
+    reader: R,
+    /// The number of bytes read so far.
+    pos: usize,
+    lines: Vec<Line>,
+}
+
+impl<'de, R: dec::Read<'de>> Explainer<R> {
+    fn take(&mut self, len: usize) -> Result<Vec<u8>, DecodeError<R::Error>> {
+        let mut bytes = Vec::new();
+        while bytes.len() < len {
+            let buf = match self.reader.fill(len - bytes.len())? {
+                dec::Reference::Long(buf) => buf,
+                dec::Reference::Short(buf) => buf,
+            };
+            if buf.is_empty() {
+                return Err(DecodeError::Eof);
+            }
+            let read = buf.len().min(len - bytes.len());
+            bytes.extend_from_slice(&buf[..read]);
+            self.reader.advance(read);
+            self.pos += read;
+        }
+        Ok(bytes)
+    }
+
+    fn push(&mut self, offset: usize, depth: usize, bytes: Vec<u8>, description: String) -> usize {
+        self.lines.push(Line {
+            offset,
+            depth,
+            bytes,
+            description,
+            violations: Vec::new(),
+        });
+        self.lines.len() - 1
+    }
+
+    fn end(&mut self) -> Result<(), DecodeError<R::Error>> {
+        match peek_one(&mut self.reader) {
+            Ok(_) => Err(DecodeError::TrailingData),
+            Err(DecodeError::Eof) => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Returns true and adds a line if the next byte is a break.
+    fn is_break(&mut self, depth: usize) -> Result<bool, DecodeError<R::Error>> {
+        if peek_one(&mut self.reader)? != marker::BREAK {
+            return Ok(false);
+        }
+        let offset = self.pos;
+        pull_one(&mut self.reader)?;
+        self.pos += 1;
+        self.push(offset, depth, vec![marker::BREAK], "break".into());
+        Ok(true)
+    }
+
+    /// Explains a single data item. Returns the content if it's a byte or text string of
+    /// definite length.
+    fn item(&mut self, depth: usize) -> Result<Option<Content>, DecodeError<R::Error>> {
+        if !self.reader.step_in() {
+            return Err(DecodeError::DepthLimit);
+        }
+
+        let offset = self.pos;
+        let byte = pull_one(&mut self.reader)?;
+        self.pos += 1;
+        let mut head = vec![byte];
+        let major = byte >> 5;
+        let info = byte & 0x1f;
+        let mut violations = Vec::new();
+        let argument = match info {
+            0..=23 => Some(u64::from(info)),
+            24..=27 => {
+                let bytes = self.take(1 << (info - 24))?;
+                let argument = bytes
+                    .iter()
+                    .fold(0, |argument, byte| argument << 8 | u64::from(*byte));
+                head.extend(bytes);
+                let minimal = match info {
+                    24 => argument >= 24,
+                    25 => argument > 0xff,
+                    26 => argument > 0xffff,
+                    _ => argument > 0xffff_ffff,
+                };
+                // Floats are not encoded as integers.
+                if !minimal && major != major::SIMPLE {
+                    violations.push(Violation::NonMinimalArgument);
+                }
+                Some(argument)
+            }
+            INDEFINITE if (major::BYTES..=major::MAP).contains(&major) => {
+                violations.push(Violation::IndefiniteLength);
+                None
+            }
+            _ => return Err(DecodeError::Unsupported { byte }),
+        };
+
+        let len = argument.map(|argument| argument.to_string());
+        let len = len.as_deref().unwrap_or("*");
+        let mut content = None;
+        let description = match major {
+            major::UNSIGNED => format!("unsigned({})", len),
+            major::NEGATIVE => {
+                let value = -1 - i128::from(argument.expect("integers have an argument"));
+                format!("negative({})", value)
+            }
+            major::BYTES | major::STRING => {
+                let name = if major == major::BYTES {
+                    "bytes"
+                } else {
+                    "text"
+                };
+                format!("{}({})", name, len)
+            }
+            major::ARRAY => format!("array({})", len),
+            major::MAP => format!("map({})", len),
+            major::TAG => {
+                let tag = argument.expect("tags have an argument");
+                if tag != CBOR_TAGS_CID {
+                    violations.push(Violation::UnsupportedTag(tag));
+                }
+                format!("tag({})", tag)
+            }
+            _ => {
+                let argument = argument.expect("simple values have an argument");
+                match info {
+                    25..=27 => {
+                        let value = match info {
+                            25 => f16_to_f64(argument as u16),
+                            26 => f64::from(f32::from_bits(argument as u32)),
+                            _ => f64::from_bits(argument),
+                        };
+                        if info != 27 {
+                            violations.push(Violation::NonDoublePrecisionFloat);
+                        }
+                        if !value.is_finite() {
+                            violations.push(Violation::NonFiniteFloat);
+                        }
+                        format!("float({:?})", value)
+                    }
+                    20 => "false".into(),
+                    21 => "true".into(),
+                    22 => "null".into(),
+                    _ => {
+                        violations.push(Violation::UnsupportedSimpleValue);
+                        match info {
+                            23 => "undefined".into(),
+                            _ => format!("simple({})", argument),
+                        }
+                    }
+                }
+            }
+        };
+        let index = self.push(offset, depth, head, description);
+        self.lines[index].violations = violations;
+
+        match (major, argument) {
+            (major::BYTES | major::STRING, Some(len)) => {
+                let offset = self.pos;
+                let len = usize::try_from(len).map_err(|_| DecodeError::Eof)?;
+                let bytes = self.take(len)?;
+                let (description, valid) = describe_string(major, &bytes);
+                let line = self.push(offset, depth + 1, bytes.clone(), description);
+                if !valid {
+                    self.lines[line].violations.push(Violation::InvalidUtf8);
+                }
+                content = Some(Content { major, bytes, line });
+            }
+            (major::BYTES | major::STRING, None) => {
+                while !self.is_break(depth + 1)? {
+                    let byte = peek_one(&mut self.reader)?;
+                    // The chunks must be strings of the same type with definite length.
+                    if byte >> 5 != major || byte & 0x1f == INDEFINITE {
+                        return Err(DecodeError::Unsupported { byte });
+                    }
+                    self.item(depth + 1)?;
+                }
+            }
+            (major::ARRAY, len) => {
+                let mut count = 0;
+                while !self.is_end(depth + 1, len, count)? {
+                    self.item(depth + 1)?;
+                    count += 1;
+                }
+            }
+            (major::MAP, len) => {
+                let mut count = 0;
+                let mut previous: Option<Vec<u8>> = None;
+                while !self.is_end(depth + 1, len, count)? {
+                    let key_line = self.lines.len();
+                    match self.item(depth + 1)? {
+                        Some(key) if key.major == major::STRING => {
+                            // Keys are sorted by length first and then bytewise.
+                            if let Some(previous) = &previous {
+                                let ordering =
+                                    (previous.len(), previous).cmp(&(key.bytes.len(), &key.bytes));
+                                if ordering.is_gt() {
+                                    self.lines[key_line].violations.push(Violation::UnsortedKey);
+                                } else if ordering.is_eq() {
+                                    self.lines[key_line]
+                                        .violations
+                                        .push(Violation::DuplicateKey);
+                                }
+                            }
+                            previous = Some(key.bytes);
+                        }
+                        _ => self.lines[key_line]
+                            .violations
+                            .push(Violation::NonStringKey),
+                    }
+                    self.item(depth + 1)?;
+                    count += 1;
+                }
+            }
+            (major::TAG, Some(CBOR_TAGS_CID)) => {
+                let cid = match self.item(depth + 1)? {
+                    Some(content) if content.major == major::BYTES => {
+                        match content.bytes.split_first() {
+                            Some((0, cid)) => {
+                                Cid::try_from(cid).ok().map(|cid| (cid, content.line))
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                match cid {
+                    Some((cid, line)) => self.lines[line].description = format!("link({})", cid),
+                    None => self.lines[index].violations.push(Violation::InvalidCid),
+                }
+            }
+            (major::TAG, _) => {
+                self.item(depth + 1)?;
+            }
+            _ => {}
+        }
+
+        self.reader.step_out();
+        Ok(content)
+    }
+
+    /// Returns whether all elements of a list or map were read.
+    fn is_end(
+        &mut self,
+        depth: usize,
+        len: Option<u64>,
+        count: u64,
+    ) -> Result<bool, DecodeError<R::Error>> {
+        match len {
+            Some(len) => Ok(count == len),
+            None => self.is_break(depth),
+        }
+    }
+}
+
+/// Describes the content of a string. Returns false if a text string isn't valid UTF-8.
+fn describe_string(major: u8, bytes: &[u8]) -> (String, bool) {
+    if major == major::STRING {
+        if let Ok(string) = core::str::from_utf8(bytes) {
+            let escaped = crate::json::to_vec(string).expect("strings can always be encoded");
+            let escaped = String::from_utf8(escaped).expect("escaped strings are valid UTF-8");
+            return (escaped, true);
+        }
+    }
+    let mut hex = String::from("h'");
+    for byte in bytes {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    hex.push('\'');
+    (hex, major != major::STRING)
+}
@@ -0,0 +1,92 @@
+//! Hashing of serialized DAG-CBOR data.
+use core::convert::{Infallible, TryFrom};
+
+use cbor4ii::core::enc;
+use cid::multihash::Multihash;
+use cid::Cid;
+use multihash::{Blake2b256, Blake2b512, Code, Hasher, MultihashDigest, Sha2_256, Sha2_512};
+
+use crate::error::VerifyError;
+
+/// A [`Hasher`] with a known multihash code.
+///
+/// It is implemented for the hashers supported by this crate. Implement it for your own hasher to
+/// use it with [`to_cid`](crate::to_cid) and [`to_vec_with_cid`](crate::to_vec_with_cid).
+pub trait MultihashHasher: Hasher + Default {
+    /// The multihash code of the hash function.
+    const CODE: u64;
+}
+
+impl MultihashHasher for Sha2_256 {
+    const CODE: u64 = 0x12;
+}
+
+impl MultihashHasher for Sha2_512 {
+    const CODE: u64 = 0x13;
+}
+
+impl MultihashHasher for Blake2b256 {
+    const CODE: u64 = 0xb220;
+}
+
+impl MultihashHasher for Blake2b512 {
+    const CODE: u64 = 0xb240;
+}
+
+/// A writer that feeds everything it writes into a hasher.
+#[derive(Debug)]
+pub struct HashWriter<H, W> {
+    hasher: H,
+    writer: W,
+}
+
+impl<H: MultihashHasher, W> HashWriter<H, W> {
+    /// Creates a new writer that hashes the data before passing it on to `writer`.
+    pub fn new(writer: W) -> Self {
+        HashWriter {
+            hasher: H::default(),
+            writer,
+        }
+    }
+
+    /// Returns the underlying writer and the multihash of all the data that was written.
+    pub fn finalize(mut self) -> Result<(W, Multihash), multihash::Error> {
+        let hash = Multihash::wrap(H::CODE, self.hasher.finalize())?;
+        Ok((self.writer, hash))
+    }
+}
+
+impl<H: Hasher, W: enc::Write> enc::Write for HashWriter<H, W> {
+    type Error = W::Error;
+
+    #[inline]
+    fn push(&mut self, input: &[u8]) -> Result<(), Self::Error> {
+        self.hasher.update(input);
+        self.writer.push(input)
+    }
+}
+
+/// A writer that discards all data.
+#[derive(Debug, Default)]
+pub struct Sink;
+
+impl enc::Write for Sink {
+    type Error = Infallible;
+
+    #[inline]
+    fn push(&mut self, _input: &[u8]) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Checks whether the hash of the CID matches the data, independent of the codec of the CID.
+pub(crate) fn verify_hash(cid: &Cid, data: &[u8]) -> Result<(), VerifyError> {
+    let hash = cid.hash();
+    let code =
+        Code::try_from(hash.code()).map_err(|_| VerifyError::UnsupportedHash(hash.code()))?;
+    if code.digest(data) == *hash {
+        Ok(())
+    } else {
+        Err(VerifyError::HashMismatch)
+    }
+}
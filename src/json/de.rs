@@ -0,0 +1,844 @@
+//! DAG-JSON deserialization.
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use core::convert::{Infallible, TryFrom};
+use core::marker::PhantomData;
+
+#[cfg(feature = "std")]
+use cbor4ii::core::utils::IoReader;
+use cbor4ii::core::{dec, utils::SliceReader};
+use cid::serde::CID_SERDE_PRIVATE_IDENTIFIER;
+use cid::Cid;
+use serde::de::{self, IntoDeserializer, Visitor};
+
+use super::{decode_base64, BYTES_KEY, RESERVED_KEY};
+use crate::cbor4ii_nonpub::{peek_one, pull_one};
+use crate::error::DecodeError;
+
+/// Decodes a value from DAG-JSON data in a slice.
+///
+/// Strings without escape sequences can be borrowed from the slice.
+pub fn from_slice<'a, T>(buf: &'a [u8]) -> Result<T, DecodeError<Infallible>>
+where
+    T: de::Deserialize<'a>,
+{
+    let mut deserializer = Deserializer::from_reader(SliceReader::new(buf));
+    let value = serde::Deserialize::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Decodes a value from DAG-JSON data in a reader.
+#[cfg(feature = "std")]
+pub fn from_reader<T, R>(reader: R) -> Result<T, DecodeError<std::io::Error>>
+where
+    T: de::DeserializeOwned,
+    R: std::io::BufRead,
+{
+    let mut deserializer = Deserializer::from_reader(IoReader::new(reader));
+    let value = serde::Deserialize::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// A string that is either borrowed from the input or had to be copied due to escape sequences.
+pub(crate) enum Str<'de> {
+    Borrowed(&'de str),
+    Owned(String),
+}
+
+impl Str<'_> {
+    pub(crate) fn as_str(&self) -> &str {
+        match self {
+            Str::Borrowed(string) => string,
+            Str::Owned(string) => string,
+        }
+    }
+}
+
+/// The start of a JSON object, which might be a link or bytes.
+pub(crate) enum Object {
+    /// A map, with the first key if it isn't empty.
+    Map(Option<String>),
+    /// The bytes of a CID.
+    Link(Vec<u8>),
+    /// Bytes.
+    Bytes(Vec<u8>),
+}
+
+/// A JSON number.
+pub(crate) enum Number {
+    Unsigned(u64),
+    Signed(i64),
+    Float(f64),
+    /// An unsigned integer that doesn't fit into 64 bits.
+    BigUnsigned(u128),
+    /// A signed integer that doesn't fit into 64 bits.
+    BigSigned(i128),
+}
+
+/// A Serde `Deserialize`r of DAG-JSON data.
+pub(crate) struct Deserializer<R> {
+    pub(crate) reader: R,
+}
+
+impl<R> Deserializer<R> {
+    /// Constructs a `Deserializer` which reads from a `Read`er.
+    pub fn from_reader(reader: R) -> Deserializer<R> {
+        Deserializer { reader }
+    }
+}
+
+impl<'de, R: dec::Read<'de>> Deserializer<R> {
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    fn try_step<'a>(
+        &'a mut self,
+    ) -> Result<scopeguard::ScopeGuard<&'a mut Self, fn(&'a mut Self) -> ()>, DecodeError<R::Error>>
+    {
+        if self.reader.step_in() {
+            Ok(scopeguard::guard(self, |de| de.reader.step_out()))
+        } else {
+            Err(DecodeError::DepthLimit)
+        }
+    }
+
+    /// Skips whitespace and returns the next byte without consuming it.
+    pub(crate) fn peek(&mut self) -> Result<u8, DecodeError<R::Error>> {
+        loop {
+            match peek_one(&mut self.reader)? {
+                b' ' | b'\n' | b'\r' | b'\t' => self.reader.advance(1),
+                byte => return Ok(byte),
+            }
+        }
+    }
+
+    /// Skips whitespace and consumes the expected byte.
+    pub(crate) fn expect(&mut self, expected: u8) -> Result<(), DecodeError<R::Error>> {
+        match self.peek()? {
+            byte if byte == expected => {
+                self.reader.advance(1);
+                Ok(())
+            }
+            byte => Err(DecodeError::Unsupported { byte }),
+        }
+    }
+
+    /// Consumes the given bytes, e.g. a literal like `true`, `false` or `null`.
+    pub(crate) fn expect_literal(&mut self, literal: &[u8]) -> Result<(), DecodeError<R::Error>> {
+        for expected in literal {
+            let byte = pull_one(&mut self.reader)?;
+            if byte != *expected {
+                return Err(DecodeError::Unsupported { byte });
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a string, including the surrounding quotes.
+    pub(crate) fn parse_str(&mut self) -> Result<Str<'de>, DecodeError<R::Error>> {
+        self.expect(b'"')?;
+
+        // Fast path for strings without escape sequences that can be borrowed.
+        if let dec::Reference::Long(buf) = self.reader.fill(usize::MAX)? {
+            if let Some(end) = buf.iter().position(|byte| *byte == b'"' || *byte == b'\\') {
+                if buf[end] == b'"' && buf[..end].iter().all(|byte| *byte >= 0x20) {
+                    let string =
+                        core::str::from_utf8(&buf[..end]).map_err(DecodeError::InvalidUtf8)?;
+                    self.reader.advance(end + 1);
+                    return Ok(Str::Borrowed(string));
+                }
+            }
+        }
+
+        let mut bytes = Vec::new();
+        loop {
+            let buf = match self.reader.fill(usize::MAX)? {
+                dec::Reference::Long(buf) => buf,
+                dec::Reference::Short(buf) => buf,
+            };
+            if buf.is_empty() {
+                return Err(DecodeError::Eof);
+            }
+            match buf
+                .iter()
+                .position(|byte| *byte == b'"' || *byte == b'\\' || *byte < 0x20)
+            {
+                Some(end) => {
+                    bytes.extend_from_slice(&buf[..end]);
+                    let byte = buf[end];
+                    self.reader.advance(end + 1);
+                    match byte {
+                        b'"' => break,
+                        b'\\' => self.parse_escape(&mut bytes)?,
+                        _ => return Err(DecodeError::Unsupported { byte }),
+                    }
+                }
+                None => {
+                    let len = buf.len();
+                    bytes.extend_from_slice(buf);
+                    self.reader.advance(len);
+                }
+            }
+        }
+        String::from_utf8(bytes)
+            .map(Str::Owned)
+            .map_err(|error| DecodeError::InvalidUtf8(error.utf8_error()))
+    }
+
+    /// Parses an escape sequence, the backslash was already consumed.
+    fn parse_escape(&mut self, bytes: &mut Vec<u8>) -> Result<(), DecodeError<R::Error>> {
+        let byte = pull_one(&mut self.reader)?;
+        let unescaped = match byte {
+            b'"' => b'"',
+            b'\\' => b'\\',
+            b'/' => b'/',
+            b'b' => 0x08,
+            b'f' => 0x0c,
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'u' => {
+                let mut code = self.parse_hex()?;
+                // Characters outside the Basic Multilingual Plane are encoded as surrogate pair.
+                if (0xd800..0xdc00).contains(&code) {
+                    self.expect_literal(b"\\u")?;
+                    let low = self.parse_hex()?;
+                    if !(0xdc00..0xe000).contains(&low) {
+                        return Err(DecodeError::Msg("Invalid unicode surrogate pair".into()));
+                    }
+                    code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                }
+                let character = char::from_u32(code)
+                    .ok_or_else(|| DecodeError::Msg("Invalid unicode escape".into()))?;
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(character.encode_utf8(&mut buf).as_bytes());
+                return Ok(());
+            }
+            _ => return Err(DecodeError::Unsupported { byte }),
+        };
+        bytes.push(unescaped);
+        Ok(())
+    }
+
+    /// Parses the four hex digits of a unicode escape sequence.
+    fn parse_hex(&mut self) -> Result<u32, DecodeError<R::Error>> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let byte = pull_one(&mut self.reader)?;
+            let digit = char::from(byte)
+                .to_digit(16)
+                .ok_or(DecodeError::Unsupported { byte })?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    /// Parses a number as unsigned or signed integer, or as float.
+    pub(crate) fn parse_number(&mut self) -> Result<Number, DecodeError<R::Error>> {
+        let mut number = String::new();
+        loop {
+            match peek_one(&mut self.reader) {
+                Ok(byte @ (b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E')) => {
+                    number.push(char::from(byte));
+                    self.reader.advance(1);
+                }
+                Ok(_) | Err(DecodeError::Eof) => break,
+                Err(error) => return Err(error),
+            }
+        }
+
+        let invalid = || DecodeError::Msg(["Invalid number: ", &number].concat());
+        if !is_valid_number(&number) {
+            return Err(invalid());
+        }
+        if number.contains(['.', 'e', 'E']) {
+            // Numbers that are too large for a 64-bit float would be infinite.
+            match number.parse::<f64>() {
+                Ok(value) if value.is_finite() => Ok(Number::Float(value)),
+                _ => Err(invalid()),
+            }
+        } else if number.starts_with('-') {
+            match number.parse() {
+                Ok(value) => Ok(Number::Signed(value)),
+                Err(_) => number.parse().map(Number::BigSigned).map_err(|_| invalid()),
+            }
+        } else {
+            match number.parse() {
+                Ok(value) => Ok(Number::Unsigned(value)),
+                Err(_) => number
+                    .parse()
+                    .map(Number::BigUnsigned)
+                    .map_err(|_| invalid()),
+            }
+        }
+    }
+
+    /// Returns whether a list or map has another element, consuming the separating comma or the
+    /// closing bracket.
+    pub(crate) fn has_next(
+        &mut self,
+        close: u8,
+        first: bool,
+    ) -> Result<bool, DecodeError<R::Error>> {
+        let byte = self.peek()?;
+        if byte == close {
+            self.reader.advance(1);
+            Ok(false)
+        } else if first {
+            Ok(true)
+        } else if byte == b',' {
+            self.reader.advance(1);
+            Ok(true)
+        } else {
+            Err(DecodeError::Unsupported { byte })
+        }
+    }
+
+    /// Parses the start of an object up to its first value. Links and bytes are parsed
+    /// completely.
+    pub(crate) fn parse_object(&mut self) -> Result<Object, DecodeError<R::Error>> {
+        self.expect(b'{')?;
+        if self.peek()? == b'}' {
+            self.reader.advance(1);
+            return Ok(Object::Map(None));
+        }
+        let key = self.parse_str()?;
+        self.expect(b':')?;
+        if key.as_str() != RESERVED_KEY {
+            return Ok(Object::Map(Some(key.as_str().into())));
+        }
+
+        let object = match self.peek()? {
+            b'"' => {
+                let cid = Cid::try_from(self.parse_str()?.as_str())
+                    .map_err(|error| DecodeError::Msg(format_error("Invalid CID: ", error)))?;
+                Object::Link(cid.to_bytes())
+            }
+            b'{' => {
+                self.reader.advance(1);
+                if self.parse_str()?.as_str() != BYTES_KEY {
+                    return Err(reserved_key_error());
+                }
+                self.expect(b':')?;
+                let bytes = decode_base64(self.parse_str()?.as_str())
+                    .ok_or_else(|| DecodeError::Msg("Invalid base64 encoded bytes".into()))?;
+                self.expect(b'}')?;
+                Object::Bytes(bytes)
+            }
+            _ => return Err(reserved_key_error()),
+        };
+        match self.peek()? {
+            b'}' => {
+                self.reader.advance(1);
+                Ok(object)
+            }
+            _ => Err(reserved_key_error()),
+        }
+    }
+
+    /// This method should be called after a value has been deserialized to ensure there is no
+    /// trailing data in the input source.
+    pub fn end(&mut self) -> Result<(), DecodeError<R::Error>> {
+        match self.peek() {
+            Ok(_) => Err(DecodeError::TrailingData),
+            Err(DecodeError::Eof) => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+fn reserved_key_error<E>() -> DecodeError<E> {
+    DecodeError::Msg("The map key `/` is reserved for links and bytes".into())
+}
+
+fn format_error<T: core::fmt::Display>(prefix: &str, error: T) -> String {
+    use core::fmt::Write;
+
+    let mut msg = String::from(prefix);
+    let _ = write!(msg, "{}", error);
+    msg
+}
+
+/// Checks whether a number follows the JSON grammar.
+fn is_valid_number(number: &str) -> bool {
+    let bytes = number.as_bytes();
+    let mut pos = usize::from(bytes.first() == Some(&b'-'));
+    let digits = |pos: &mut usize| {
+        let start = *pos;
+        while bytes.get(*pos).is_some_and(u8::is_ascii_digit) {
+            *pos += 1;
+        }
+        *pos - start
+    };
+
+    // Integer part without leading zeros.
+    let start = pos;
+    match digits(&mut pos) {
+        0 => return false,
+        len if len > 1 && bytes[start] == b'0' => return false,
+        _ => {}
+    }
+    if bytes.get(pos) == Some(&b'.') {
+        pos += 1;
+        if digits(&mut pos) == 0 {
+            return false;
+        }
+    }
+    if matches!(bytes.get(pos), Some(b'e' | b'E')) {
+        pos += 1;
+        if matches!(bytes.get(pos), Some(b'+' | b'-')) {
+            pos += 1;
+        }
+        if digits(&mut pos) == 0 {
+            return false;
+        }
+    }
+    pos == bytes.len()
+}
+
+impl<'de, R: dec::Read<'de>> serde::Deserializer<'de> for &mut Deserializer<R> {
+    type Error = DecodeError<R::Error>;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut de = self.try_step()?;
+        let de = &mut *de;
+
+        match de.peek()? {
+            b'n' => {
+                de.expect_literal(b"null")?;
+                visitor.visit_none()
+            }
+            b't' => {
+                de.expect_literal(b"true")?;
+                visitor.visit_bool(true)
+            }
+            b'f' => {
+                de.expect_literal(b"false")?;
+                visitor.visit_bool(false)
+            }
+            b'"' => match de.parse_str()? {
+                Str::Borrowed(string) => visitor.visit_borrowed_str(string),
+                Str::Owned(string) => visitor.visit_string(string),
+            },
+            b'[' => de.deserialize_seq(visitor),
+            b'{' => match de.parse_object()? {
+                Object::Map(key) => visit_map(de, key, visitor),
+                Object::Link(cid) => visitor.visit_newtype_struct(CidDeserializer::new(cid)),
+                Object::Bytes(bytes) => visitor.visit_byte_buf(bytes),
+            },
+            b'-' | b'0'..=b'9' => match de.parse_number()? {
+                Number::Unsigned(value) => visitor.visit_u64(value),
+                Number::Signed(value) => visitor.visit_i64(value),
+                Number::Float(value) => visitor.visit_f64(value),
+                Number::BigUnsigned(value) => visitor.visit_u128(value),
+                Number::BigSigned(value) => visitor.visit_i128(value),
+            },
+            byte => Err(DecodeError::Unsupported { byte }),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string map ignored_any
+    }
+
+    #[inline]
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.parse_object()? {
+            Object::Bytes(bytes) => visitor.visit_byte_buf(bytes),
+            _ => Err(DecodeError::Msg("Expected bytes".into())),
+        }
+    }
+
+    #[inline]
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    #[inline]
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.peek()? == b'n' {
+            self.expect_literal(b"null")?;
+            visitor.visit_none()
+        } else {
+            let mut de = self.try_step()?;
+            visitor.visit_some(&mut **de)
+        }
+    }
+
+    #[inline]
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.peek()?;
+        self.expect_literal(b"null")?;
+        visitor.visit_unit()
+    }
+
+    #[inline]
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if name == CID_SERDE_PRIVATE_IDENTIFIER {
+            match self.parse_object()? {
+                Object::Link(cid) => visitor.visit_newtype_struct(CidDeserializer::new(cid)),
+                _ => Err(DecodeError::Msg("Expected a link".into())),
+            }
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    #[inline]
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut de = self.try_step()?;
+        de.expect(b'[')?;
+        let mut accessor = Accessor::new(&mut de, None);
+        let value = visitor.visit_seq(&mut accessor)?;
+        accessor.end(b']')?;
+        Ok(value)
+    }
+
+    #[inline]
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    #[inline]
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    #[inline]
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    #[inline]
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut de = self.try_step()?;
+        match de.peek()? {
+            // Unit variant.
+            b'"' => match de.parse_str()? {
+                Str::Borrowed(variant) => visitor.visit_enum(variant.into_deserializer()),
+                Str::Owned(variant) => visitor.visit_enum(variant.into_deserializer()),
+            },
+            // Map with a single entry.
+            _ => match de.parse_object()? {
+                Object::Map(Some(variant)) => {
+                    let value = visitor.visit_enum(EnumAccessor {
+                        de: &mut de,
+                        variant,
+                    })?;
+                    de.expect(b'}')?;
+                    Ok(value)
+                }
+                _ => Err(DecodeError::Msg("Expected an enum".into())),
+            },
+        }
+    }
+
+    #[inline]
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        true
+    }
+}
+
+/// Visits the remaining entries of a map, whose start was already parsed.
+fn visit_map<'de, R, V>(
+    de: &mut Deserializer<R>,
+    first_key: Option<String>,
+    visitor: V,
+) -> Result<V::Value, DecodeError<R::Error>>
+where
+    R: dec::Read<'de>,
+    V: Visitor<'de>,
+{
+    let mut accessor = Accessor::new(de, Some(first_key));
+    let value = visitor.visit_map(&mut accessor)?;
+    accessor.end(b'}')?;
+    Ok(value)
+}
+
+fn string_deserializer<E>(string: String) -> de::value::StringDeserializer<DecodeError<E>>
+where
+    DecodeError<E>: de::Error,
+{
+    string.into_deserializer()
+}
+
+/// Access to the elements of lists and entries of maps.
+struct Accessor<'a, R> {
+    de: &'a mut Deserializer<R>,
+    /// Whether the closing bracket was already consumed.
+    done: bool,
+    first: bool,
+    /// For maps, the first key, which was already parsed in order to detect links and bytes.
+    /// `Some(None)` means it's an empty map.
+    first_key: Option<Option<String>>,
+}
+
+impl<'de, 'a, R: dec::Read<'de>> Accessor<'a, R> {
+    fn new(de: &'a mut Deserializer<R>, first_key: Option<Option<String>>) -> Self {
+        let done = matches!(first_key, Some(None));
+        Accessor {
+            de,
+            done,
+            first: true,
+            first_key,
+        }
+    }
+
+    /// Returns whether there is another element.
+    fn has_next(&mut self, close: u8) -> Result<bool, DecodeError<R::Error>> {
+        if self.done {
+            return Ok(false);
+        }
+        let has_next = self.de.has_next(close, self.first)?;
+        self.first = false;
+        self.done = !has_next;
+        Ok(has_next)
+    }
+
+    /// Makes sure the visitor consumed all elements.
+    fn end(&mut self, close: u8) -> Result<(), DecodeError<R::Error>> {
+        if self.done {
+            Ok(())
+        } else {
+            self.de.expect(close)
+        }
+    }
+}
+
+impl<'de, 'a, R: dec::Read<'de>> de::SeqAccess<'de> for Accessor<'a, R> {
+    type Error = DecodeError<R::Error>;
+
+    #[inline]
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        if self.has_next(b']')? {
+            Ok(Some(seed.deserialize(&mut *self.de)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<'de, 'a, R: dec::Read<'de>> de::MapAccess<'de> for Accessor<'a, R> {
+    type Error = DecodeError<R::Error>;
+
+    #[inline]
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        if let Some(Some(key)) = self.first_key.take() {
+            self.first = false;
+            return seed.deserialize(string_deserializer(key)).map(Some);
+        }
+        if !self.has_next(b'}')? {
+            return Ok(None);
+        }
+        let key = match self.de.parse_str()? {
+            Str::Borrowed(key) => seed.deserialize(de::value::BorrowedStrDeserializer::new(key)),
+            Str::Owned(key) => seed.deserialize(string_deserializer(key)),
+        }?;
+        self.de.expect(b':')?;
+        Ok(Some(key))
+    }
+
+    #[inline]
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+struct EnumAccessor<'a, R> {
+    de: &'a mut Deserializer<R>,
+    variant: String,
+}
+
+impl<'de, 'a, R: dec::Read<'de>> de::EnumAccess<'de> for EnumAccessor<'a, R> {
+    type Error = DecodeError<R::Error>;
+    type Variant = Self;
+
+    #[inline]
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = core::mem::take(&mut self.variant);
+        let variant = seed.deserialize(string_deserializer(variant))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a, R: dec::Read<'de>> de::VariantAccess<'de> for EnumAccessor<'a, R> {
+    type Error = DecodeError<R::Error>;
+
+    #[inline]
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        use serde::Deserializer;
+
+        self.de.deserialize_unit(de::IgnoredAny).map(|_| ())
+    }
+
+    #[inline]
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut *self.de)
+    }
+
+    #[inline]
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        use serde::Deserializer;
+
+        self.de.deserialize_tuple(len, visitor)
+    }
+
+    #[inline]
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        use serde::Deserializer;
+
+        self.de.deserialize_map(visitor)
+    }
+}
+
+/// Deserializes the bytes of a CID that was parsed from a link.
+struct CidDeserializer<E> {
+    cid: Vec<u8>,
+    error: PhantomData<E>,
+}
+
+impl<E> CidDeserializer<E> {
+    fn new(cid: Vec<u8>) -> Self {
+        CidDeserializer {
+            cid,
+            error: PhantomData,
+        }
+    }
+}
+
+impl<'de, E> de::Deserializer<'de> for CidDeserializer<E>
+where
+    DecodeError<E>: de::Error,
+{
+    type Error = DecodeError<E>;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom(
+            "Only bytes can be deserialized into a CID",
+        ))
+    }
+
+    #[inline]
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_byte_buf(self.cid)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if name == CID_SERDE_PRIVATE_IDENTIFIER {
+            self.deserialize_bytes(visitor)
+        } else {
+            Err(de::Error::custom([
+                "This deserializer must not be called on newtype structs other than one named `",
+                CID_SERDE_PRIVATE_IDENTIFIER,
+                "`"
+            ].concat()))
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool byte_buf char enum f32 f64 i8 i16 i32 i64 identifier ignored_any map option seq str
+        string struct tuple tuple_struct u8 u16 u32 u64 unit unit_struct
+    }
+}
@@ -0,0 +1,102 @@
+//! DAG-JSON serialization and deserialization.
+//!
+//! [DAG-JSON] is the JSON based codec of the IPLD Data Model. It uses the same Serde
+//! representation of CIDs as the DAG-CBOR implementation of this crate, hence types that can be
+//! stored as DAG-CBOR can also be stored as DAG-JSON.
+//!
+//! - Links are encoded as `{"/": "<cid>"}`, where the CID is encoded with its default multibase.
+//! - Bytes are encoded as `{"/": {"bytes": "<base64>"}}`, using standard base64 without padding.
+//! - Map keys are sorted bytewise.
+//!
+//! The `"/"` key is reserved for links and bytes, maps containing it cannot be encoded.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::collections::BTreeMap;
+//! use std::convert::TryFrom;
+//!
+//! use cid::Cid;
+//! use serde_ipld_dagcbor::json;
+//!
+//! let cid = Cid::try_from("bafyreih4jspvxgh5xrp3wboocaajx5ym4ky7ok3gs5dsdkizgnegtanfxu").unwrap();
+//! let mut map = BTreeMap::new();
+//! map.insert("link", cid);
+//!
+//! let encoded = json::to_vec(&map).unwrap();
+//! assert_eq!(
+//!     encoded,
+//!     br#"{"link":{"/":"bafyreih4jspvxgh5xrp3wboocaajx5ym4ky7ok3gs5dsdkizgnegtanfxu"}}"#
+//! );
+//! let decoded: BTreeMap<String, Cid> = json::from_slice(&encoded).unwrap();
+//! assert_eq!(decoded["link"], cid);
+//! ```
+//!
+//! [DAG-JSON]: https://ipld.io/specs/codecs/dag-json/spec/
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+
+pub(crate) mod de;
+pub(crate) mod ser;
+
+#[cfg(feature = "std")]
+pub use self::de::from_reader;
+pub use self::de::from_slice;
+pub use self::ser::to_vec;
+#[cfg(feature = "std")]
+pub use self::ser::to_writer;
+
+/// The multicodec code of DAG-JSON.
+pub const DAG_JSON_CODEC: u64 = 0x0129;
+
+/// The key that is reserved for links and bytes.
+const RESERVED_KEY: &str = "/";
+
+/// The key of the map containing the base64 encoded bytes.
+const BYTES_KEY: &str = "bytes";
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes bytes as standard base64 without padding.
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let word = chunk.iter().enumerate().fold(0u32, |word, (ii, byte)| {
+            word | u32::from(*byte) << (16 - ii * 8)
+        });
+        // Without padding, a chunk of n bytes is encoded as n + 1 characters.
+        for ii in 0..=chunk.len() {
+            let index = (word >> (18 - ii * 6)) & 0x3f;
+            encoded.push(char::from(BASE64_ALPHABET[index as usize]));
+        }
+    }
+    encoded
+}
+
+/// Decodes standard base64 without padding. Returns `None` if the input isn't valid.
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(encoded.len() / 4 * 3 + 2);
+    for chunk in encoded.as_bytes().chunks(4) {
+        // A single trailing character cannot encode a full byte.
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut word = 0u32;
+        for (ii, character) in chunk.iter().enumerate() {
+            let value = BASE64_ALPHABET
+                .iter()
+                .position(|candidate| candidate == character)?;
+            word |= (value as u32) << (18 - ii * 6);
+        }
+        let len = chunk.len() - 1;
+        // The bits that don't belong to a full byte must be zero for a canonical encoding.
+        if word & (0xffffff >> (len * 8)) != 0 {
+            return None;
+        }
+        for ii in 0..len {
+            bytes.push((word >> (16 - ii * 8)) as u8);
+        }
+    }
+    Some(bytes)
+}
@@ -0,0 +1,749 @@
+//! DAG-JSON serialization.
+#[cfg(not(feature = "std"))]
+use alloc::collections::TryReserveError;
+#[cfg(not(feature = "std"))]
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::convert::TryFrom;
+use core::fmt::Write as _;
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::collections::TryReserveError;
+
+#[cfg(feature = "std")]
+use cbor4ii::core::utils::IoWriter;
+use cbor4ii::core::{enc, utils::BufWriter};
+use cid::serde::CID_SERDE_PRIVATE_IDENTIFIER;
+use cid::Cid;
+use serde::{ser, Serialize};
+
+use super::{encode_base64, RESERVED_KEY};
+use crate::error::EncodeError;
+
+/// Serializes a value to a vector of DAG-JSON encoded bytes.
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>, EncodeError<TryReserveError>>
+where
+    T: Serialize + ?Sized,
+{
+    let mut serializer = Serializer::new(BufWriter::new(Vec::new()));
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_inner().into_inner())
+}
+
+/// Serializes a value as DAG-JSON to a writer.
+#[cfg(feature = "std")]
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<(), EncodeError<std::io::Error>>
+where
+    W: std::io::Write,
+    T: Serialize + ?Sized,
+{
+    let mut serializer = Serializer::new(IoWriter::new(writer));
+    value.serialize(&mut serializer)
+}
+
+/// Serializes a value into a separate buffer, e.g. for sorting map entries.
+fn to_buffer<T, E>(value: &T) -> Result<Vec<u8>, EncodeError<E>>
+where
+    T: Serialize + ?Sized,
+{
+    to_vec(value).map_err(|error| match error {
+        EncodeError::Msg(msg) => EncodeError::Msg(msg),
+        EncodeError::Write(error) => EncodeError::Msg(error.to_string()),
+    })
+}
+
+/// A structure for serializing Rust values to DAG-JSON.
+pub(crate) struct Serializer<W> {
+    writer: W,
+}
+
+impl<W: enc::Write> Serializer<W> {
+    /// Creates a new DAG-JSON serializer.
+    pub fn new(writer: W) -> Serializer<W> {
+        Serializer { writer }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    #[inline]
+    pub(crate) fn write(&mut self, bytes: &[u8]) -> Result<(), EncodeError<W::Error>> {
+        self.writer.push(bytes)?;
+        Ok(())
+    }
+
+    #[inline]
+    fn write_display<T: core::fmt::Display>(
+        &mut self,
+        value: T,
+    ) -> Result<(), EncodeError<W::Error>> {
+        let mut buf = String::new();
+        // Writing into a string cannot fail.
+        let _ = write!(buf, "{}", value);
+        self.write(buf.as_bytes())
+    }
+
+    /// Writes a string with quotes, escaping quotes, backslashes and control characters.
+    fn write_str(&mut self, value: &str) -> Result<(), EncodeError<W::Error>> {
+        self.write(b"\"")?;
+        let bytes = value.as_bytes();
+        let mut start = 0;
+        for (ii, byte) in bytes.iter().enumerate() {
+            let escaped: &[u8] = match byte {
+                b'"' => b"\\\"",
+                b'\\' => b"\\\\",
+                b'\n' => b"\\n",
+                b'\r' => b"\\r",
+                b'\t' => b"\\t",
+                0x08 => b"\\b",
+                0x0c => b"\\f",
+                0x00..=0x1f => b"",
+                _ => continue,
+            };
+            self.write(&bytes[start..ii])?;
+            if escaped.is_empty() {
+                self.write_display(format_args!("\\u{:04x}", byte))?;
+            } else {
+                self.write(escaped)?;
+            }
+            start = ii + 1;
+        }
+        self.write(&bytes[start..])?;
+        self.write(b"\"")
+    }
+
+    /// Writes a map from its keys and already serialized values, sorting the keys.
+    pub(crate) fn write_map(
+        &mut self,
+        mut entries: Vec<(String, Vec<u8>)>,
+    ) -> Result<(), EncodeError<W::Error>> {
+        // The keys are sorted by their UTF-8 bytes, which is the order of Rust strings.
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        check_sorted_keys(entries.iter().map(|(key, _)| key.as_str()))?;
+
+        self.write(b"{")?;
+        for (ii, (key, value)) in entries.iter().enumerate() {
+            self.write_key(ii == 0, key)?;
+            self.write(value)?;
+        }
+        self.write(b"}")
+    }
+
+    /// Writes the key of a map entry, preceded by a comma if it isn't the first one.
+    pub(crate) fn write_key(
+        &mut self,
+        first: bool,
+        key: &str,
+    ) -> Result<(), EncodeError<W::Error>> {
+        if !first {
+            self.write(b",")?;
+        }
+        self.write_str(key)?;
+        self.write(b":")
+    }
+
+    /// Writes the opening of a map with a single entry, as used for enum variants.
+    fn write_variant_start(&mut self, variant: &str) -> Result<(), EncodeError<W::Error>> {
+        if variant == RESERVED_KEY {
+            return Err(reserved_key_error());
+        }
+        self.write(b"{")?;
+        self.write_str(variant)?;
+        self.write(b":")
+    }
+}
+
+/// Checks that the sorted keys of a map are unique and don't contain the reserved key.
+pub(crate) fn check_sorted_keys<'a, E, I>(keys: I) -> Result<(), EncodeError<E>>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let mut previous = None;
+    for key in keys {
+        if previous == Some(key) {
+            return Err(EncodeError::Msg("Duplicate map key".into()));
+        }
+        if key == RESERVED_KEY {
+            return Err(reserved_key_error());
+        }
+        previous = Some(key);
+    }
+    Ok(())
+}
+
+fn reserved_key_error<E>() -> EncodeError<E> {
+    EncodeError::Msg("The map key `/` is reserved for links and bytes".into())
+}
+
+impl<'a, W: enc::Write> serde::Serializer for &'a mut Serializer<W> {
+    type Ok = ();
+    type Error = EncodeError<W::Error>;
+
+    type SerializeSeq = Collect<'a, W>;
+    type SerializeTuple = Collect<'a, W>;
+    type SerializeTupleStruct = Collect<'a, W>;
+    type SerializeTupleVariant = Collect<'a, W>;
+    type SerializeMap = CollectMap<'a, W>;
+    type SerializeStruct = CollectMap<'a, W>;
+    type SerializeStructVariant = CollectMap<'a, W>;
+
+    #[inline]
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.write(if v { b"true" } else { b"false" })
+    }
+
+    #[inline]
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.write_display(v)
+    }
+
+    #[inline]
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.write_display(v)
+    }
+
+    #[inline]
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.write_display(v)
+    }
+
+    #[inline]
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        self.write_display(v)
+    }
+
+    #[inline]
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        self.write_display(v)
+    }
+
+    #[inline]
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.write_display(v)
+    }
+
+    #[inline]
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.write_display(v)
+    }
+
+    #[inline]
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.write_display(v)
+    }
+
+    #[inline]
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        self.write_display(v)
+    }
+
+    #[inline]
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        self.write_display(v)
+    }
+
+    #[inline]
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        // Floats are always encoded as f64, like in DAG-CBOR.
+        self.serialize_f64(f64::from(v))
+    }
+
+    #[inline]
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        if !v.is_finite() {
+            Err(EncodeError::Msg(
+                "Float must be a finite number, not Infinity or NaN".into(),
+            ))
+        } else {
+            // The debug representation is the shortest one that roundtrips and always contains a
+            // decimal point or an exponent, so that it's distinguishable from an integer.
+            self.write_display(format_args!("{:?}", v))
+        }
+    }
+
+    #[inline]
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        let mut buf = [0; 4];
+        self.write_str(v.encode_utf8(&mut buf))
+    }
+
+    #[inline]
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        self.write_str(v)
+    }
+
+    #[inline]
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.write(b"{\"/\":{\"bytes\":")?;
+        self.write_str(&encode_base64(v))?;
+        self.write(b"}}")
+    }
+
+    #[inline]
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.write(b"null")
+    }
+
+    #[inline]
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    #[inline]
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.write(b"null")
+    }
+
+    #[inline]
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.serialize_unit()
+    }
+
+    #[inline]
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.write_str(variant)
+    }
+
+    #[inline]
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        if name == CID_SERDE_PRIVATE_IDENTIFIER {
+            let bytes = value.serialize(BytesSerializer(PhantomData))?;
+            let cid = Cid::try_from(bytes).map_err(|error| {
+                EncodeError::Msg(["Invalid CID: ", &error.to_string()].concat())
+            })?;
+            self.write(b"{\"/\":")?;
+            self.write_str(&cid.to_string())?;
+            self.write(b"}")
+        } else {
+            value.serialize(self)
+        }
+    }
+
+    #[inline]
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.write_variant_start(variant)?;
+        value.serialize(&mut *self)?;
+        self.write(b"}")
+    }
+
+    #[inline]
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        self.write(b"[")?;
+        Ok(Collect {
+            ser: self,
+            first: true,
+            end: b"]",
+        })
+    }
+
+    #[inline]
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    #[inline]
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    #[inline]
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        self.write_variant_start(variant)?;
+        self.write(b"[")?;
+        Ok(Collect {
+            ser: self,
+            first: true,
+            end: b"]}",
+        })
+    }
+
+    #[inline]
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(CollectMap {
+            ser: self,
+            entries: Vec::new(),
+            key: None,
+            variant: false,
+        })
+    }
+
+    #[inline]
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.serialize_map(Some(len))
+    }
+
+    #[inline]
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        self.write_variant_start(variant)?;
+        Ok(CollectMap {
+            ser: self,
+            entries: Vec::new(),
+            key: None,
+            variant: true,
+        })
+    }
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        true
+    }
+}
+
+/// Collects the elements of lists.
+pub(crate) struct Collect<'a, W> {
+    ser: &'a mut Serializer<W>,
+    first: bool,
+    /// The bytes that close the list, and possibly the map of an enum variant.
+    end: &'static [u8],
+}
+
+impl<W: enc::Write> Collect<'_, W> {
+    #[inline]
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError<W::Error>> {
+        if !self.first {
+            self.ser.write(b",")?;
+        }
+        self.first = false;
+        value.serialize(&mut *self.ser)
+    }
+}
+
+impl<W: enc::Write> ser::SerializeSeq for Collect<'_, W> {
+    type Ok = ();
+    type Error = EncodeError<W::Error>;
+
+    #[inline]
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.ser.write(self.end)
+    }
+}
+
+impl<W: enc::Write> ser::SerializeTuple for Collect<'_, W> {
+    type Ok = ();
+    type Error = EncodeError<W::Error>;
+
+    #[inline]
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.ser.write(self.end)
+    }
+}
+
+impl<W: enc::Write> ser::SerializeTupleStruct for Collect<'_, W> {
+    type Ok = ();
+    type Error = EncodeError<W::Error>;
+
+    #[inline]
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.ser.write(self.end)
+    }
+}
+
+impl<W: enc::Write> ser::SerializeTupleVariant for Collect<'_, W> {
+    type Ok = ();
+    type Error = EncodeError<W::Error>;
+
+    #[inline]
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.ser.write(self.end)
+    }
+}
+
+/// Collects the entries of maps and structs.
+///
+/// The keys need to be sorted, hence the values are serialized into separate buffers first and
+/// only written to the actual output once all entries are known.
+pub(crate) struct CollectMap<'a, W> {
+    ser: &'a mut Serializer<W>,
+    entries: Vec<(String, Vec<u8>)>,
+    key: Option<String>,
+    /// Whether it's a struct variant, that is wrapped in a map with the variant as key.
+    variant: bool,
+}
+
+impl<W: enc::Write> CollectMap<'_, W> {
+    fn entry<T: Serialize + ?Sized>(
+        &mut self,
+        key: String,
+        value: &T,
+    ) -> Result<(), EncodeError<W::Error>> {
+        self.entries.push((key, to_buffer(value)?));
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), EncodeError<W::Error>> {
+        self.ser.write_map(self.entries)?;
+        if self.variant {
+            self.ser.write(b"}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: enc::Write> ser::SerializeMap for CollectMap<'_, W> {
+    type Ok = ();
+    type Error = EncodeError<W::Error>;
+
+    #[inline]
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.key = Some(key.serialize(KeySerializer(PhantomData))?);
+        Ok(())
+    }
+
+    #[inline]
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| EncodeError::Msg("Map value without a key".into()))?;
+        self.entry(key, value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+impl<W: enc::Write> ser::SerializeStruct for CollectMap<'_, W> {
+    type Ok = ();
+    type Error = EncodeError<W::Error>;
+
+    #[inline]
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.entry(key.into(), value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+impl<W: enc::Write> ser::SerializeStructVariant for CollectMap<'_, W> {
+    type Ok = ();
+    type Error = EncodeError<W::Error>;
+
+    #[inline]
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.entry(key.into(), value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+/// Serializes map keys, which must be strings in DAG-JSON.
+struct KeySerializer<E>(PhantomData<E>);
+
+/// Serializes the bytes of a CID.
+struct BytesSerializer<E>(PhantomData<E>);
+
+/// Implements all methods of a serializer, except for the given one, with an error.
+macro_rules! only {
+    ($name:ident, $msg:expr) => {
+        type SerializeSeq = ser::Impossible<Self::Ok, Self::Error>;
+        type SerializeTuple = ser::Impossible<Self::Ok, Self::Error>;
+        type SerializeTupleStruct = ser::Impossible<Self::Ok, Self::Error>;
+        type SerializeTupleVariant = ser::Impossible<Self::Ok, Self::Error>;
+        type SerializeMap = ser::Impossible<Self::Ok, Self::Error>;
+        type SerializeStruct = ser::Impossible<Self::Ok, Self::Error>;
+        type SerializeStructVariant = ser::Impossible<Self::Ok, Self::Error>;
+
+        only!(@ $name, $msg;
+            serialize_bool(bool) serialize_i8(i8) serialize_i16(i16) serialize_i32(i32)
+            serialize_i64(i64) serialize_u8(u8) serialize_u16(u16) serialize_u32(u32)
+            serialize_u64(u64) serialize_f32(f32) serialize_f64(f64) serialize_char(char)
+            serialize_str(&str) serialize_bytes(&[u8])
+            serialize_unit_struct(&'static str)
+        );
+
+        fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+            Err(EncodeError::Msg($msg.into()))
+        }
+        fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<Self::Ok, Self::Error> {
+            Err(EncodeError::Msg($msg.into()))
+        }
+        fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+            Err(EncodeError::Msg($msg.into()))
+        }
+        fn serialize_unit_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+        ) -> Result<Self::Ok, Self::Error> {
+            Err(EncodeError::Msg($msg.into()))
+        }
+        fn serialize_newtype_struct<T: ?Sized + Serialize>(
+            self,
+            _name: &'static str,
+            _value: &T,
+        ) -> Result<Self::Ok, Self::Error> {
+            Err(EncodeError::Msg($msg.into()))
+        }
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _value: &T,
+        ) -> Result<Self::Ok, Self::Error> {
+            Err(EncodeError::Msg($msg.into()))
+        }
+        fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+            Err(EncodeError::Msg($msg.into()))
+        }
+        fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+            Err(EncodeError::Msg($msg.into()))
+        }
+        fn serialize_tuple_struct(
+            self,
+            _name: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+            Err(EncodeError::Msg($msg.into()))
+        }
+        fn serialize_tuple_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+            Err(EncodeError::Msg($msg.into()))
+        }
+        fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+            Err(EncodeError::Msg($msg.into()))
+        }
+        fn serialize_struct(
+            self,
+            _name: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeStruct, Self::Error> {
+            Err(EncodeError::Msg($msg.into()))
+        }
+        fn serialize_struct_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeStructVariant, Self::Error> {
+            Err(EncodeError::Msg($msg.into()))
+        }
+    };
+    (@ $name:ident, $msg:expr; $( $method:ident ( $t:ty ) )* ) => {
+        $(
+            only!(@@ $name, $msg, $method, $t);
+        )*
+    };
+    (@@ serialize_str, $msg:expr, serialize_str, $t:ty) => {
+        fn serialize_str(self, value: &str) -> Result<Self::Ok, Self::Error> {
+            Ok(value.into())
+        }
+    };
+    (@@ serialize_str, $msg:expr, serialize_char, $t:ty) => {
+        fn serialize_char(self, value: char) -> Result<Self::Ok, Self::Error> {
+            Ok(value.into())
+        }
+    };
+    (@@ serialize_bytes, $msg:expr, serialize_bytes, $t:ty) => {
+        fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok, Self::Error> {
+            Ok(value.into())
+        }
+    };
+    (@@ $name:ident, $msg:expr, $method:ident, $t:ty) => {
+        fn $method(self, _value: $t) -> Result<Self::Ok, Self::Error> {
+            Err(EncodeError::Msg($msg.into()))
+        }
+    };
+}
+
+impl<E> ser::Serializer for KeySerializer<E>
+where
+    EncodeError<E>: ser::Error,
+{
+    type Ok = String;
+    type Error = EncodeError<E>;
+
+    only!(serialize_str, "Map keys must be strings");
+}
+
+impl<E> ser::Serializer for BytesSerializer<E>
+where
+    EncodeError<E>: ser::Error,
+{
+    type Ok = Vec<u8>;
+    type Error = EncodeError<E>;
+
+    only!(serialize_bytes, "CIDs must be serialized as bytes");
+}
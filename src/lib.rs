@@ -103,6 +103,43 @@
 //!
 //! Without the `std` feature the functions [from_reader], and [to_writer] are not exported.
 //!
+//! # Hashing
+//!
+//! The `hash` feature, which is enabled by default, adds the `multihash` dependency for computing
+//! and verifying CIDs. It provides `to_cid`, `to_vec_with_cid`, the `hash` module and
+//! `Block::encode`/`Block::verify`. The `blockstore`, `car`, `path`, `selector`, `walk` and
+//! `schema` modules need it as well. Users that only encode and decode values can disable it.
+//!
+//! # Asynchronous I/O
+//!
+//! Values can be read from and written to asynchronous streams with the functions of the
+//! `async_io` module. Enable the `futures` feature for `futures::io` and the `tokio` feature
+//! for `tokio::io` support.
+//!
+//! # DAG-JSON
+//!
+//! The same types can be stored as DAG-JSON with the functions of the [`json`] module. Encoded
+//! data can be converted between the two codecs with the `transcode` module.
+//!
+//! # Diagnostic notation
+//!
+//! The [`diag`] module renders encoded data as CBOR diagnostic notation and parses it back into
+//! bytes, which is handy for debugging and for writing test fixtures. The [`explain`] module
+//! creates annotated hex dumps that flag violations of the DAG-CBOR spec.
+//!
+//! # Representation strategies
+//!
+//! With the `derive` feature the `DagCborSerialize` and `DagCborDeserialize` derive macros
+//! implement the representation strategies of IPLD Schemas that Serde's attributes can't express,
+//! like structs encoded as tuples with optional trailing fields or kinded unions. See the
+//! `serde_ipld_dagcbor_derive` crate for the details.
+//!
+//! # Command-line tool
+//!
+//! The `dagcbor` binary, enabled by the `cli` feature, makes those available on the command line,
+//! together with DAG-JSON conversion, CID computation and listing links. Run `dagcbor help` for
+//! the details.
+//!
 //! *Note*: to use derive macros in serde you will need to declare `serde`
 //! dependency like so:
 //! ``` toml
@@ -115,13 +152,42 @@
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
+#[cfg(any(feature = "futures", feature = "tokio"))]
+pub mod async_io;
+pub mod block;
+#[cfg(all(feature = "std", feature = "hash"))]
+pub mod blockstore;
+#[cfg(all(feature = "std", feature = "hash"))]
+pub mod car;
 mod cbor4ii_nonpub;
 pub mod de;
+pub mod diag;
 pub mod error;
+pub mod explain;
+#[cfg(feature = "hash")]
+pub mod hash;
+pub mod json;
+pub mod link;
+pub mod node;
+#[cfg(all(feature = "std", feature = "hash"))]
+pub mod path;
+#[cfg(all(feature = "std", feature = "hash"))]
+pub mod schema;
+#[cfg(all(feature = "std", feature = "hash"))]
+pub mod selector;
 pub mod ser;
+#[cfg(feature = "std")]
+pub mod transcode;
+#[cfg(all(feature = "std", feature = "hash"))]
+pub mod walk;
 
 #[doc(inline)]
-pub use crate::error::{DecodeError, EncodeError};
+pub use crate::block::Block;
+#[cfg(all(feature = "std", feature = "hash"))]
+#[doc(inline)]
+pub use crate::error::{BlockstoreError, CarError, SchemaError, TraversalError};
+#[doc(inline)]
+pub use crate::error::{DecodeError, DiagError, EncodeError, TranscodeError, VerifyError};
 
 // Convenience functions for serialization and deserialization.
 #[doc(inline)]
@@ -134,9 +200,29 @@ pub use crate::de::from_reader;
 #[doc(inline)]
 pub use crate::ser::to_vec;
 
+#[cfg(feature = "hash")]
+#[doc(inline)]
+pub use crate::ser::{to_cid, to_vec_with_cid};
+
 #[cfg(feature = "std")]
 #[doc(inline)]
 pub use crate::ser::to_writer;
 
+#[cfg(feature = "derive")]
+pub use serde_ipld_dagcbor_derive::{DagCborDeserialize, DagCborSerialize};
+
+// Used by the code the derive macros generate, not public API.
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(not(feature = "std"))]
+    pub use alloc::string::{String, ToString};
+    pub use serde;
+    #[cfg(feature = "std")]
+    pub use std::string::{String, ToString};
+}
+
 /// The CBOR tag that is used for CIDs.
 const CBOR_TAGS_CID: u64 = 42;
+
+/// The multicodec code of DAG-CBOR.
+pub const DAG_CBOR_CODEC: u64 = 0x71;
@@ -0,0 +1,158 @@
+//! Typed links.
+//!
+//! A [`Link<T>`] is a CID that points to a block containing a value of type `T`. It is encoded
+//! exactly like a [`Cid`], the type only exists on the Rust side.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(not(all(feature = "std", feature = "hash")))]
+//! # fn main() {}
+//! # #[cfg(all(feature = "std", feature = "hash"))]
+//! # fn main() {
+//! use serde_derive::{Deserialize, Serialize};
+//! use serde_ipld_dagcbor::blockstore::MemoryBlockstore;
+//! use serde_ipld_dagcbor::link::Link;
+//!
+//! #[derive(Debug, PartialEq, Deserialize, Serialize)]
+//! struct Node {
+//!     name: String,
+//!     parent: Option<Link<Node>>,
+//! }
+//!
+//! let mut store = MemoryBlockstore::new();
+//! let root = Node { name: "root".into(), parent: None };
+//! let root_link = Link::store(&root, &mut store).unwrap();
+//! let child = Node { name: "child".into(), parent: Some(root_link) };
+//!
+//! let parent = child.parent.unwrap().load(&store).unwrap();
+//! assert_eq!(parent, root);
+//! # }
+//! ```
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+
+use cid::Cid;
+use serde::{de, ser};
+
+#[cfg(all(feature = "std", feature = "hash"))]
+use crate::blockstore::Blockstore;
+#[cfg(all(feature = "std", feature = "hash"))]
+use crate::error::BlockstoreError;
+
+/// A CID pointing to a block that contains a value of type `T`.
+pub struct Link<T> {
+    cid: Cid,
+    // `fn() -> T` makes the link `Send` and `Sync` independent of `T`.
+    _target: PhantomData<fn() -> T>,
+}
+
+impl<T> Link<T> {
+    /// Creates a link from a CID. It's not checked whether the block it points to is of type `T`.
+    pub fn new(cid: Cid) -> Self {
+        Link {
+            cid,
+            _target: PhantomData,
+        }
+    }
+
+    /// Returns the CID.
+    pub fn cid(&self) -> &Cid {
+        &self.cid
+    }
+
+    /// Returns the CID, dropping the type information.
+    pub fn into_cid(self) -> Cid {
+        self.cid
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl<T: de::DeserializeOwned> Link<T> {
+    /// Loads the block the link points to and deserializes it.
+    pub fn load<S: Blockstore>(&self, store: &S) -> Result<T, BlockstoreError> {
+        store.get_value(&self.cid)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hash"))]
+impl<T: ser::Serialize> Link<T> {
+    /// Serializes a value, stores it and returns a link to it.
+    pub fn store<S: Blockstore>(value: &T, store: &mut S) -> Result<Self, BlockstoreError> {
+        store.put_value(value).map(Link::new)
+    }
+}
+
+impl<T> From<Cid> for Link<T> {
+    fn from(cid: Cid) -> Self {
+        Link::new(cid)
+    }
+}
+
+impl<T> From<Link<T>> for Cid {
+    fn from(link: Link<T>) -> Self {
+        link.cid
+    }
+}
+
+// The traits are implemented manually, so that they don't require `T` to implement them.
+
+impl<T> Clone for Link<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Link<T> {}
+
+impl<T> PartialEq for Link<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cid == other.cid
+    }
+}
+
+impl<T> Eq for Link<T> {}
+
+impl<T> PartialOrd for Link<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Link<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cid.cmp(&other.cid)
+    }
+}
+
+impl<T> Hash for Link<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&self.cid, state)
+    }
+}
+
+impl<T> fmt::Debug for Link<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Link").field(&self.cid).finish()
+    }
+}
+
+impl<T> fmt::Display for Link<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.cid, f)
+    }
+}
+
+impl<T> ser::Serialize for Link<T> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::ser::serialize_cid(&self.cid, serializer)
+    }
+}
+
+impl<'de, T> de::Deserialize<'de> for Link<T> {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Cid::deserialize(deserializer).map(Link::new)
+    }
+}
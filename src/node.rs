@@ -0,0 +1,228 @@
+//! A lazy view of DAG-CBOR encoded data.
+//!
+//! A [`Node`] borrows the encoded bytes of a single data item. Nothing is decoded upfront, lists
+//! and maps are only scanned when their children are accessed. This makes it cheap to look at a
+//! small part of a large block, as done by [path resolution] and [selectors].
+//!
+//! [path resolution]: crate::path
+//! [selectors]: crate::selector
+//!
+//! # Examples
+//!
+//! ```
+//! use serde_ipld_dagcbor::node::{Kind, Node};
+//!
+//! let data = serde_ipld_dagcbor::diag::from_diag(r#"{"a": [1, "two"]}"#).unwrap();
+//! let node = Node::new(&data).unwrap();
+//! assert_eq!(node.kind(), Kind::Map);
+//! let list = node.get_field("a").unwrap().unwrap();
+//! assert_eq!(list.get_index(1).unwrap().unwrap().as_str(), Some("two"));
+//! ```
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::convert::{Infallible, TryFrom};
+
+use cbor4ii::core::dec::{self, Decode};
+use cbor4ii::core::utils::SliceReader;
+use cbor4ii::core::{major, types};
+use cid::Cid;
+use serde::de::Deserialize;
+
+use crate::block::has_next;
+use crate::cbor4ii_nonpub::marker;
+use crate::error::DecodeError;
+use crate::CBOR_TAGS_CID;
+
+/// The kinds of the IPLD Data Model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// Null.
+    Null,
+    /// A boolean.
+    Bool,
+    /// A signed or unsigned integer.
+    Integer,
+    /// A floating point number.
+    Float,
+    /// A UTF-8 string.
+    String,
+    /// A byte string.
+    Bytes,
+    /// A list.
+    List,
+    /// A map.
+    Map,
+    /// A link (CBOR tag 42).
+    Link,
+}
+
+/// A single DAG-CBOR encoded data item that is decoded on demand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Node<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Node<'a> {
+    /// Creates a node from data that contains exactly one data item.
+    ///
+    /// The structure of the whole item is checked, but its values are not validated.
+    pub fn new(data: &'a [u8]) -> Result<Self, DecodeError<Infallible>> {
+        let mut reader = SliceReader::new(data);
+        dec::IgnoredAny::decode(&mut reader)?;
+        if remaining(&mut reader).is_empty() {
+            Ok(Node::new_unchecked(data))
+        } else {
+            Err(DecodeError::TrailingData)
+        }
+    }
+
+    /// Creates a node from data that is known to contain exactly one well-formed data item.
+    pub(crate) fn new_unchecked(data: &'a [u8]) -> Self {
+        Node { data }
+    }
+
+    /// Returns the encoded data of the node.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns the kind of the node.
+    ///
+    /// Simple values other than booleans and null are reported as [`Kind::Null`], all tags as
+    /// [`Kind::Link`].
+    pub fn kind(&self) -> Kind {
+        let byte = self.data[0];
+        match dec::if_major(byte) {
+            major::UNSIGNED | major::NEGATIVE => Kind::Integer,
+            major::BYTES => Kind::Bytes,
+            major::STRING => Kind::String,
+            major::ARRAY => Kind::List,
+            major::MAP => Kind::Map,
+            major::TAG => Kind::Link,
+            _ => match byte {
+                marker::FALSE | marker::TRUE => Kind::Bool,
+                marker::F16 | marker::F32 | marker::F64 => Kind::Float,
+                _ => Kind::Null,
+            },
+        }
+    }
+
+    /// Returns the boolean, `None` if the node is of another kind.
+    pub fn as_bool(&self) -> Option<bool> {
+        bool::decode(&mut self.reader()).ok()
+    }
+
+    /// Returns the integer, `None` if the node is of another kind.
+    pub fn as_integer(&self) -> Option<i128> {
+        i128::decode(&mut self.reader()).ok()
+    }
+
+    /// Returns the floating point number, `None` if the node is of another kind.
+    pub fn as_float(&self) -> Option<f64> {
+        f64::decode(&mut self.reader()).ok()
+    }
+
+    /// Returns the string, `None` if the node is of another kind or not valid UTF-8.
+    pub fn as_str(&self) -> Option<&'a str> {
+        <&str>::decode(&mut self.reader()).ok()
+    }
+
+    /// Returns the bytes, `None` if the node is of another kind.
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        <types::Bytes<&[u8]>>::decode(&mut self.reader())
+            .ok()
+            .map(|bytes| bytes.0)
+    }
+
+    /// Returns the CID of a link, `None` if the node is of another kind or no valid CID.
+    pub fn as_link(&self) -> Option<Cid> {
+        let mut reader = self.reader();
+        if dec::TagStart::decode(&mut reader).ok()?.0 != CBOR_TAGS_CID {
+            return None;
+        }
+        let bytes = <types::Bytes<&[u8]>>::decode(&mut reader).ok()?.0;
+        // CBOR encoded CIDs have a zero byte prefix we have to remove.
+        match bytes.split_first() {
+            Some((0, cid)) => Cid::try_from(cid).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of a list.
+    pub fn elements(&self) -> Result<Vec<Node<'a>>, DecodeError<Infallible>> {
+        let mut reader = self.reader();
+        let len = dec::ArrayStart::decode(&mut reader)?.0;
+        let mut elements = Vec::new();
+        while has_next(&mut reader, len, elements.len())? {
+            elements.push(next_node(&mut reader)?);
+        }
+        Ok(elements)
+    }
+
+    /// Returns the entries of a map in the order they are encoded.
+    pub fn entries(&self) -> Result<Vec<(&'a str, Node<'a>)>, DecodeError<Infallible>> {
+        let mut reader = self.reader();
+        let len = dec::MapStart::decode(&mut reader)?.0;
+        let mut entries = Vec::new();
+        while has_next(&mut reader, len, entries.len())? {
+            let key = <&str>::decode(&mut reader)?;
+            entries.push((key, next_node(&mut reader)?));
+        }
+        Ok(entries)
+    }
+
+    /// Returns the element of a list at the given index, `None` if it's out of bounds.
+    pub fn get_index(&self, index: usize) -> Result<Option<Node<'a>>, DecodeError<Infallible>> {
+        let mut reader = self.reader();
+        let len = dec::ArrayStart::decode(&mut reader)?.0;
+        let mut count = 0;
+        while has_next(&mut reader, len, count)? {
+            if count == index {
+                return next_node(&mut reader).map(Some);
+            }
+            dec::IgnoredAny::decode(&mut reader)?;
+            count += 1;
+        }
+        Ok(None)
+    }
+
+    /// Returns the value of a map with the given key, `None` if there is no such key.
+    pub fn get_field(&self, key: &str) -> Result<Option<Node<'a>>, DecodeError<Infallible>> {
+        let mut reader = self.reader();
+        let len = dec::MapStart::decode(&mut reader)?.0;
+        let mut count = 0;
+        while has_next(&mut reader, len, count)? {
+            if <&str>::decode(&mut reader)? == key {
+                return next_node(&mut reader).map(Some);
+            }
+            dec::IgnoredAny::decode(&mut reader)?;
+            count += 1;
+        }
+        Ok(None)
+    }
+
+    /// Deserializes the node into a value.
+    pub fn decode<T: Deserialize<'a>>(&self) -> Result<T, DecodeError<Infallible>> {
+        crate::from_slice(self.data)
+    }
+
+    fn reader(&self) -> SliceReader<'a> {
+        SliceReader::new(self.data)
+    }
+}
+
+/// Returns the data that wasn't read yet.
+fn remaining<'a>(reader: &mut SliceReader<'a>) -> &'a [u8] {
+    match dec::Read::fill(reader, usize::MAX) {
+        Ok(dec::Reference::Long(buf)) => buf,
+        _ => unreachable!("slice readers always return borrowed data"),
+    }
+}
+
+/// Skips over the next data item and returns it as node.
+fn next_node<'a>(reader: &mut SliceReader<'a>) -> Result<Node<'a>, DecodeError<Infallible>> {
+    let before = remaining(reader);
+    dec::IgnoredAny::decode(reader)?;
+    let after = remaining(reader);
+    Ok(Node::new_unchecked(&before[..before.len() - after.len()]))
+}
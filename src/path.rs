@@ -0,0 +1,161 @@
+//! Resolution of IPLD paths across block boundaries.
+//!
+//! A [`Path`] is a sequence of segments separated by slashes, like `links/0/Hash/name`. Map
+//! fields are selected by key, list elements by index. Whenever a link is traversed, the block it
+//! points to is loaded from a [`Blockstore`] and the resolution continues at its root.
+//!
+//! # Examples
+//!
+//! ```
+//! use serde_ipld_dagcbor::blockstore::{Blockstore, MemoryBlockstore};
+//! use serde_ipld_dagcbor::path::{resolve, Path};
+//!
+//! let mut store = MemoryBlockstore::new();
+//! let leaf = store.put_value(&vec!["a", "b"]).unwrap();
+//! let root = store.put_value(&vec![leaf]).unwrap();
+//!
+//! let resolved = resolve(&store, &root, &Path::from("0/1")).unwrap();
+//! assert_eq!(resolved.node().as_str(), Some("b"));
+//! assert_eq!(resolved.blocks, [root, leaf]);
+//! ```
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use cid::Cid;
+
+use crate::blockstore::Blockstore;
+use crate::error::{BlockstoreError, TraversalError};
+use crate::node::{Kind, Node};
+
+/// A path within a DAG.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Path {
+    segments: Vec<String>,
+}
+
+impl Path {
+    /// Creates a path from its segments.
+    pub fn new(segments: Vec<String>) -> Self {
+        Path { segments }
+    }
+
+    /// Returns the segments.
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+
+    /// Returns true if the path has no segments, it then points to the root.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns a new path with a segment appended.
+    pub fn join<S: Into<String>>(&self, segment: S) -> Path {
+        let mut segments = self.segments.clone();
+        segments.push(segment.into());
+        Path { segments }
+    }
+}
+
+impl From<&str> for Path {
+    /// Splits the string at slashes, empty segments are ignored.
+    fn from(path: &str) -> Self {
+        Path {
+            segments: path
+                .split('/')
+                .filter(|segment| !segment.is_empty())
+                .map(String::from)
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for Path {
+    type Err = core::convert::Infallible;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        Ok(Path::from(path))
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.segments.join("/"))
+    }
+}
+
+/// The node a path resolved to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resolved {
+    /// The CID of the block that contains the node.
+    pub cid: Cid,
+    /// The encoded data of the node.
+    pub data: Vec<u8>,
+    /// The CIDs of the blocks that were loaded, in the order they were loaded.
+    pub blocks: Vec<Cid>,
+}
+
+impl Resolved {
+    /// Returns a view of the node.
+    pub fn node(&self) -> Node<'_> {
+        Node::new(&self.data).expect("the data was taken from a valid node")
+    }
+}
+
+/// Resolves a path starting at the block with the given CID.
+///
+/// A link at the end of the path is not followed, the resolved node is the link itself.
+pub fn resolve<S: Blockstore>(
+    store: &S,
+    root: &Cid,
+    path: &Path,
+) -> Result<Resolved, TraversalError> {
+    let mut cid = *root;
+    let mut block = load(store, &cid)?;
+    let mut blocks = vec![cid];
+    // The range of the current node within the current block.
+    let mut range = 0..block.len();
+    for (ii, segment) in path.segments().iter().enumerate() {
+        let mut node = Node::new_unchecked(&block[range.clone()]);
+        if node.kind() == Kind::Link {
+            cid = node.as_link().ok_or_else(|| not_found(path, ii))?;
+            block = load(store, &cid)?;
+            blocks.push(cid);
+            node = Node::new_unchecked(&block);
+        }
+        let child = match node.kind() {
+            Kind::Map => node.get_field(segment)?,
+            Kind::List => match segment.parse() {
+                Ok(index) => node.get_index(index)?,
+                Err(_) => None,
+            },
+            _ => None,
+        }
+        .ok_or_else(|| not_found(path, ii))?;
+        range = subrange(&block, child.data());
+    }
+    Ok(Resolved {
+        cid,
+        data: block[range].to_vec(),
+        blocks,
+    })
+}
+
+/// Loads a block and checks that it's a single data item.
+pub(crate) fn load<S: Blockstore>(store: &S, cid: &Cid) -> Result<Vec<u8>, TraversalError> {
+    let data = store.get(cid)?.ok_or(BlockstoreError::NotFound(*cid))?;
+    Node::new(&data)?;
+    Ok(data)
+}
+
+/// Returns the range of a slice within the slice it was taken from.
+pub(crate) fn subrange(outer: &[u8], inner: &[u8]) -> Range<usize> {
+    let start = inner.as_ptr() as usize - outer.as_ptr() as usize;
+    start..start + inner.len()
+}
+
+/// Returns an error with the path up to the segment at `index`, which doesn't exist.
+fn not_found(path: &Path, index: usize) -> TraversalError {
+    TraversalError::NotFound(Path::new(path.segments()[..=index].to_vec()))
+}
@@ -0,0 +1,1147 @@
+//! Generation of Rust types from IPLD Schemas.
+//!
+//! The [`Generator`] emits a Rust type for every type of a [`Schema`], together with `Serialize`
+//! and `Deserialize` implementations that follow the representation strategy of the schema. Where
+//! Serde's attributes are sufficient the implementations are derived, otherwise they are
+//! generated. It's meant to be called from a build script, see [`compile`].
+//!
+//! The generated code uses the crates `serde`, `serde_bytes`, `cid` and `serde_ipld_dagcbor`,
+//! they need to be dependencies of the crate that includes it. Types are mapped as follows:
+//!
+//!  - `Bool`, `String`, `Int` and `Float` become `bool`, `String`, `i64` and `f64`
+//!  - `Bytes` becomes `serde_bytes::ByteBuf`
+//!  - `&Foo` becomes `Link<Foo>`, `&Any` becomes `Cid`
+//!  - lists become `Vec`, maps with map representation become `BTreeMap` and nullable values
+//!    become `Option`
+//!  - structs become structs, optional fields are `Option`s that are omitted if they are `None`
+//!  - unions become enums with a newtype variant for every member
+//!  - enums become enums with unit variants, string enums also implement `Display` and `FromStr`
+//!
+//! Fields of structs with map representation are declared in DAG-CBOR key order, so that they are
+//! serialized canonically.
+//!
+//! Schemas that cannot be expressed result in a [`SchemaError::Unsupported`]. These are the `Any`
+//! type, map keys other than strings, fields that are optional and nullable, structs with
+//! `listpairs` representation and links within the members of envelope and inline unions, as
+//! those are buffered during deserialization.
+//!
+//! # Examples
+//!
+//! A build script that generates the types of `schema.ipldsch`:
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! let out = Path::new(&std::env::var("OUT_DIR").unwrap()).join("schema.rs");
+//! serde_ipld_dagcbor::schema::codegen::compile("schema.ipldsch", out).unwrap();
+//! println!("cargo:rerun-if-changed=schema.ipldsch");
+//! ```
+//!
+//! The generated file is then included with
+//! `include!(concat!(env!("OUT_DIR"), "/schema.rs"));`.
+use std::collections::HashSet;
+use std::fs;
+
+use super::{
+    EnumRepresentation, EnumType, LinkType, MapRepresentation, MapType, Schema, StructField,
+    StructRepresentation, StructType, Type, TypeTerm, UnionRepresentation,
+};
+use crate::error::SchemaError;
+use crate::node::Kind;
+
+/// The identifiers that need to be written as raw identifiers.
+const KEYWORDS: [&str; 46] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while",
+];
+
+/// Reads a schema in the DSL from a file and writes the generated Rust code to another file.
+///
+/// This is a convenience function for build scripts.
+pub fn compile<P, Q>(schema: P, out: Q) -> Result<(), SchemaError>
+where
+    P: AsRef<std::path::Path>,
+    Q: AsRef<std::path::Path>,
+{
+    let schema = Schema::parse(&fs::read_to_string(schema)?)?;
+    let code = Generator::new(&schema).generate()?;
+    fs::write(out, code)?;
+    Ok(())
+}
+
+/// Generates Rust code from a schema.
+#[derive(Debug)]
+pub struct Generator<'a> {
+    schema: &'a Schema,
+    derive_crate: String,
+}
+
+impl<'a> Generator<'a> {
+    /// Creates a generator for the given schema.
+    pub fn new(schema: &'a Schema) -> Self {
+        Generator {
+            schema,
+            derive_crate: "serde".to_string(),
+        }
+    }
+
+    /// Sets the crate the `Serialize` and `Deserialize` derive macros are taken from, the default
+    /// is `serde`. Crates that depend on `serde_derive` directly set it to `serde_derive`.
+    pub fn set_derive_crate(&mut self, name: &str) {
+        self.derive_crate = name.to_string();
+    }
+
+    /// Returns the generated code of all types of the schema.
+    pub fn generate(&self) -> Result<String, SchemaError> {
+        let mut out = String::new();
+        out.push_str("// Generated by serde_ipld_dagcbor from an IPLD Schema, do not edit.\n");
+        for (name, ty) in self.schema.types() {
+            out.push('\n');
+            self.definition(&mut out, name, ty)?;
+        }
+        Ok(out)
+    }
+
+    fn definition(&self, out: &mut String, name: &str, ty: &Type) -> Result<(), SchemaError> {
+        match ty {
+            Type::Bool | Type::String | Type::Bytes | Type::Int | Type::Float | Type::List(_) => {
+                let rust = self.inline_type(ty)?;
+                out.push_str(&format!("pub type {} = {};\n", name, rust));
+            }
+            Type::Link(link) => {
+                out.push_str(&format!("pub type {} = {};\n", name, self.link_type(link)));
+            }
+            Type::Any => return Err(unsupported("the `Any` type")),
+            Type::Map(map) => self.map(out, name, map)?,
+            Type::Struct(structure) => self.structure(out, name, structure)?,
+            Type::Union(union) => self.union(out, name, union)?,
+            Type::Enum(enumeration) => self.enumeration(out, name, enumeration)?,
+        }
+        Ok(())
+    }
+
+    /// Returns the Rust type of a type term.
+    fn rust_type(&self, term: &TypeTerm) -> Result<String, SchemaError> {
+        match term {
+            TypeTerm::Named(name) => match name.as_str() {
+                "Bool" => Ok("bool".to_string()),
+                "String" => Ok("String".to_string()),
+                "Bytes" => Ok("::serde_bytes::ByteBuf".to_string()),
+                "Int" => Ok("i64".to_string()),
+                "Float" => Ok("f64".to_string()),
+                "Link" => Ok("::cid::Cid".to_string()),
+                "Any" | "Map" | "List" => Err(unsupported("the `Any` type")),
+                _ => Ok(name.clone()),
+            },
+            TypeTerm::Inline(ty) => self.inline_type(ty),
+        }
+    }
+
+    /// Returns the Rust type of a type that doesn't need a definition of its own.
+    fn inline_type(&self, ty: &Type) -> Result<String, SchemaError> {
+        let nullable = |rust: String, nullable: bool| {
+            if nullable {
+                format!("Option<{}>", rust)
+            } else {
+                rust
+            }
+        };
+        match ty {
+            Type::Bool => self.rust_type(&named("Bool")),
+            Type::String => self.rust_type(&named("String")),
+            Type::Bytes => self.rust_type(&named("Bytes")),
+            Type::Int => self.rust_type(&named("Int")),
+            Type::Float => self.rust_type(&named("Float")),
+            Type::List(list) => Ok(format!(
+                "Vec<{}>",
+                nullable(self.rust_type(&list.value_type)?, list.value_nullable)
+            )),
+            Type::Map(map) if map.representation == MapRepresentation::Map => Ok(format!(
+                "::std::collections::BTreeMap<{}>",
+                self.map_parameters(map)?
+            )),
+            Type::Link(link) => Ok(self.link_type(link)),
+            _ => Err(unsupported("inline types other than maps, lists and links")),
+        }
+    }
+
+    fn link_type(&self, link: &LinkType) -> String {
+        match &link.expected_type {
+            Some(name) => format!("::serde_ipld_dagcbor::link::Link<{}>", name),
+            None => "::cid::Cid".to_string(),
+        }
+    }
+
+    /// Returns the key and value type of a map, e.g. `String, i64`.
+    fn map_parameters(&self, map: &MapType) -> Result<String, SchemaError> {
+        if self.resolve(&named(&map.key_type))? != Type::String {
+            return Err(unsupported("map keys other than strings"));
+        }
+        let value = self.rust_type(&map.value_type)?;
+        Ok(if map.value_nullable {
+            format!("String, Option<{}>", value)
+        } else {
+            format!("String, {}", value)
+        })
+    }
+
+    fn resolve(&self, term: &TypeTerm) -> Result<Type, SchemaError> {
+        match term {
+            TypeTerm::Named(name) => self.schema.resolve(name).map(|ty| ty.into_owned()),
+            TypeTerm::Inline(ty) => Ok(ty.as_ref().clone()),
+        }
+    }
+
+    /// Checks that a type can be represented as string with `Display` and `FromStr`.
+    fn check_string_representable(&self, term: &TypeTerm) -> Result<(), SchemaError> {
+        match self.resolve(term)? {
+            Type::Bool | Type::String | Type::Int | Type::Float => Ok(()),
+            Type::Enum(EnumType {
+                representation: EnumRepresentation::String(_),
+                ..
+            }) => Ok(()),
+            _ => Err(unsupported(
+                "types that can't be represented as string in `stringpairs` or `stringjoin`",
+            )),
+        }
+    }
+
+    /// Returns whether values of a type may contain links.
+    fn contains_link(
+        &self,
+        term: &TypeTerm,
+        seen: &mut HashSet<String>,
+    ) -> Result<bool, SchemaError> {
+        if let TypeTerm::Named(name) = term {
+            if !seen.insert(name.clone()) {
+                return Ok(false);
+            }
+        }
+        let terms: Vec<TypeTerm> = match self.resolve(term)? {
+            Type::Link(_) | Type::Any => return Ok(true),
+            Type::Map(map) => vec![map.value_type],
+            Type::List(list) => vec![list.value_type],
+            Type::Struct(structure) => structure
+                .fields
+                .into_iter()
+                .map(|field| field.value_type)
+                .collect(),
+            Type::Union(UnionRepresentation::Keyed(members))
+            | Type::Union(UnionRepresentation::Envelope {
+                discriminants: members,
+                ..
+            }) => members.into_iter().map(|(_, member)| member).collect(),
+            Type::Union(UnionRepresentation::Kinded(members)) => {
+                members.into_iter().map(|(_, member)| member).collect()
+            }
+            Type::Union(UnionRepresentation::Inline { discriminants, .. }) => discriminants
+                .into_iter()
+                .map(|(_, member)| TypeTerm::Named(member))
+                .collect(),
+            _ => Vec::new(),
+        };
+        for term in &terms {
+            if self.contains_link(term, seen)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn derive(&self, out: &mut String, traits: &[&str]) {
+        let traits: Vec<_> = traits
+            .iter()
+            .map(|name| match *name {
+                "Serialize" | "Deserialize" => format!("{}::{}", self.derive_crate, name),
+                _ => name.to_string(),
+            })
+            .collect();
+        out.push_str(&format!("#[derive({})]\n", traits.join(", ")));
+    }
+
+    fn map(&self, out: &mut String, name: &str, map: &MapType) -> Result<(), SchemaError> {
+        let parameters = self.map_parameters(map)?;
+        if map.representation == MapRepresentation::Map {
+            out.push_str(&format!(
+                "pub type {} = ::std::collections::BTreeMap<{}>;\n",
+                name, parameters
+            ));
+            return Ok(());
+        }
+        self.derive(out, &["Clone", "Debug", "Default", "PartialEq"]);
+        out.push_str(&format!(
+            "pub struct {}(pub ::std::collections::BTreeMap<{}>);\n",
+            name, parameters
+        ));
+        match &map.representation {
+            MapRepresentation::StringPairs {
+                inner_delim,
+                entry_delim,
+            } => {
+                if map.value_nullable {
+                    return Err(unsupported("nullable values in `stringpairs` maps"));
+                }
+                self.check_string_representable(&map.value_type)?;
+                out.push_str(&format!(
+                    r#"
+impl ::serde::Serialize for {name} {{
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        let entries: Vec<String> = self
+            .0
+            .iter()
+            .map(|(key, value)| format!("{{}}{{}}{{}}", key, {inner:?}, value))
+            .collect();
+        serializer.serialize_str(&entries.join({entry:?}))
+    }}
+}}
+
+impl<'de> ::serde::Deserialize<'de> for {name} {{
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{
+        let string = <String as ::serde::Deserialize>::deserialize(deserializer)?;
+        let mut map = ::std::collections::BTreeMap::new();
+        if !string.is_empty() {{
+            for entry in string.split({entry:?}) {{
+                let (key, value) = entry.split_once({inner:?}).ok_or_else(|| {{
+                    ::serde::de::Error::custom(format!("entry `{{}}` has no delimiter", entry))
+                }})?;
+                let value = value.parse().map_err(::serde::de::Error::custom)?;
+                map.insert(key.to_string(), value);
+            }}
+        }}
+        Ok({name}(map))
+    }}
+}}
+"#,
+                    name = name,
+                    inner = inner_delim,
+                    entry = entry_delim
+                ));
+            }
+            MapRepresentation::ListPairs => {
+                out.push_str(&format!(
+                    r#"
+impl ::serde::Serialize for {name} {{
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        serializer.collect_seq(self.0.iter())
+    }}
+}}
+
+impl<'de> ::serde::Deserialize<'de> for {name} {{
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{
+        let pairs: Vec<({parameters})> = ::serde::Deserialize::deserialize(deserializer)?;
+        Ok({name}(pairs.into_iter().collect()))
+    }}
+}}
+"#,
+                    name = name,
+                    parameters = parameters
+                ));
+            }
+            MapRepresentation::Map => unreachable!("maps are handled above"),
+        }
+        Ok(())
+    }
+
+    /// Returns the Rust type of a field.
+    fn field_type(&self, field: &StructField) -> Result<String, SchemaError> {
+        if field.optional && field.nullable {
+            return Err(unsupported("fields that are optional and nullable"));
+        }
+        let rust = self.rust_type(&field.value_type)?;
+        Ok(if field.optional || field.nullable {
+            format!("Option<{}>", rust)
+        } else {
+            rust
+        })
+    }
+
+    fn structure(
+        &self,
+        out: &mut String,
+        name: &str,
+        structure: &StructType,
+    ) -> Result<(), SchemaError> {
+        let fields = &structure.fields;
+        match &structure.representation {
+            StructRepresentation::Map { renames } => {
+                self.derive(
+                    out,
+                    &["Clone", "Debug", "PartialEq", "Serialize", "Deserialize"],
+                );
+                out.push_str("#[serde(deny_unknown_fields)]\n");
+                out.push_str(&format!("pub struct {} {{\n", name));
+                let mut fields: Vec<_> = fields
+                    .iter()
+                    .map(|field| (map_key(field, renames), field))
+                    .collect();
+                fields.sort_by(|(a, _), (b, _)| canonical_order(a, b));
+                for (key, field) in fields {
+                    let ident = field_ident(&field.name);
+                    if ident != key {
+                        out.push_str(&format!("    #[serde(rename = {:?})]\n", key));
+                    }
+                    if field.optional {
+                        out.push_str(
+                            "    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n",
+                        );
+                    }
+                    out.push_str(&format!(
+                        "    pub {}: {},\n",
+                        ident,
+                        self.field_type(field)?
+                    ));
+                }
+                out.push_str("}\n");
+                return Ok(());
+            }
+            StructRepresentation::ListPairs => {
+                return Err(unsupported("structs with `listpairs` representation"))
+            }
+            _ => {}
+        }
+
+        self.derive(out, &["Clone", "Debug", "PartialEq"]);
+        out.push_str(&format!("pub struct {} {{\n", name));
+        for field in fields {
+            out.push_str(&format!(
+                "    pub {}: {},\n",
+                field_ident(&field.name),
+                self.field_type(field)?
+            ));
+        }
+        out.push_str("}\n");
+        // The struct expression that is built from the `field_<index>` variables.
+        let construct = format!(
+            "{} {{ {} }}",
+            name,
+            fields
+                .iter()
+                .enumerate()
+                .map(|(index, field)| format!("{}: field_{}", field_ident(&field.name), index))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        match &structure.representation {
+            StructRepresentation::Tuple => {
+                let required = fields
+                    .iter()
+                    .rposition(|field| !field.optional)
+                    .map_or(0, |index| index + 1);
+                if fields[..required].iter().any(|field| field.optional) {
+                    return Err(unsupported("optional tuple fields that are not at the end"));
+                }
+                let mutable = if required < fields.len() { "mut " } else { "" };
+                let mut serialize = format!("        let {}len = {};\n", mutable, required);
+                for (index, field) in fields.iter().enumerate().skip(required) {
+                    serialize.push_str(&format!(
+                        "        if self.{}.is_some() {{\n            len = {};\n        }}\n",
+                        field_ident(&field.name),
+                        index + 1
+                    ));
+                }
+                serialize.push_str("        let mut seq = serializer.serialize_seq(Some(len))?;\n");
+                let mut deserialize = String::new();
+                for (index, field) in fields.iter().enumerate() {
+                    let ident = field_ident(&field.name);
+                    if index < required {
+                        serialize.push_str(&format!(
+                            "        seq.serialize_element(&self.{})?;\n",
+                            ident
+                        ));
+                        deserialize.push_str(&format!(
+                            "                let field_{index} = seq\n                    .next_element()?\n                    .ok_or_else(|| ::serde::de::Error::invalid_length({index}, &self))?;\n",
+                            index = index
+                        ));
+                    } else {
+                        serialize.push_str(&format!(
+                            r#"        if len > {index} {{
+            match &self.{ident} {{
+                Some(value) => seq.serialize_element(value)?,
+                None => {{
+                    return Err(::serde::ser::Error::custom(
+                        "only trailing optional fields may be absent",
+                    ))
+                }}
+            }}
+        }}
+"#,
+                            index = index,
+                            ident = ident
+                        ));
+                        deserialize.push_str(&format!(
+                            "                let field_{} = seq.next_element()?;\n",
+                            index
+                        ));
+                    }
+                }
+                out.push_str(&format!(
+                    r#"
+impl ::serde::Serialize for {name} {{
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        use ::serde::ser::SerializeSeq;
+{serialize}        seq.end()
+    }}
+}}
+
+impl<'de> ::serde::Deserialize<'de> for {name} {{
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{
+        struct TupleVisitor;
+
+        impl<'de> ::serde::de::Visitor<'de> for TupleVisitor {{
+            type Value = {name};
+
+            fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {{
+                formatter.write_str("a list of {required} to {len} elements")
+            }}
+
+            fn visit_seq<A: ::serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<{name}, A::Error> {{
+{deserialize}                if seq.next_element::<::serde::de::IgnoredAny>()?.is_some() {{
+                    return Err(::serde::de::Error::invalid_length({extra}, &self));
+                }}
+                Ok({construct})
+            }}
+        }}
+
+        deserializer.deserialize_seq(TupleVisitor)
+    }}
+}}
+"#,
+                    name = name,
+                    serialize = serialize,
+                    deserialize = deserialize,
+                    required = required,
+                    len = fields.len(),
+                    extra = fields.len() + 1,
+                    construct = construct
+                ));
+            }
+            StructRepresentation::StringJoin { join } => {
+                for field in fields {
+                    if field.optional || field.nullable {
+                        return Err(unsupported(
+                            "optional or nullable fields in `stringjoin` structs",
+                        ));
+                    }
+                    self.check_string_representable(&field.value_type)?;
+                }
+                let values: Vec<_> = fields
+                    .iter()
+                    .map(|field| format!("self.{}.to_string()", field_ident(&field.name)))
+                    .collect();
+                let parse: String = (0..fields.len())
+                    .map(|index| {
+                        format!(
+                            "        let field_{index} = parts[{index}].parse().map_err(::serde::de::Error::custom)?;\n",
+                            index = index
+                        )
+                    })
+                    .collect();
+                out.push_str(&format!(
+                    r#"
+impl ::serde::Serialize for {name} {{
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        serializer.serialize_str(&[{values}].join({join:?}))
+    }}
+}}
+
+impl<'de> ::serde::Deserialize<'de> for {name} {{
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{
+        let string = <String as ::serde::Deserialize>::deserialize(deserializer)?;
+        let parts: Vec<&str> = string.split({join:?}).collect();
+        if parts.len() != {len} {{
+            return Err(::serde::de::Error::invalid_length(
+                parts.len(),
+                &"{len} values joined by {join_escaped}",
+            ));
+        }}
+{parse}        Ok({construct})
+    }}
+}}
+"#,
+                    name = name,
+                    values = values.join(", "),
+                    join = join,
+                    join_escaped = join.escape_default(),
+                    len = fields.len(),
+                    parse = parse,
+                    construct = construct
+                ));
+            }
+            StructRepresentation::StringPairs {
+                inner_delim,
+                entry_delim,
+            } => {
+                let mut serialize = String::new();
+                let mut matches = String::new();
+                let mut construct_fields = Vec::new();
+                for (index, field) in fields.iter().enumerate() {
+                    if field.nullable {
+                        return Err(unsupported("nullable fields in `stringpairs` structs"));
+                    }
+                    self.check_string_representable(&field.value_type)?;
+                    let ident = field_ident(&field.name);
+                    if field.optional {
+                        serialize.push_str(&format!(
+                            "        if let Some(value) = &self.{ident} {{\n            entries.push(format!(\"{{}}{{}}{{}}\", {key:?}, {inner:?}, value));\n        }}\n",
+                            ident = ident,
+                            key = field.name,
+                            inner = inner_delim
+                        ));
+                        construct_fields.push(format!("{}: field_{}", ident, index));
+                    } else {
+                        serialize.push_str(&format!(
+                            "        entries.push(format!(\"{{}}{{}}{{}}\", {key:?}, {inner:?}, self.{ident}));\n",
+                            ident = ident,
+                            key = field.name,
+                            inner = inner_delim
+                        ));
+                        construct_fields.push(format!(
+                            "{}: field_{}.ok_or_else(|| ::serde::de::Error::missing_field({:?}))?",
+                            ident, index, field.name
+                        ));
+                    }
+                    matches.push_str(&format!(
+                        "                    {:?} => field_{} = Some(value.parse().map_err(::serde::de::Error::custom)?),\n",
+                        field.name,
+                        index
+                    ));
+                }
+                let declarations: String = (0..fields.len())
+                    .map(|index| format!("        let mut field_{} = None;\n", index))
+                    .collect();
+                let names: Vec<_> = fields
+                    .iter()
+                    .map(|field| format!("{:?}", field.name))
+                    .collect();
+                out.push_str(&format!(
+                    r#"
+impl ::serde::Serialize for {name} {{
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        let mut entries: Vec<String> = Vec::new();
+{serialize}        serializer.serialize_str(&entries.join({entry:?}))
+    }}
+}}
+
+impl<'de> ::serde::Deserialize<'de> for {name} {{
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{
+        let string = <String as ::serde::Deserialize>::deserialize(deserializer)?;
+{declarations}        if !string.is_empty() {{
+            for entry in string.split({entry:?}) {{
+                let (key, value) = entry.split_once({inner:?}).ok_or_else(|| {{
+                    ::serde::de::Error::custom(format!("entry `{{}}` has no delimiter", entry))
+                }})?;
+                match key {{
+{matches}                    _ => return Err(::serde::de::Error::unknown_field(key, &[{names}])),
+                }}
+            }}
+        }}
+        Ok({name} {{ {construct} }})
+    }}
+}}
+"#,
+                    name = name,
+                    serialize = serialize,
+                    entry = entry_delim,
+                    inner = inner_delim,
+                    declarations = declarations,
+                    matches = matches,
+                    names = names.join(", "),
+                    construct = construct_fields.join(", ")
+                ));
+            }
+            StructRepresentation::Map { .. } | StructRepresentation::ListPairs => {
+                unreachable!("handled above")
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the name of the variant for a union member.
+    fn variant_name(&self, member: &TypeTerm) -> String {
+        match member {
+            TypeTerm::Named(name) => name.clone(),
+            TypeTerm::Inline(ty) => match ty.as_ref() {
+                Type::Link(LinkType {
+                    expected_type: Some(name),
+                }) => format!("{}Link", name),
+                Type::Link(_) => "Link".to_string(),
+                Type::List(_) => "List".to_string(),
+                _ => "Map".to_string(),
+            },
+        }
+    }
+
+    /// Writes the enum of a union, with a newtype variant per member.
+    fn union_enum(
+        &self,
+        out: &mut String,
+        name: &str,
+        members: &[(Option<&str>, &TypeTerm)],
+    ) -> Result<(), SchemaError> {
+        out.push_str(&format!("pub enum {} {{\n", name));
+        for (rename, member) in members {
+            if let Some(rename) = rename {
+                out.push_str(&format!("    #[serde(rename = {:?})]\n", rename));
+            }
+            out.push_str(&format!(
+                "    {}({}),\n",
+                self.variant_name(member),
+                self.rust_type(member)?
+            ));
+        }
+        out.push_str("}\n");
+        Ok(())
+    }
+
+    /// Checks that the members of a union that is buffered during deserialization don't contain
+    /// links.
+    fn check_buffered_members<'t, I>(&self, members: I) -> Result<(), SchemaError>
+    where
+        I: IntoIterator<Item = &'t TypeTerm>,
+    {
+        for member in members {
+            if self.contains_link(member, &mut HashSet::new())? {
+                return Err(unsupported(
+                    "links within the members of envelope and inline unions",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn union(
+        &self,
+        out: &mut String,
+        name: &str,
+        union: &UnionRepresentation,
+    ) -> Result<(), SchemaError> {
+        match union {
+            UnionRepresentation::Keyed(members) => {
+                self.derive(
+                    out,
+                    &["Clone", "Debug", "PartialEq", "Serialize", "Deserialize"],
+                );
+                let members: Vec<_> = members
+                    .iter()
+                    .map(|(key, member)| (Some(key.as_str()), member))
+                    .collect();
+                self.union_enum(out, name, &members)?;
+            }
+            UnionRepresentation::Kinded(members) => self.kinded(out, name, members)?,
+            UnionRepresentation::Envelope {
+                discriminant_key,
+                content_key,
+                discriminants,
+            } => {
+                self.check_buffered_members(discriminants.iter().map(|(_, member)| member))?;
+                self.derive(out, &["Clone", "Debug", "PartialEq", "Deserialize"]);
+                out.push_str(&format!(
+                    "#[serde(tag = {:?}, content = {:?})]\n",
+                    discriminant_key, content_key
+                ));
+                let members: Vec<_> = discriminants
+                    .iter()
+                    .map(|(key, member)| (Some(key.as_str()), member))
+                    .collect();
+                self.union_enum(out, name, &members)?;
+                let mut arms = String::new();
+                for (discriminant, member) in discriminants {
+                    let mut entries = vec![
+                        (discriminant_key.as_str(), format!("{:?}", discriminant)),
+                        (content_key.as_str(), "value".to_string()),
+                    ];
+                    entries.sort_by(|(a, _), (b, _)| canonical_order(a, b));
+                    arms.push_str(&format!(
+                        "            {}::{}(value) => {{\n",
+                        name,
+                        self.variant_name(member)
+                    ));
+                    for (key, value) in entries {
+                        arms.push_str(&format!(
+                            "                map.serialize_entry({:?}, {})?;\n",
+                            key, value
+                        ));
+                    }
+                    arms.push_str("            }\n");
+                }
+                out.push_str(&format!(
+                    r#"
+impl ::serde::Serialize for {name} {{
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        use ::serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(2))?;
+        match self {{
+{arms}        }}
+        map.end()
+    }}
+}}
+"#,
+                    name = name,
+                    arms = arms
+                ));
+            }
+            UnionRepresentation::Inline {
+                discriminant_key,
+                discriminants,
+            } => {
+                let terms: Vec<_> = discriminants
+                    .iter()
+                    .map(|(_, member)| named(member))
+                    .collect();
+                self.check_buffered_members(&terms)?;
+                self.derive(out, &["Clone", "Debug", "PartialEq", "Deserialize"]);
+                out.push_str(&format!("#[serde(tag = {:?})]\n", discriminant_key));
+                let members: Vec<_> = discriminants
+                    .iter()
+                    .zip(&terms)
+                    .map(|((key, _), member)| (Some(key.as_str()), member))
+                    .collect();
+                self.union_enum(out, name, &members)?;
+                let mut arms = String::new();
+                for ((discriminant, member), term) in discriminants.iter().zip(&terms) {
+                    let (fields, renames) = match self.resolve(term)? {
+                        Type::Struct(StructType {
+                            fields,
+                            representation: StructRepresentation::Map { renames },
+                        }) => (fields, renames),
+                        _ => unreachable!("members of inline unions are checked to be structs"),
+                    };
+                    let required = fields.iter().filter(|field| !field.optional).count();
+                    let mutable = if required < fields.len() { "mut " } else { "" };
+                    arms.push_str(&format!(
+                        "            {}::{}(value) => {{\n                let {}len = {};\n",
+                        name,
+                        member,
+                        mutable,
+                        required + 1
+                    ));
+                    for field in fields.iter().filter(|field| field.optional) {
+                        arms.push_str(&format!(
+                            "                if value.{}.is_some() {{\n                    len += 1;\n                }}\n",
+                            field_ident(&field.name)
+                        ));
+                    }
+                    arms.push_str(
+                        "                let mut map = serializer.serialize_map(Some(len))?;\n",
+                    );
+                    let mut entries: Vec<_> = fields
+                        .iter()
+                        .map(|field| (map_key(field, &renames), Some(field)))
+                        .collect();
+                    entries.push((discriminant_key.clone(), None));
+                    entries.sort_by(|(a, _), (b, _)| canonical_order(a, b));
+                    for (key, field) in entries {
+                        let entry = match field {
+                            None => format!(
+                                "                map.serialize_entry({:?}, {:?})?;\n",
+                                key, discriminant
+                            ),
+                            Some(field) if field.optional => format!(
+                                "                if let Some(field) = &value.{} {{\n                    map.serialize_entry({:?}, field)?;\n                }}\n",
+                                field_ident(&field.name),
+                                key
+                            ),
+                            Some(field) => format!(
+                                "                map.serialize_entry({:?}, &value.{})?;\n",
+                                key,
+                                field_ident(&field.name)
+                            ),
+                        };
+                        arms.push_str(&entry);
+                    }
+                    arms.push_str("                map.end()\n            }\n");
+                }
+                out.push_str(&format!(
+                    r#"
+impl ::serde::Serialize for {name} {{
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        use ::serde::ser::SerializeMap;
+        match self {{
+{arms}        }}
+    }}
+}}
+"#,
+                    name = name,
+                    arms = arms
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn kinded(
+        &self,
+        out: &mut String,
+        name: &str,
+        members: &[(Kind, TypeTerm)],
+    ) -> Result<(), SchemaError> {
+        self.derive(out, &["Clone", "Debug", "PartialEq"]);
+        let terms: Vec<_> = members.iter().map(|(_, member)| (None, member)).collect();
+        self.union_enum(out, name, &terms)?;
+
+        let mut arms = String::new();
+        let mut visits = String::new();
+        let mut kinds = Vec::new();
+        for (kind, member) in members {
+            let variant = self.variant_name(member);
+            arms.push_str(&format!(
+                "            {}::{}(value) => ::serde::Serialize::serialize(value, serializer),\n",
+                name, variant
+            ));
+            // The visited value is turned into a deserializer for the member type.
+            let scalar = |method: &str, ty: &str, deserializer: &str| {
+                format!(
+                    r#"
+            fn {method}<E: ::serde::de::Error>(self, value: {ty}) -> Result<{name}, E> {{
+                ::serde::Deserialize::deserialize({deserializer}).map({name}::{variant})
+            }}
+"#,
+                    method = method,
+                    ty = ty,
+                    name = name,
+                    variant = variant,
+                    deserializer = deserializer,
+                )
+            };
+            let into = "::serde::de::IntoDeserializer::into_deserializer(value)";
+            let visit = match kind {
+                Kind::Bool => scalar("visit_bool", "bool", into),
+                Kind::Integer => {
+                    scalar("visit_i64", "i64", into) + &scalar("visit_u64", "u64", into)
+                }
+                Kind::Float => scalar("visit_f64", "f64", into),
+                Kind::String => scalar("visit_str", "&str", into),
+                Kind::Bytes => scalar(
+                    "visit_bytes",
+                    "&[u8]",
+                    "::serde::de::value::BytesDeserializer::new(value)",
+                ),
+                Kind::List => format!(
+                    r#"
+            fn visit_seq<A: ::serde::de::SeqAccess<'de>>(self, seq: A) -> Result<{name}, A::Error> {{
+                let deserializer = ::serde::de::value::SeqAccessDeserializer::new(seq);
+                ::serde::Deserialize::deserialize(deserializer).map({name}::{variant})
+            }}
+"#,
+                    name = name,
+                    variant = variant
+                ),
+                Kind::Map => format!(
+                    r#"
+            fn visit_map<A: ::serde::de::MapAccess<'de>>(self, map: A) -> Result<{name}, A::Error> {{
+                let deserializer = ::serde::de::value::MapAccessDeserializer::new(map);
+                ::serde::Deserialize::deserialize(deserializer).map({name}::{variant})
+            }}
+"#,
+                    name = name,
+                    variant = variant
+                ),
+                Kind::Link => format!(
+                    r#"
+            fn visit_newtype_struct<D: ::serde::Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<{name}, D::Error> {{
+                ::serde::Deserialize::deserialize(deserializer).map({name}::{variant})
+            }}
+"#,
+                    name = name,
+                    variant = variant
+                ),
+                Kind::Null => return Err(unsupported("kinded unions with a null member")),
+            };
+            visits.push_str(&visit);
+            kinds.push(super::kind_name(*kind));
+        }
+        out.push_str(&format!(
+            r#"
+impl ::serde::Serialize for {name} {{
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        match self {{
+{arms}        }}
+    }}
+}}
+
+impl<'de> ::serde::Deserialize<'de> for {name} {{
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{
+        struct KindVisitor;
+
+        impl<'de> ::serde::de::Visitor<'de> for KindVisitor {{
+            type Value = {name};
+
+            fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {{
+                formatter.write_str("a value of kind {kinds}")
+            }}
+{visits}        }}
+
+        deserializer.deserialize_any(KindVisitor)
+    }}
+}}
+"#,
+            name = name,
+            arms = arms,
+            kinds = kinds.join(", "),
+            visits = visits
+        ));
+        Ok(())
+    }
+
+    fn enumeration(
+        &self,
+        out: &mut String,
+        name: &str,
+        enumeration: &EnumType,
+    ) -> Result<(), SchemaError> {
+        let traits = [
+            "Clone",
+            "Copy",
+            "Debug",
+            "PartialEq",
+            "Eq",
+            "PartialOrd",
+            "Ord",
+            "Hash",
+        ];
+        match &enumeration.representation {
+            EnumRepresentation::String(renames) => {
+                let mut traits = traits.to_vec();
+                traits.extend(["Serialize", "Deserialize"]);
+                self.derive(out, &traits);
+                out.push_str(&format!("pub enum {} {{\n", name));
+                let mut display = String::new();
+                let mut from_str = String::new();
+                for member in &enumeration.members {
+                    let value = renames.get(member).unwrap_or(member);
+                    if value != member {
+                        out.push_str(&format!("    #[serde(rename = {:?})]\n", value));
+                    }
+                    out.push_str(&format!("    {},\n", member));
+                    display.push_str(&format!(
+                        "            {}::{} => {:?},\n",
+                        name, member, value
+                    ));
+                    from_str.push_str(&format!(
+                        "            {:?} => Ok({}::{}),\n",
+                        value, name, member
+                    ));
+                }
+                out.push_str("}\n");
+                out.push_str(&format!(
+                    r#"
+impl ::std::fmt::Display for {name} {{
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {{
+        formatter.write_str(match self {{
+{display}        }})
+    }}
+}}
+
+impl ::std::str::FromStr for {name} {{
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {{
+        match value {{
+{from_str}            _ => Err(format!("unknown member `{{}}`", value)),
+        }}
+    }}
+}}
+"#,
+                    name = name,
+                    display = display,
+                    from_str = from_str
+                ));
+            }
+            EnumRepresentation::Int(values) => {
+                self.derive(out, &traits);
+                out.push_str(&format!("pub enum {} {{\n", name));
+                let mut serialize = String::new();
+                let mut deserialize = String::new();
+                for member in &enumeration.members {
+                    let value = values[member];
+                    out.push_str(&format!("    {},\n", member));
+                    serialize
+                        .push_str(&format!("            {}::{} => {},\n", name, member, value));
+                    deserialize.push_str(&format!(
+                        "            {} => Ok({}::{}),\n",
+                        value, name, member
+                    ));
+                }
+                out.push_str("}\n");
+                out.push_str(&format!(
+                    r#"
+impl ::serde::Serialize for {name} {{
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        serializer.serialize_i64(match self {{
+{serialize}        }})
+    }}
+}}
+
+impl<'de> ::serde::Deserialize<'de> for {name} {{
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{
+        match <i64 as ::serde::Deserialize>::deserialize(deserializer)? {{
+{deserialize}            other => Err(::serde::de::Error::invalid_value(
+                ::serde::de::Unexpected::Signed(other),
+                &"a member of {name}",
+            )),
+        }}
+    }}
+}}
+"#,
+                    name = name,
+                    serialize = serialize,
+                    deserialize = deserialize
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn unsupported(what: &str) -> SchemaError {
+    SchemaError::Unsupported(what.to_string())
+}
+
+fn named(name: &str) -> TypeTerm {
+    TypeTerm::Named(name.to_string())
+}
+
+/// Returns the key of a field in the map representation of a struct.
+fn map_key(field: &StructField, renames: &std::collections::BTreeMap<String, String>) -> String {
+    renames.get(&field.name).unwrap_or(&field.name).to_string()
+}
+
+/// Orders map keys like DAG-CBOR, shorter keys first, then bytewise.
+fn canonical_order(a: &str, b: &str) -> std::cmp::Ordering {
+    (a.len(), a).cmp(&(b.len(), b))
+}
+
+/// Converts a field name into a snake case Rust identifier.
+fn field_ident(name: &str) -> String {
+    let mut ident = String::new();
+    for (index, char) in name.chars().enumerate() {
+        if char.is_ascii_uppercase() {
+            if index > 0 && !ident.ends_with('_') {
+                ident.push('_');
+            }
+            ident.push(char.to_ascii_lowercase());
+        } else if char.is_ascii_alphanumeric() {
+            ident.push(char);
+        } else {
+            ident.push('_');
+        }
+    }
+    if ident.starts_with(|char: char| char.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    match ident.as_str() {
+        "self" | "super" | "crate" | "Self" => ident + "_",
+        _ if KEYWORDS.contains(&ident.as_str()) => format!("r#{}", ident),
+        _ => ident,
+    }
+}
@@ -0,0 +1,379 @@
+//! Parser of the DMT (the schema-schema) form of IPLD Schemas.
+//!
+//! The DMT is deserialized into a tree that keeps maps in the order they are encoded, so that the
+//! order of struct fields is preserved.
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+use serde::de::{self, Deserialize, MapAccess, SeqAccess, Visitor};
+
+use super::{
+    kind_from_name, EnumRepresentation, EnumType, LinkType, ListType, MapRepresentation, MapType,
+    StructField, StructRepresentation, StructType, Type, TypeTerm, UnionRepresentation,
+};
+use crate::error::SchemaError;
+
+/// Parses the type definitions from a DAG-CBOR encoded DMT.
+pub(super) fn from_slice(data: &[u8]) -> Result<BTreeMap<String, Type>, SchemaError> {
+    types(&crate::from_slice(data)?)
+}
+
+/// Parses the type definitions from a DAG-JSON encoded DMT.
+pub(super) fn from_json_slice(data: &[u8]) -> Result<BTreeMap<String, Type>, SchemaError> {
+    types(&crate::json::from_slice(data)?)
+}
+
+/// The subset of the Data Model the DMT consists of.
+enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    String(String),
+    List(Vec<Value>),
+    Map(Vec<(String, Value)>),
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ValueVisitor;
+
+        impl<'de> Visitor<'de> for ValueVisitor {
+            type Value = Value;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a schema DMT value")
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+                Ok(Value::Null)
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+                Ok(Value::Null)
+            }
+
+            fn visit_bool<E: de::Error>(self, value: bool) -> Result<Value, E> {
+                Ok(Value::Bool(value))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Value, E> {
+                Ok(Value::Int(value))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Value, E> {
+                i64::try_from(value)
+                    .map(Value::Int)
+                    .map_err(|_| E::custom("integer out of range"))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Value, E> {
+                Ok(Value::String(value.to_string()))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+                let mut values = Vec::new();
+                while let Some(value) = seq.next_element()? {
+                    values.push(value);
+                }
+                Ok(Value::List(values))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Value::Map(entries))
+            }
+        }
+
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+fn invalid(reason: String) -> SchemaError {
+    SchemaError::Invalid(reason)
+}
+
+impl Value {
+    fn entries(&self, name: &str) -> Result<&[(String, Value)], SchemaError> {
+        match self {
+            Value::Map(entries) => Ok(entries),
+            _ => Err(invalid(format!("`{}` must be a map", name))),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries
+                .iter()
+                .find(|(entry_key, _)| entry_key == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn field(&self, key: &str) -> Result<&Value, SchemaError> {
+        self.get(key)
+            .ok_or_else(|| invalid(format!("missing field `{}`", key)))
+    }
+
+    fn as_str(&self, name: &str) -> Result<&str, SchemaError> {
+        match self {
+            Value::String(string) => Ok(string),
+            _ => Err(invalid(format!("`{}` must be a string", name))),
+        }
+    }
+
+    fn string(&self, key: &str) -> Result<String, SchemaError> {
+        self.field(key)?.as_str(key).map(str::to_string)
+    }
+
+    /// Returns a boolean that defaults to false if it's absent.
+    fn flag(&self, key: &str) -> Result<bool, SchemaError> {
+        match self.get(key) {
+            None => Ok(false),
+            Some(Value::Bool(flag)) => Ok(*flag),
+            Some(_) => Err(invalid(format!("`{}` must be a boolean", key))),
+        }
+    }
+
+    /// Returns the single entry of a map that represents a union, like `{"map": {...}}`.
+    fn keyed(&self, name: &str) -> Result<(&str, &Value), SchemaError> {
+        match self.entries(name)? {
+            [(key, value)] => Ok((key, value)),
+            _ => Err(invalid(format!("`{}` must have a single entry", name))),
+        }
+    }
+}
+
+fn types(dmt: &Value) -> Result<BTreeMap<String, Type>, SchemaError> {
+    dmt.field("types")?
+        .entries("types")?
+        .iter()
+        .map(|(name, ty)| Ok((name.clone(), type_definition(ty)?)))
+        .collect()
+}
+
+fn type_definition(value: &Value) -> Result<Type, SchemaError> {
+    let (kind, body) = value.keyed("type definition")?;
+    match kind {
+        "bool" => Ok(Type::Bool),
+        "string" => Ok(Type::String),
+        "bytes" => Ok(Type::Bytes),
+        "int" => Ok(Type::Int),
+        "float" => Ok(Type::Float),
+        "any" => Ok(Type::Any),
+        "map" => map(body),
+        "list" => Ok(Type::List(ListType {
+            value_type: type_term(body.field("valueType")?)?,
+            value_nullable: body.flag("valueNullable")?,
+        })),
+        "link" => Ok(Type::Link(LinkType {
+            expected_type: match body.get("expectedType") {
+                None => None,
+                Some(name) => match name.as_str("expectedType")? {
+                    "Any" => None,
+                    name => Some(name.to_string()),
+                },
+            },
+        })),
+        "struct" => structure(body),
+        "union" => union(body),
+        "enum" => enumeration(body),
+        _ => Err(invalid(format!("unsupported type kind `{}`", kind))),
+    }
+}
+
+fn type_term(value: &Value) -> Result<TypeTerm, SchemaError> {
+    match value {
+        Value::String(name) => Ok(TypeTerm::Named(name.clone())),
+        _ => Ok(TypeTerm::Inline(Box::new(type_definition(value)?))),
+    }
+}
+
+/// Returns the representation strategy and its parameters, `None` if there is none.
+fn representation(body: &Value) -> Result<Option<(&str, &Value)>, SchemaError> {
+    body.get("representation")
+        .map(|representation| representation.keyed("representation"))
+        .transpose()
+}
+
+fn map(body: &Value) -> Result<Type, SchemaError> {
+    let representation = match representation(body)? {
+        None | Some(("map", _)) => MapRepresentation::Map,
+        Some(("stringpairs", options)) => MapRepresentation::StringPairs {
+            inner_delim: options.string("innerDelim")?,
+            entry_delim: options.string("entryDelim")?,
+        },
+        Some(("listpairs", _)) => MapRepresentation::ListPairs,
+        Some((name, _)) => {
+            return Err(invalid(format!(
+                "unsupported map representation `{}`",
+                name
+            )))
+        }
+    };
+    Ok(Type::Map(MapType {
+        key_type: body.string("keyType")?,
+        value_type: type_term(body.field("valueType")?)?,
+        value_nullable: body.flag("valueNullable")?,
+        representation,
+    }))
+}
+
+fn structure(body: &Value) -> Result<Type, SchemaError> {
+    let mut fields = body
+        .field("fields")?
+        .entries("fields")?
+        .iter()
+        .map(|(name, field)| {
+            Ok(StructField {
+                name: name.clone(),
+                value_type: type_term(field.field("type")?)?,
+                optional: field.flag("optional")?,
+                nullable: field.flag("nullable")?,
+            })
+        })
+        .collect::<Result<Vec<_>, SchemaError>>()?;
+    let representation = match representation(body)? {
+        None => StructRepresentation::Map {
+            renames: BTreeMap::new(),
+        },
+        Some(("map", options)) => {
+            let mut renames = BTreeMap::new();
+            if let Some(representation_fields) = options.get("fields") {
+                for (name, details) in representation_fields.entries("fields")? {
+                    if let Some(rename) = details.get("rename") {
+                        renames.insert(name.clone(), rename.as_str("rename")?.to_string());
+                    }
+                }
+            }
+            StructRepresentation::Map { renames }
+        }
+        Some(("tuple", options)) => {
+            // The field order may be given explicitly, as map keys might have been reordered.
+            if let Some(Value::List(order)) = options.get("fieldOrder") {
+                let mut ordered = Vec::new();
+                for name in order {
+                    let name = name.as_str("fieldOrder")?;
+                    let index = fields
+                        .iter()
+                        .position(|field| field.name == name)
+                        .ok_or_else(|| invalid(format!("unknown field `{}`", name)))?;
+                    ordered.push(fields.remove(index));
+                }
+                ordered.append(&mut fields);
+                fields = ordered;
+            }
+            StructRepresentation::Tuple
+        }
+        Some(("stringpairs", options)) => StructRepresentation::StringPairs {
+            inner_delim: options.string("innerDelim")?,
+            entry_delim: options.string("entryDelim")?,
+        },
+        Some(("stringjoin", options)) => StructRepresentation::StringJoin {
+            join: options.string("join")?,
+        },
+        Some(("listpairs", _)) => StructRepresentation::ListPairs,
+        Some((name, _)) => {
+            return Err(invalid(format!(
+                "unsupported struct representation `{}`",
+                name
+            )))
+        }
+    };
+    Ok(Type::Struct(StructType {
+        fields,
+        representation,
+    }))
+}
+
+/// Returns the entries of a map from discriminants to members.
+fn members(value: &Value, name: &str) -> Result<Vec<(String, TypeTerm)>, SchemaError> {
+    value
+        .entries(name)?
+        .iter()
+        .map(|(discriminant, member)| Ok((discriminant.clone(), type_term(member)?)))
+        .collect()
+}
+
+fn union(body: &Value) -> Result<Type, SchemaError> {
+    let representation = match representation(body)? {
+        Some(("keyed", options)) => UnionRepresentation::Keyed(members(options, "keyed")?),
+        Some(("kinded", options)) => UnionRepresentation::Kinded(
+            members(options, "kinded")?
+                .into_iter()
+                .map(|(kind, member)| match kind_from_name(&kind) {
+                    Some(kind) => Ok((kind, member)),
+                    None => Err(invalid(format!("unknown kind `{}`", kind))),
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        Some(("envelope", options)) => UnionRepresentation::Envelope {
+            discriminant_key: options.string("discriminantKey")?,
+            content_key: options.string("contentKey")?,
+            discriminants: members(options.field("discriminantTable")?, "discriminantTable")?,
+        },
+        Some(("inline", options)) => UnionRepresentation::Inline {
+            discriminant_key: options.string("discriminantKey")?,
+            discriminants: options
+                .field("discriminantTable")?
+                .entries("discriminantTable")?
+                .iter()
+                .map(|(discriminant, member)| {
+                    Ok((discriminant.clone(), member.as_str("member")?.to_string()))
+                })
+                .collect::<Result<_, SchemaError>>()?,
+        },
+        Some((name, _)) => {
+            return Err(invalid(format!(
+                "unsupported union representation `{}`",
+                name
+            )))
+        }
+        None => return Err(invalid("unions need a representation".to_string())),
+    };
+    Ok(Type::Union(representation))
+}
+
+fn enumeration(body: &Value) -> Result<Type, SchemaError> {
+    let members = match body.field("members")? {
+        Value::List(members) => members
+            .iter()
+            .map(|member| member.as_str("members").map(str::to_string))
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => return Err(invalid("`members` must be a list".to_string())),
+    };
+    let representation = match representation(body)? {
+        None => EnumRepresentation::String(BTreeMap::new()),
+        Some(("string", renames)) => EnumRepresentation::String(
+            renames
+                .entries("string")?
+                .iter()
+                .map(|(member, value)| Ok((member.clone(), value.as_str("string")?.to_string())))
+                .collect::<Result<_, SchemaError>>()?,
+        ),
+        Some(("int", values)) => EnumRepresentation::Int(
+            values
+                .entries("int")?
+                .iter()
+                .map(|(member, value)| match value {
+                    Value::Int(value) => Ok((member.clone(), *value)),
+                    _ => Err(invalid("`int` values must be integers".to_string())),
+                })
+                .collect::<Result<_, SchemaError>>()?,
+        ),
+        Some((name, _)) => {
+            return Err(invalid(format!(
+                "unsupported enum representation `{}`",
+                name
+            )))
+        }
+    };
+    Ok(Type::Enum(EnumType {
+        members,
+        representation,
+    }))
+}
@@ -0,0 +1,416 @@
+//! Parser of the IPLD Schema DSL.
+use std::collections::BTreeMap;
+
+use super::{
+    kind_from_name, EnumRepresentation, EnumType, LinkType, ListType, MapRepresentation, MapType,
+    StructField, StructRepresentation, StructType, Type, TypeTerm, UnionRepresentation,
+};
+use crate::error::SchemaError;
+
+/// Parses the type definitions of a schema.
+pub(super) fn parse(dsl: &str) -> Result<BTreeMap<String, Type>, SchemaError> {
+    let mut parser = Parser {
+        input: dsl.as_bytes(),
+        pos: 0,
+    };
+    let mut types = BTreeMap::new();
+    parser.skip_whitespace();
+    while parser.peek().is_some() {
+        parser.keyword("type")?;
+        let name_pos = parser.pos;
+        let name = parser.name()?;
+        let ty = parser.type_definition()?;
+        if types.insert(name.clone(), ty).is_some() {
+            return Err(SchemaError::Invalid(format!(
+                "type `{}` defined twice at position {}",
+                name, name_pos
+            )));
+        }
+    }
+    Ok(types)
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn syntax_error(&self, expected: &'static str) -> SchemaError {
+        SchemaError::Syntax {
+            position: self.pos,
+            expected,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    /// Skips whitespace and comments, which start with `#` and end at the end of the line.
+    fn skip_whitespace(&mut self) {
+        while let Some(byte) = self.peek() {
+            match byte {
+                b' ' | b'\n' | b'\r' | b'\t' => self.pos += 1,
+                b'#' => {
+                    while self.peek().is_some_and(|byte| byte != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Consumes the byte if it's next, skipping whitespace after it.
+    fn eat(&mut self, expected: u8) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            self.skip_whitespace();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: u8, description: &'static str) -> Result<(), SchemaError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.syntax_error(description))
+        }
+    }
+
+    /// Returns the next identifier without consuming it, it's empty if there is none.
+    fn peek_identifier(&self) -> &str {
+        let len = self.input[self.pos..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_alphanumeric() || **byte == b'_')
+            .count();
+        std::str::from_utf8(&self.input[self.pos..self.pos + len]).expect("identifiers are ASCII")
+    }
+
+    /// Consumes the identifier if it's next.
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek_identifier() == keyword {
+            self.pos += keyword.len();
+            self.skip_whitespace();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn keyword(&mut self, keyword: &'static str) -> Result<(), SchemaError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.syntax_error(keyword))
+        }
+    }
+
+    fn identifier(&mut self, expected: &'static str) -> Result<String, SchemaError> {
+        let identifier = self.peek_identifier().to_string();
+        if identifier.is_empty() {
+            return Err(self.syntax_error(expected));
+        }
+        self.pos += identifier.len();
+        self.skip_whitespace();
+        Ok(identifier)
+    }
+
+    /// Parses a type name, which starts with a letter.
+    fn name(&mut self) -> Result<String, SchemaError> {
+        if !self.peek().is_some_and(|byte| byte.is_ascii_alphabetic()) {
+            return Err(self.syntax_error("type name"));
+        }
+        self.identifier("type name")
+    }
+
+    fn string(&mut self) -> Result<String, SchemaError> {
+        if self.peek() != Some(b'"') {
+            return Err(self.syntax_error("string"));
+        }
+        let start = self.pos + 1;
+        let len = self.input[start..]
+            .iter()
+            .position(|byte| *byte == b'"')
+            .ok_or_else(|| self.syntax_error("end of string"))?;
+        let string = std::str::from_utf8(&self.input[start..start + len])
+            .expect("the input is a string")
+            .to_string();
+        self.pos = start + len + 1;
+        self.skip_whitespace();
+        Ok(string)
+    }
+
+    /// Parses the string value of an option like `join ":"`.
+    fn option(&mut self, name: &'static str) -> Result<String, SchemaError> {
+        self.keyword(name)?;
+        self.string()
+    }
+
+    fn type_definition(&mut self) -> Result<Type, SchemaError> {
+        match self.peek() {
+            Some(b'{') => self.map(),
+            Some(b'[') => self.list(),
+            Some(b'&') => self.link(),
+            _ => match self.identifier("type kind")?.as_str() {
+                "bool" => Ok(Type::Bool),
+                "string" => Ok(Type::String),
+                "bytes" => Ok(Type::Bytes),
+                "int" => Ok(Type::Int),
+                "float" => Ok(Type::Float),
+                "any" => Ok(Type::Any),
+                "struct" => self.structure(),
+                "union" => self.union(),
+                "enum" => self.enumeration(),
+                _ => Err(SchemaError::Syntax {
+                    position: self.pos,
+                    expected: "supported type kind",
+                }),
+            },
+        }
+    }
+
+    /// Parses a type name or an inline map, list or link type.
+    fn type_term(&mut self) -> Result<TypeTerm, SchemaError> {
+        match self.peek() {
+            Some(b'{') | Some(b'[') | Some(b'&') => {
+                Ok(TypeTerm::Inline(Box::new(self.type_definition()?)))
+            }
+            _ => Ok(TypeTerm::Named(self.name()?)),
+        }
+    }
+
+    fn map(&mut self) -> Result<Type, SchemaError> {
+        self.expect(b'{', "`{`")?;
+        let key_type = self.name()?;
+        self.expect(b':', "`:`")?;
+        let value_nullable = self.eat_keyword("nullable");
+        let value_type = self.type_term()?;
+        self.expect(b'}', "`}`")?;
+        let representation = if self.representation() {
+            match self.identifier("map representation")?.as_str() {
+                "map" => MapRepresentation::Map,
+                "stringpairs" => {
+                    let (inner_delim, entry_delim) = self.pairs_options()?;
+                    MapRepresentation::StringPairs {
+                        inner_delim,
+                        entry_delim,
+                    }
+                }
+                "listpairs" => MapRepresentation::ListPairs,
+                _ => return Err(self.syntax_error("supported map representation")),
+            }
+        } else {
+            MapRepresentation::Map
+        };
+        Ok(Type::Map(MapType {
+            key_type,
+            value_type,
+            value_nullable,
+            representation,
+        }))
+    }
+
+    fn list(&mut self) -> Result<Type, SchemaError> {
+        self.expect(b'[', "`[`")?;
+        let value_nullable = self.eat_keyword("nullable");
+        let value_type = self.type_term()?;
+        self.expect(b']', "`]`")?;
+        Ok(Type::List(ListType {
+            value_type,
+            value_nullable,
+        }))
+    }
+
+    fn link(&mut self) -> Result<Type, SchemaError> {
+        self.expect(b'&', "`&`")?;
+        let expected_type = match self.name()? {
+            name if name == "Any" => None,
+            name => Some(name),
+        };
+        Ok(Type::Link(LinkType { expected_type }))
+    }
+
+    /// Consumes the `representation` keyword if it's next.
+    fn representation(&mut self) -> bool {
+        self.eat_keyword("representation")
+    }
+
+    /// Parses `{ innerDelim "=" entryDelim "," }`.
+    fn pairs_options(&mut self) -> Result<(String, String), SchemaError> {
+        self.expect(b'{', "`{`")?;
+        let inner_delim = self.option("innerDelim")?;
+        let entry_delim = self.option("entryDelim")?;
+        self.expect(b'}', "`}`")?;
+        Ok((inner_delim, entry_delim))
+    }
+
+    fn structure(&mut self) -> Result<Type, SchemaError> {
+        self.expect(b'{', "`{`")?;
+        let mut fields = Vec::new();
+        let mut renames = BTreeMap::new();
+        while !self.eat(b'}') {
+            let name = self.identifier("field name or `}`")?;
+            let optional = self.eat_keyword("optional");
+            let nullable = self.eat_keyword("nullable");
+            let value_type = self.type_term()?;
+            if self.eat(b'(') {
+                renames.insert(name.clone(), self.option("rename")?);
+                self.expect(b')', "`)`")?;
+            }
+            fields.push(StructField {
+                name,
+                value_type,
+                optional,
+                nullable,
+            });
+        }
+        let representation = if self.representation() {
+            match self.identifier("struct representation")?.as_str() {
+                "map" => StructRepresentation::Map { renames },
+                "tuple" => StructRepresentation::Tuple,
+                "stringpairs" => {
+                    let (inner_delim, entry_delim) = self.pairs_options()?;
+                    StructRepresentation::StringPairs {
+                        inner_delim,
+                        entry_delim,
+                    }
+                }
+                "stringjoin" => {
+                    self.expect(b'{', "`{`")?;
+                    let join = self.option("join")?;
+                    self.expect(b'}', "`}`")?;
+                    StructRepresentation::StringJoin { join }
+                }
+                "listpairs" => StructRepresentation::ListPairs,
+                _ => return Err(self.syntax_error("supported struct representation")),
+            }
+        } else {
+            StructRepresentation::Map { renames }
+        };
+        Ok(Type::Struct(StructType {
+            fields,
+            representation,
+        }))
+    }
+
+    fn union(&mut self) -> Result<Type, SchemaError> {
+        self.expect(b'{', "`{`")?;
+        // The discriminators are either strings or kinds, depending on the representation.
+        let mut members = Vec::new();
+        while !self.eat(b'}') {
+            self.expect(b'|', "`|` or `}`")?;
+            let member = self.type_term()?;
+            let discriminator = match self.peek() {
+                Some(b'"') => self.string()?,
+                _ => self.identifier("discriminator")?,
+            };
+            members.push((discriminator, member));
+        }
+        if !self.representation() {
+            return Err(self.syntax_error("representation"));
+        }
+        let representation_pos = self.pos;
+        let representation = match self.identifier("union representation")?.as_str() {
+            "keyed" => UnionRepresentation::Keyed(members),
+            "kinded" => UnionRepresentation::Kinded(
+                members
+                    .into_iter()
+                    .map(|(kind, member)| match kind_from_name(&kind) {
+                        Some(kind) => Ok((kind, member)),
+                        None => Err(SchemaError::Invalid(format!("unknown kind `{}`", kind))),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            "envelope" => {
+                self.expect(b'{', "`{`")?;
+                let discriminant_key = self.option("discriminantKey")?;
+                let content_key = self.option("contentKey")?;
+                self.expect(b'}', "`}`")?;
+                UnionRepresentation::Envelope {
+                    discriminant_key,
+                    content_key,
+                    discriminants: members,
+                }
+            }
+            "inline" => {
+                self.expect(b'{', "`{`")?;
+                let discriminant_key = self.option("discriminantKey")?;
+                self.expect(b'}', "`}`")?;
+                let discriminants = members
+                    .into_iter()
+                    .map(|(discriminant, member)| match member {
+                        TypeTerm::Named(name) => Ok((discriminant, name)),
+                        TypeTerm::Inline(_) => Err(SchemaError::Invalid(
+                            "members of inline unions must be named types".to_string(),
+                        )),
+                    })
+                    .collect::<Result<_, _>>()?;
+                UnionRepresentation::Inline {
+                    discriminant_key,
+                    discriminants,
+                }
+            }
+            _ => {
+                return Err(SchemaError::Syntax {
+                    position: representation_pos,
+                    expected: "supported union representation",
+                })
+            }
+        };
+        Ok(Type::Union(representation))
+    }
+
+    fn enumeration(&mut self) -> Result<Type, SchemaError> {
+        self.expect(b'{', "`{`")?;
+        let mut members = Vec::new();
+        let mut values = BTreeMap::new();
+        while !self.eat(b'}') {
+            self.expect(b'|', "`|` or `}`")?;
+            let member = self.identifier("enum member")?;
+            if self.eat(b'(') {
+                values.insert(member.clone(), (self.pos, self.string()?));
+                self.expect(b')', "`)`")?;
+            }
+            members.push(member);
+        }
+        let representation = if self.representation() {
+            self.identifier("enum representation")?
+        } else {
+            "string".to_string()
+        };
+        let representation = match representation.as_str() {
+            "string" => EnumRepresentation::String(
+                values
+                    .into_iter()
+                    .map(|(member, (_, value))| (member, value))
+                    .collect(),
+            ),
+            "int" => {
+                let mut ints = BTreeMap::new();
+                for member in &members {
+                    let (position, value) = values.get(member).ok_or_else(|| {
+                        SchemaError::Invalid(format!(
+                            "member `{}` of an int enum needs a value",
+                            member
+                        ))
+                    })?;
+                    let value = value.parse().map_err(|_| SchemaError::Syntax {
+                        position: *position,
+                        expected: "integer",
+                    })?;
+                    ints.insert(member.clone(), value);
+                }
+                EnumRepresentation::Int(ints)
+            }
+            _ => return Err(self.syntax_error("supported enum representation")),
+        };
+        Ok(Type::Enum(EnumType {
+            members,
+            representation,
+        }))
+    }
+}
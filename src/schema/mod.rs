@@ -0,0 +1,461 @@
+//! IPLD Schemas.
+//!
+//! An [IPLD Schema] describes the shape of data in terms of named types and how they are
+//! represented in the Data Model. A [`Schema`] is parsed from the schema DSL with
+//! [`Schema::parse`] or from its DMT (the schema-schema) form with [`Schema::from_dmt`] or
+//! [`Schema::from_dmt_json`]. Data is then checked against one of its types with
+//! [`Schema::validate`], errors carry the [`Path`] to the offending node.
+//!
+//! The supported representation strategies are:
+//!
+//!  - structs: `map` (with field renames), `tuple`, `stringpairs`, `stringjoin` and `listpairs`
+//!  - maps: `map`, `stringpairs` and `listpairs`
+//!  - unions: `keyed`, `kinded`, `envelope` and `inline`
+//!  - enums: `string` (with renames) and `int`
+//!
+//! Typed links like `&Foo` are checked to be links. With [`Schema::validate_dag`] the blocks they
+//! point to are loaded and validated as well.
+//!
+//! Rust types for the types of a schema are generated with the [`codegen`] module.
+//!
+//! [IPLD Schema]: https://ipld.io/docs/schemas/
+//!
+//! # Examples
+//!
+//! ```
+//! use serde_ipld_dagcbor::schema::Schema;
+//! use serde_ipld_dagcbor::SchemaError;
+//!
+//! let schema = Schema::parse(
+//!     r#"
+//!     type Point struct {
+//!         x Int
+//!         y Int
+//!         label optional String
+//!     } representation tuple
+//!     "#,
+//! )
+//! .unwrap();
+//!
+//! schema.validate_value("Point", &(1, 2)).unwrap();
+//! schema.validate_value("Point", &(1, 2, "origin")).unwrap();
+//! match schema.validate_value("Point", &(1, "two")) {
+//!     Err(SchemaError::Mismatch { path, reason }) => {
+//!         assert_eq!(path.to_string(), "1");
+//!         assert_eq!(reason, "expected Int, found String");
+//!     }
+//!     other => panic!("unexpected result {:?}", other),
+//! }
+//! ```
+pub mod codegen;
+mod dmt;
+mod dsl;
+mod validate;
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use cid::Cid;
+use serde::Serialize;
+
+use crate::blockstore::{Blockstore, MemoryBlockstore};
+use crate::error::SchemaError;
+use crate::node::{Kind, Node};
+use crate::path::Path;
+
+/// The names of the types that are available without defining them.
+const PRELUDE: [&str; 9] = [
+    "Bool", "String", "Bytes", "Int", "Float", "Map", "List", "Link", "Any",
+];
+
+/// A type definition.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Type {
+    /// A boolean.
+    Bool,
+    /// A UTF-8 string.
+    String,
+    /// A byte string.
+    Bytes,
+    /// An integer.
+    Int,
+    /// A floating point number.
+    Float,
+    /// Any value.
+    Any,
+    /// A map.
+    Map(MapType),
+    /// A list.
+    List(ListType),
+    /// A link, optionally to a value of a certain type.
+    Link(LinkType),
+    /// A struct.
+    Struct(StructType),
+    /// A union of other types.
+    Union(UnionRepresentation),
+    /// An enumeration of names.
+    Enum(EnumType),
+}
+
+/// A reference to a type, either by name or an anonymous map, list or link type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeTerm {
+    /// A named type.
+    Named(String),
+    /// An inline definition of a map, list or link type.
+    Inline(Box<Type>),
+}
+
+/// A map type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MapType {
+    /// The name of the key type, it must be representable as string.
+    pub key_type: String,
+    /// The type of the values.
+    pub value_type: TypeTerm,
+    /// Whether values may be null.
+    pub value_nullable: bool,
+    /// How the map is represented.
+    pub representation: MapRepresentation,
+}
+
+/// The representation strategies of maps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MapRepresentation {
+    /// A map.
+    Map,
+    /// A string of entries separated by `entry_delim`, keys and values are separated by
+    /// `inner_delim`.
+    StringPairs {
+        /// The delimiter between a key and its value.
+        inner_delim: String,
+        /// The delimiter between entries.
+        entry_delim: String,
+    },
+    /// A list of two element lists with the key and the value.
+    ListPairs,
+}
+
+/// A list type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListType {
+    /// The type of the elements.
+    pub value_type: TypeTerm,
+    /// Whether elements may be null.
+    pub value_nullable: bool,
+}
+
+/// A link type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinkType {
+    /// The name of the type the linked block contains, `None` if it may contain anything.
+    pub expected_type: Option<String>,
+}
+
+/// A struct type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructType {
+    /// The fields in the order they are defined.
+    pub fields: Vec<StructField>,
+    /// How the struct is represented.
+    pub representation: StructRepresentation,
+}
+
+/// A field of a struct.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructField {
+    /// The name of the field.
+    pub name: String,
+    /// The type of the field.
+    pub value_type: TypeTerm,
+    /// Whether the field may be absent.
+    pub optional: bool,
+    /// Whether the field may be null.
+    pub nullable: bool,
+}
+
+/// The representation strategies of structs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StructRepresentation {
+    /// A map from field names to values. Fields may be renamed, the keys are the field names
+    /// and the values the names used in the data.
+    Map {
+        /// The renamed fields.
+        renames: BTreeMap<String, String>,
+    },
+    /// A list of the values in field order, optional fields may only be omitted at the end.
+    Tuple,
+    /// A string of fields separated by `entry_delim`, names and values are separated by
+    /// `inner_delim`.
+    StringPairs {
+        /// The delimiter between a field name and its value.
+        inner_delim: String,
+        /// The delimiter between fields.
+        entry_delim: String,
+    },
+    /// A string of the values in field order separated by `join`.
+    StringJoin {
+        /// The delimiter between the values.
+        join: String,
+    },
+    /// A list of two element lists with the field name and the value.
+    ListPairs,
+}
+
+/// The representation strategies of unions. The members are listed in the order they are
+/// defined.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UnionRepresentation {
+    /// A map with a single entry, the key determines the member.
+    Keyed(Vec<(String, TypeTerm)>),
+    /// The kind of the data determines the member.
+    Kinded(Vec<(Kind, TypeTerm)>),
+    /// A map with two entries, the value of the discriminant key determines the member, the
+    /// value of the content key is the content.
+    Envelope {
+        /// The key of the discriminant.
+        discriminant_key: String,
+        /// The key of the content.
+        content_key: String,
+        /// The discriminants and their members.
+        discriminants: Vec<(String, TypeTerm)>,
+    },
+    /// The discriminant is an entry of the map representation of the member, which must be a
+    /// struct with map representation.
+    Inline {
+        /// The key of the discriminant.
+        discriminant_key: String,
+        /// The discriminants and the names of their members.
+        discriminants: Vec<(String, String)>,
+    },
+}
+
+/// An enum type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnumType {
+    /// The names of the members in the order they are defined.
+    pub members: Vec<String>,
+    /// How the enum is represented.
+    pub representation: EnumRepresentation,
+}
+
+/// The representation strategies of enums.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EnumRepresentation {
+    /// A string, members are represented by their name unless they are renamed.
+    String(BTreeMap<String, String>),
+    /// An integer, every member has a value.
+    Int(BTreeMap<String, i64>),
+}
+
+/// A set of named types.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Schema {
+    types: BTreeMap<String, Type>,
+}
+
+impl Schema {
+    /// Creates a schema from type definitions.
+    ///
+    /// It's checked that all referenced types are defined and that the members of inline unions
+    /// are structs with map representation.
+    pub fn new(types: BTreeMap<String, Type>) -> Result<Self, SchemaError> {
+        let schema = Schema { types };
+        schema.check()?;
+        Ok(schema)
+    }
+
+    /// Parses a schema from the schema DSL.
+    pub fn parse(dsl: &str) -> Result<Self, SchemaError> {
+        Schema::new(dsl::parse(dsl)?)
+    }
+
+    /// Parses a schema from its DMT form encoded as DAG-CBOR.
+    pub fn from_dmt(data: &[u8]) -> Result<Self, SchemaError> {
+        Schema::new(dmt::from_slice(data)?)
+    }
+
+    /// Parses a schema from its DMT form encoded as DAG-JSON.
+    pub fn from_dmt_json(data: &[u8]) -> Result<Self, SchemaError> {
+        Schema::new(dmt::from_json_slice(data)?)
+    }
+
+    /// Returns the defined types, without the prelude.
+    pub fn types(&self) -> &BTreeMap<String, Type> {
+        &self.types
+    }
+
+    /// Returns the type with the given name, including the types of the prelude like `Int` or
+    /// `Link`.
+    pub fn get(&self, name: &str) -> Option<Cow<'_, Type>> {
+        if let Some(ty) = self.types.get(name) {
+            return Some(Cow::Borrowed(ty));
+        }
+        let ty = match name {
+            "Bool" => Type::Bool,
+            "String" => Type::String,
+            "Bytes" => Type::Bytes,
+            "Int" => Type::Int,
+            "Float" => Type::Float,
+            "Any" => Type::Any,
+            "Map" => Type::Map(MapType {
+                key_type: "String".to_string(),
+                value_type: TypeTerm::Named("Any".to_string()),
+                value_nullable: true,
+                representation: MapRepresentation::Map,
+            }),
+            "List" => Type::List(ListType {
+                value_type: TypeTerm::Named("Any".to_string()),
+                value_nullable: true,
+            }),
+            "Link" => Type::Link(LinkType {
+                expected_type: None,
+            }),
+            _ => return None,
+        };
+        Some(Cow::Owned(ty))
+    }
+
+    /// Validates DAG-CBOR encoded data against the type with the given name.
+    pub fn validate(&self, type_name: &str, data: &[u8]) -> Result<(), SchemaError> {
+        let node = Node::new(data)?;
+        validate::Validator::<MemoryBlockstore>::new(self, None).validate(
+            node,
+            type_name,
+            &Path::default(),
+        )
+    }
+
+    /// Validates a value against the type with the given name, using its DAG-CBOR encoding.
+    pub fn validate_value<T>(&self, type_name: &str, value: &T) -> Result<(), SchemaError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let data = crate::to_vec(value)?;
+        self.validate(type_name, &data)
+    }
+
+    /// Validates the block with the given CID against the type with the given name.
+    ///
+    /// Typed links are followed, the blocks they point to are validated against the linked type.
+    /// The paths of errors continue across the links.
+    pub fn validate_dag<S: Blockstore>(
+        &self,
+        store: &S,
+        type_name: &str,
+        root: &Cid,
+    ) -> Result<(), SchemaError> {
+        validate::Validator::new(self, Some(store)).validate_block(
+            root,
+            type_name,
+            &Path::default(),
+        )
+    }
+
+    /// Returns the type with the given name or an error if it's not defined.
+    fn resolve(&self, name: &str) -> Result<Cow<'_, Type>, SchemaError> {
+        self.get(name)
+            .ok_or_else(|| SchemaError::Invalid(format!("undefined type `{}`", name)))
+    }
+
+    fn check(&self) -> Result<(), SchemaError> {
+        for (name, ty) in &self.types {
+            if PRELUDE.contains(&name.as_str()) {
+                return Err(SchemaError::Invalid(format!(
+                    "type `{}` is already defined in the prelude",
+                    name
+                )));
+            }
+            self.check_type(ty)?;
+        }
+        Ok(())
+    }
+
+    fn check_term(&self, term: &TypeTerm) -> Result<(), SchemaError> {
+        match term {
+            TypeTerm::Named(name) => self.resolve(name).map(|_| ()),
+            TypeTerm::Inline(ty) => self.check_type(ty),
+        }
+    }
+
+    fn check_type(&self, ty: &Type) -> Result<(), SchemaError> {
+        match ty {
+            Type::Map(map) => {
+                self.resolve(&map.key_type)?;
+                self.check_term(&map.value_type)
+            }
+            Type::List(list) => self.check_term(&list.value_type),
+            Type::Link(LinkType {
+                expected_type: Some(name),
+            }) => self.resolve(name).map(|_| ()),
+            Type::Struct(structure) => {
+                for field in &structure.fields {
+                    self.check_term(&field.value_type)?;
+                }
+                Ok(())
+            }
+            Type::Union(UnionRepresentation::Keyed(members))
+            | Type::Union(UnionRepresentation::Envelope {
+                discriminants: members,
+                ..
+            }) => members
+                .iter()
+                .try_for_each(|(_, member)| self.check_term(member)),
+            Type::Union(UnionRepresentation::Kinded(members)) => members
+                .iter()
+                .try_for_each(|(_, member)| self.check_term(member)),
+            Type::Union(UnionRepresentation::Inline { discriminants, .. }) => {
+                for (_, member) in discriminants {
+                    match self.resolve(member)?.as_ref() {
+                        Type::Struct(StructType {
+                            representation: StructRepresentation::Map { .. },
+                            ..
+                        }) => {}
+                        _ => {
+                            return Err(SchemaError::Invalid(format!(
+                                "member `{}` of an inline union must be a struct with map \
+                                 representation",
+                                member
+                            )))
+                        }
+                    }
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns the name of a kind as used in schemas.
+fn kind_name(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Null => "null",
+        Kind::Bool => "bool",
+        Kind::Integer => "int",
+        Kind::Float => "float",
+        Kind::String => "string",
+        Kind::Bytes => "bytes",
+        Kind::List => "list",
+        Kind::Map => "map",
+        Kind::Link => "link",
+    }
+}
+
+/// Returns the kind with the given name as used in schemas.
+fn kind_from_name(name: &str) -> Option<Kind> {
+    [
+        Kind::Null,
+        Kind::Bool,
+        Kind::Integer,
+        Kind::Float,
+        Kind::String,
+        Kind::Bytes,
+        Kind::List,
+        Kind::Map,
+        Kind::Link,
+    ]
+    .iter()
+    .copied()
+    .find(|kind| kind_name(*kind) == name)
+}
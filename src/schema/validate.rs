@@ -0,0 +1,477 @@
+//! Validation of data against the types of a schema.
+use cid::Cid;
+
+use super::{
+    kind_name, EnumRepresentation, EnumType, LinkType, MapRepresentation, MapType, Schema,
+    StructField, StructRepresentation, StructType, Type, TypeTerm, UnionRepresentation,
+};
+use crate::blockstore::Blockstore;
+use crate::error::{BlockstoreError, SchemaError};
+use crate::node::{Kind, Node};
+use crate::path::Path;
+
+type Result<T> = std::result::Result<T, SchemaError>;
+
+fn mismatch(path: &Path, reason: String) -> SchemaError {
+    SchemaError::Mismatch {
+        path: path.clone(),
+        reason,
+    }
+}
+
+fn expect_kind(node: Node<'_>, kind: Kind, name: &str, path: &Path) -> Result<()> {
+    if node.kind() == kind {
+        Ok(())
+    } else {
+        Err(mismatch(
+            path,
+            format!("expected {}, found {}", name, describe(node)),
+        ))
+    }
+}
+
+/// Describes the kind of a node for error messages.
+fn describe(node: Node<'_>) -> &'static str {
+    match node.kind() {
+        Kind::Null => "Null",
+        Kind::Bool => "Bool",
+        Kind::Integer => "Int",
+        Kind::Float => "Float",
+        Kind::String => "String",
+        Kind::Bytes => "Bytes",
+        Kind::List => "List",
+        Kind::Map => "Map",
+        Kind::Link => "Link",
+    }
+}
+
+fn string<'a>(node: Node<'a>, path: &Path) -> Result<&'a str> {
+    expect_kind(node, Kind::String, "String", path)?;
+    node.as_str()
+        .ok_or_else(|| mismatch(path, "invalid UTF-8".to_string()))
+}
+
+/// Returns the key and value of a two element list.
+fn pair<'a>(node: Node<'a>, path: &Path) -> Result<(&'a str, Node<'a>)> {
+    expect_kind(node, Kind::List, "List", path)?;
+    match node.elements()?.as_slice() {
+        [key, value] => Ok((string(*key, &path.join("0"))?, *value)),
+        _ => Err(mismatch(
+            path,
+            "expected a list of two elements".to_string(),
+        )),
+    }
+}
+
+/// Splits a string into pairs.
+fn string_pairs<'a>(
+    string: &'a str,
+    inner_delim: &str,
+    entry_delim: &str,
+    path: &Path,
+) -> Result<Vec<(&'a str, &'a str)>> {
+    if string.is_empty() {
+        return Ok(Vec::new());
+    }
+    string
+        .split(entry_delim)
+        .map(|entry| {
+            let mut parts = entry.splitn(2, inner_delim);
+            match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => Ok((key, value)),
+                _ => Err(mismatch(
+                    path,
+                    format!("entry `{}` has no delimiter `{}`", entry, inner_delim),
+                )),
+            }
+        })
+        .collect()
+}
+
+pub(super) struct Validator<'a, S> {
+    schema: &'a Schema,
+    /// The store to load linked blocks from, typed links are only followed if it's set.
+    store: Option<&'a S>,
+}
+
+impl<'a, S: Blockstore> Validator<'a, S> {
+    pub(super) fn new(schema: &'a Schema, store: Option<&'a S>) -> Self {
+        Validator { schema, store }
+    }
+
+    /// Validates the block with the given CID.
+    pub(super) fn validate_block(&self, cid: &Cid, type_name: &str, path: &Path) -> Result<()> {
+        let store = self.store.expect("blocks are only loaded with a store");
+        let data = store.get(cid)?.ok_or(BlockstoreError::NotFound(*cid))?;
+        self.validate(Node::new(&data)?, type_name, path)
+    }
+
+    /// Validates a node against the type with the given name.
+    pub(super) fn validate(&self, node: Node<'_>, type_name: &str, path: &Path) -> Result<()> {
+        let ty = self.schema.resolve(type_name)?;
+        self.validate_type(node, &ty, path)
+    }
+
+    fn validate_term(
+        &self,
+        node: Node<'_>,
+        term: &TypeTerm,
+        nullable: bool,
+        path: &Path,
+    ) -> Result<()> {
+        if nullable && node.kind() == Kind::Null {
+            return Ok(());
+        }
+        match term {
+            TypeTerm::Named(name) => self.validate(node, name, path),
+            TypeTerm::Inline(ty) => self.validate_type(node, ty, path),
+        }
+    }
+
+    fn validate_type(&self, node: Node<'_>, ty: &Type, path: &Path) -> Result<()> {
+        match ty {
+            Type::Bool => expect_kind(node, Kind::Bool, "Bool", path),
+            Type::String => string(node, path).map(|_| ()),
+            Type::Bytes => expect_kind(node, Kind::Bytes, "Bytes", path),
+            Type::Int => expect_kind(node, Kind::Integer, "Int", path),
+            Type::Float => expect_kind(node, Kind::Float, "Float", path),
+            Type::Any => Ok(()),
+            Type::Map(map) => self.validate_map(node, map, path),
+            Type::List(list) => {
+                expect_kind(node, Kind::List, "List", path)?;
+                for (index, element) in node.elements()?.into_iter().enumerate() {
+                    let path = path.join(index.to_string());
+                    self.validate_term(element, &list.value_type, list.value_nullable, &path)?;
+                }
+                Ok(())
+            }
+            Type::Link(link) => self.validate_link(node, link, path),
+            Type::Struct(structure) => self.validate_struct(node, structure, None, path),
+            Type::Union(union) => self.validate_union(node, union, path),
+            Type::Enum(enumeration) => self.validate_enum(node, enumeration, path),
+        }
+    }
+
+    fn validate_link(&self, node: Node<'_>, link: &LinkType, path: &Path) -> Result<()> {
+        expect_kind(node, Kind::Link, "Link", path)?;
+        let cid = node
+            .as_link()
+            .ok_or_else(|| mismatch(path, "invalid CID".to_string()))?;
+        match (&link.expected_type, self.store) {
+            (Some(expected_type), Some(_)) => self.validate_block(&cid, expected_type, path),
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_map(&self, node: Node<'_>, map: &MapType, path: &Path) -> Result<()> {
+        let value = |value: Node<'_>, path: &Path| {
+            self.validate_term(value, &map.value_type, map.value_nullable, path)
+        };
+        match &map.representation {
+            MapRepresentation::Map => {
+                expect_kind(node, Kind::Map, "Map", path)?;
+                for (key, entry) in node.entries()? {
+                    let path = path.join(key);
+                    self.validate_str(key, &TypeTerm::Named(map.key_type.clone()), &path)?;
+                    value(entry, &path)?;
+                }
+            }
+            MapRepresentation::StringPairs {
+                inner_delim,
+                entry_delim,
+            } => {
+                for (key, entry) in
+                    string_pairs(string(node, path)?, inner_delim, entry_delim, path)?
+                {
+                    let path = path.join(key);
+                    self.validate_str(key, &TypeTerm::Named(map.key_type.clone()), &path)?;
+                    self.validate_str(entry, &map.value_type, &path)?;
+                }
+            }
+            MapRepresentation::ListPairs => {
+                expect_kind(node, Kind::List, "List", path)?;
+                for (index, entry) in node.elements()?.into_iter().enumerate() {
+                    let (key, entry) = pair(entry, &path.join(index.to_string()))?;
+                    let path = path.join(key);
+                    self.validate_str(key, &TypeTerm::Named(map.key_type.clone()), &path)?;
+                    value(entry, &path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates a struct, the `skip` key is ignored, it's the discriminant of inline unions.
+    fn validate_struct(
+        &self,
+        node: Node<'_>,
+        structure: &StructType,
+        skip: Option<&str>,
+        path: &Path,
+    ) -> Result<()> {
+        let fields = &structure.fields;
+        match &structure.representation {
+            StructRepresentation::Map { renames } => {
+                expect_kind(node, Kind::Map, "Map", path)?;
+                let key = |field: &StructField| {
+                    renames
+                        .get(&field.name)
+                        .map(String::as_str)
+                        .unwrap_or(&field.name)
+                        .to_string()
+                };
+                let entries = node.entries()?;
+                for (entry_key, value) in &entries {
+                    if Some(*entry_key) == skip {
+                        continue;
+                    }
+                    let path = path.join(*entry_key);
+                    let field = fields
+                        .iter()
+                        .find(|field| key(field) == *entry_key)
+                        .ok_or_else(|| mismatch(&path, "unknown field".to_string()))?;
+                    self.validate_term(*value, &field.value_type, field.nullable, &path)?;
+                }
+                for field in fields.iter().filter(|field| !field.optional) {
+                    let key = key(field);
+                    if !entries.iter().any(|(entry_key, _)| *entry_key == key) {
+                        return Err(mismatch(path, format!("missing field `{}`", key)));
+                    }
+                }
+            }
+            StructRepresentation::Tuple => {
+                expect_kind(node, Kind::List, "List", path)?;
+                let elements = node.elements()?;
+                let required = fields
+                    .iter()
+                    .rposition(|field| !field.optional)
+                    .map_or(0, |index| index + 1);
+                if elements.len() < required || elements.len() > fields.len() {
+                    return Err(mismatch(
+                        path,
+                        format!(
+                            "expected {} to {} elements, found {}",
+                            required,
+                            fields.len(),
+                            elements.len()
+                        ),
+                    ));
+                }
+                for (index, (element, field)) in elements.into_iter().zip(fields).enumerate() {
+                    let path = path.join(index.to_string());
+                    self.validate_term(element, &field.value_type, field.nullable, &path)?;
+                }
+            }
+            StructRepresentation::StringPairs {
+                inner_delim,
+                entry_delim,
+            } => {
+                let pairs = string_pairs(string(node, path)?, inner_delim, entry_delim, path)?;
+                for (key, value) in &pairs {
+                    let path = path.join(*key);
+                    let field = fields
+                        .iter()
+                        .find(|field| field.name == *key)
+                        .ok_or_else(|| mismatch(&path, "unknown field".to_string()))?;
+                    self.validate_str(value, &field.value_type, &path)?;
+                }
+                for field in fields.iter().filter(|field| !field.optional) {
+                    if !pairs.iter().any(|(key, _)| *key == field.name) {
+                        return Err(mismatch(path, format!("missing field `{}`", field.name)));
+                    }
+                }
+            }
+            StructRepresentation::StringJoin { join } => {
+                let values: Vec<_> = string(node, path)?.split(join.as_str()).collect();
+                if values.len() != fields.len() {
+                    return Err(mismatch(
+                        path,
+                        format!(
+                            "expected {} values joined by `{}`, found {}",
+                            fields.len(),
+                            join,
+                            values.len()
+                        ),
+                    ));
+                }
+                for (value, field) in values.into_iter().zip(fields) {
+                    self.validate_str(value, &field.value_type, &path.join(field.name.as_str()))?;
+                }
+            }
+            StructRepresentation::ListPairs => {
+                expect_kind(node, Kind::List, "List", path)?;
+                let mut present = Vec::new();
+                for (index, entry) in node.elements()?.into_iter().enumerate() {
+                    let (key, value) = pair(entry, &path.join(index.to_string()))?;
+                    let path = path.join(key);
+                    let field = fields
+                        .iter()
+                        .find(|field| field.name == key)
+                        .ok_or_else(|| mismatch(&path, "unknown field".to_string()))?;
+                    self.validate_term(value, &field.value_type, field.nullable, &path)?;
+                    present.push(key);
+                }
+                for field in fields.iter().filter(|field| !field.optional) {
+                    if !present.contains(&field.name.as_str()) {
+                        return Err(mismatch(path, format!("missing field `{}`", field.name)));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_union(
+        &self,
+        node: Node<'_>,
+        union: &UnionRepresentation,
+        path: &Path,
+    ) -> Result<()> {
+        match union {
+            UnionRepresentation::Keyed(members) => {
+                expect_kind(node, Kind::Map, "Map", path)?;
+                let (key, value) = match node.entries()?.as_slice() {
+                    [entry] => *entry,
+                    _ => return Err(mismatch(path, "expected a map with one entry".to_string())),
+                };
+                let path = path.join(key);
+                let member = lookup(members, key)
+                    .ok_or_else(|| mismatch(&path, format!("unknown union key `{}`", key)))?;
+                self.validate_term(value, member, false, &path)
+            }
+            UnionRepresentation::Kinded(members) => {
+                let kind = node.kind();
+                let member = members
+                    .iter()
+                    .find(|(member_kind, _)| *member_kind == kind)
+                    .map(|(_, member)| member)
+                    .ok_or_else(|| {
+                        mismatch(path, format!("no union member of kind {}", kind_name(kind)))
+                    })?;
+                self.validate_term(node, member, false, path)
+            }
+            UnionRepresentation::Envelope {
+                discriminant_key,
+                content_key,
+                discriminants,
+            } => {
+                expect_kind(node, Kind::Map, "Map", path)?;
+                let entries = node.entries()?;
+                if let Some((key, _)) = entries
+                    .iter()
+                    .find(|(key, _)| key != discriminant_key && key != content_key)
+                {
+                    return Err(mismatch(&path.join(*key), "unknown field".to_string()));
+                }
+                let discriminant = self.discriminant(node, discriminant_key, path)?;
+                let member = lookup(discriminants, discriminant).ok_or_else(|| {
+                    mismatch(
+                        &path.join(discriminant_key.as_str()),
+                        format!("unknown discriminant `{}`", discriminant),
+                    )
+                })?;
+                let content = node
+                    .get_field(content_key)?
+                    .ok_or_else(|| mismatch(path, format!("missing field `{}`", content_key)))?;
+                self.validate_term(content, member, false, &path.join(content_key.as_str()))
+            }
+            UnionRepresentation::Inline {
+                discriminant_key,
+                discriminants,
+            } => {
+                expect_kind(node, Kind::Map, "Map", path)?;
+                let discriminant = self.discriminant(node, discriminant_key, path)?;
+                let member = discriminants
+                    .iter()
+                    .find(|(key, _)| key == discriminant)
+                    .map(|(_, member)| member)
+                    .ok_or_else(|| {
+                        mismatch(
+                            &path.join(discriminant_key.as_str()),
+                            format!("unknown discriminant `{}`", discriminant),
+                        )
+                    })?;
+                match self.schema.resolve(member)?.as_ref() {
+                    Type::Struct(structure) => {
+                        self.validate_struct(node, structure, Some(discriminant_key), path)
+                    }
+                    _ => unreachable!("members of inline unions are checked to be structs"),
+                }
+            }
+        }
+    }
+
+    /// Returns the string value of the discriminant key of a map.
+    fn discriminant<'n>(&self, node: Node<'n>, key: &str, path: &Path) -> Result<&'n str> {
+        let value = node
+            .get_field(key)?
+            .ok_or_else(|| mismatch(path, format!("missing field `{}`", key)))?;
+        string(value, &path.join(key))
+    }
+
+    fn validate_enum(&self, node: Node<'_>, enumeration: &EnumType, path: &Path) -> Result<()> {
+        let known = match &enumeration.representation {
+            EnumRepresentation::String(_) => {
+                let value = string(node, path)?;
+                self.enum_member(value, enumeration).is_some()
+            }
+            EnumRepresentation::Int(values) => {
+                expect_kind(node, Kind::Integer, "Int", path)?;
+                let value = node.as_integer();
+                values
+                    .values()
+                    .any(|member| Some(i128::from(*member)) == value)
+            }
+        };
+        if known {
+            Ok(())
+        } else {
+            Err(mismatch(path, "unknown enum member".to_string()))
+        }
+    }
+
+    /// Returns the member of a string enum that is represented by the given string.
+    fn enum_member<'e>(&self, value: &str, enumeration: &'e EnumType) -> Option<&'e str> {
+        let renames = match &enumeration.representation {
+            EnumRepresentation::String(renames) => renames,
+            EnumRepresentation::Int(_) => return None,
+        };
+        enumeration
+            .members
+            .iter()
+            .find(|member| renames.get(*member).unwrap_or(member) == value)
+            .map(String::as_str)
+    }
+
+    /// Validates a value that is represented as a string, like a map key or a `stringjoin` field.
+    fn validate_str(&self, value: &str, term: &TypeTerm, path: &Path) -> Result<()> {
+        let ty = match term {
+            TypeTerm::Named(name) => self.schema.resolve(name)?,
+            TypeTerm::Inline(ty) => std::borrow::Cow::Borrowed(ty.as_ref()),
+        };
+        let valid = match ty.as_ref() {
+            Type::String => true,
+            Type::Int => value.parse::<i64>().is_ok(),
+            Type::Float => value.parse::<f64>().is_ok(),
+            Type::Bool => value == "true" || value == "false",
+            Type::Enum(enumeration) => self.enum_member(value, enumeration).is_some(),
+            _ => {
+                return Err(SchemaError::Invalid(
+                    "type can't be represented as a string".to_string(),
+                ))
+            }
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(mismatch(path, format!("invalid value `{}`", value)))
+        }
+    }
+}
+
+fn lookup<'a>(members: &'a [(String, TypeTerm)], key: &str) -> Option<&'a TypeTerm> {
+    members
+        .iter()
+        .find(|(member_key, _)| member_key == key)
+        .map(|(_, member)| member)
+}
@@ -0,0 +1,477 @@
+//! IPLD selectors.
+//!
+//! A [`Selector`] describes which nodes of a DAG should be visited and which of them are matched.
+//! Selectors are DAG-CBOR documents themselves, they are parsed with [`Selector::from_slice`].
+//! The [`select`] function evaluates a selector on the lazy [`Node`] view of the blocks, links are
+//! followed transparently by loading the blocks from a [`Blockstore`].
+//!
+//! The supported selectors and their keys in the serialized form are:
+//!
+//!  - `.`: [`Selector::Matcher`]
+//!  - `a`: [`Selector::ExploreAll`]
+//!  - `f`: [`Selector::ExploreFields`]
+//!  - `i`: [`Selector::ExploreIndex`]
+//!  - `r`: [`Selector::ExploreRange`]
+//!  - `|`: [`Selector::ExploreUnion`]
+//!  - `R`: [`Selector::ExploreRecursive`]
+//!  - `@`: [`Selector::ExploreRecursiveEdge`]
+//!
+//! # Examples
+//!
+//! ```
+//! use serde_ipld_dagcbor::blockstore::{Blockstore, MemoryBlockstore};
+//! use serde_ipld_dagcbor::diag::from_diag;
+//! use serde_ipld_dagcbor::selector::{select, Selector};
+//!
+//! let mut store = MemoryBlockstore::new();
+//! let leaf = store.put_value(&"leaf").unwrap();
+//! let root = store.put_value(&vec![leaf, leaf]).unwrap();
+//!
+//! // Match every node of the DAG.
+//! let selector = from_diag(r#"{"R": {"l": {"none": {}}, ":>": {"|": [{".": {}}, {"a": {">": {"@": {}}}}]}}}"#).unwrap();
+//! let selector = Selector::from_slice(&selector).unwrap();
+//! let traversal = select(&store, &root, &selector).unwrap();
+//! let paths: Vec<_> = traversal.matches.iter().map(|m| m.path.to_string()).collect();
+//! assert_eq!(paths, ["", "0", "1"]);
+//! assert_eq!(traversal.blocks, [root, leaf]);
+//! ```
+use std::collections::{BTreeMap, HashSet};
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::rc::Rc;
+
+use cid::Cid;
+
+use crate::blockstore::Blockstore;
+use crate::error::TraversalError;
+use crate::node::{Kind, Node};
+use crate::path::{load, subrange, Path};
+
+/// The limit of an [`Selector::ExploreRecursive`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecursionLimit {
+    /// The recursion is not limited.
+    None,
+    /// The maximum number of levels the sequence is applied to, the level the recursion starts
+    /// at counts as the first one.
+    Depth(u64),
+}
+
+/// A selector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Selector {
+    /// Matches the current node.
+    Matcher,
+    /// Applies the selector to all elements of a list or all values of a map.
+    ExploreAll(Box<Selector>),
+    /// Applies the selectors to the map values with the given keys.
+    ExploreFields(BTreeMap<String, Selector>),
+    /// Applies the selector to the list element at the given index.
+    ExploreIndex(usize, Box<Selector>),
+    /// Applies the selector to the list elements in the range from start (inclusive) to end
+    /// (exclusive).
+    ExploreRange(usize, usize, Box<Selector>),
+    /// Applies all selectors to the current node.
+    ExploreUnion(Vec<Selector>),
+    /// Applies the sequence to the current node. Within the sequence, the
+    /// [`Selector::ExploreRecursiveEdge`] applies the sequence again.
+    ExploreRecursive {
+        /// How deep the recursion may go.
+        limit: RecursionLimit,
+        /// The selector that is applied on every level.
+        sequence: Box<Selector>,
+    },
+    /// Marks where the sequence of the innermost [`Selector::ExploreRecursive`] is applied again.
+    ExploreRecursiveEdge,
+}
+
+impl Selector {
+    /// Parses a selector from DAG-CBOR.
+    pub fn from_slice(data: &[u8]) -> Result<Self, TraversalError> {
+        Selector::from_node(Node::new(data)?)
+    }
+
+    /// Parses a selector from a node.
+    pub fn from_node(node: Node<'_>) -> Result<Self, TraversalError> {
+        let entries = map_entries(node, "selector")?;
+        let (key, body) = match entries.as_slice() {
+            [entry] => *entry,
+            _ => return Err(invalid("a selector must be a map with a single key")),
+        };
+        match key {
+            "." => Ok(Selector::Matcher),
+            "a" => Ok(Selector::ExploreAll(Box::new(next(body)?))),
+            "f" => {
+                let fields = field(body, "f>")?;
+                let fields = map_entries(fields, "f>")?
+                    .into_iter()
+                    .map(|(key, selector)| Ok((key.to_string(), Selector::from_node(selector)?)))
+                    .collect::<Result<_, TraversalError>>()?;
+                Ok(Selector::ExploreFields(fields))
+            }
+            "i" => Ok(Selector::ExploreIndex(
+                index(body, "i")?,
+                Box::new(next(body)?),
+            )),
+            "r" => Ok(Selector::ExploreRange(
+                index(body, "^")?,
+                index(body, "$")?,
+                Box::new(next(body)?),
+            )),
+            "|" => {
+                let selectors = body
+                    .elements()
+                    .map_err(|_| invalid("`|` must be a list"))?
+                    .into_iter()
+                    .map(Selector::from_node)
+                    .collect::<Result<_, _>>()?;
+                Ok(Selector::ExploreUnion(selectors))
+            }
+            "R" => {
+                let limit = field(body, "l")?;
+                let limit = match map_entries(limit, "l")?.as_slice() {
+                    [("none", _)] => RecursionLimit::None,
+                    [("depth", depth)] => RecursionLimit::Depth(
+                        depth
+                            .as_integer()
+                            .and_then(|depth| u64::try_from(depth).ok())
+                            .ok_or_else(|| invalid("`depth` must be a non-negative integer"))?,
+                    ),
+                    _ => return Err(invalid("`l` must be either `none` or `depth`")),
+                };
+                let sequence = Box::new(Selector::from_node(field(body, ":>")?)?);
+                Ok(Selector::ExploreRecursive { limit, sequence })
+            }
+            "@" => Ok(Selector::ExploreRecursiveEdge),
+            _ => Err(TraversalError::InvalidSelector(format!(
+                "unknown selector `{}`",
+                key
+            ))),
+        }
+    }
+}
+
+fn invalid(reason: &str) -> TraversalError {
+    TraversalError::InvalidSelector(reason.to_string())
+}
+
+fn map_entries<'a>(node: Node<'a>, name: &str) -> Result<Vec<(&'a str, Node<'a>)>, TraversalError> {
+    node.entries()
+        .map_err(|_| TraversalError::InvalidSelector(format!("`{}` must be a map", name)))
+}
+
+fn field<'a>(node: Node<'a>, key: &str) -> Result<Node<'a>, TraversalError> {
+    node.get_field(key)?
+        .ok_or_else(|| TraversalError::InvalidSelector(format!("missing field `{}`", key)))
+}
+
+fn next(node: Node<'_>) -> Result<Selector, TraversalError> {
+    Selector::from_node(field(node, ">")?)
+}
+
+fn index(node: Node<'_>, key: &str) -> Result<usize, TraversalError> {
+    field(node, key)?
+        .as_integer()
+        .and_then(|index| usize::try_from(index).ok())
+        .ok_or_else(|| {
+            TraversalError::InvalidSelector(format!("`{}` must be a non-negative integer", key))
+        })
+}
+
+/// A node that was matched by a [`Selector::Matcher`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+    /// The path from the root to the node.
+    pub path: Path,
+    /// The CID of the block that contains the node.
+    pub cid: Cid,
+    /// The encoded data of the node.
+    pub data: Vec<u8>,
+}
+
+impl Match {
+    /// Returns a view of the node.
+    pub fn node(&self) -> Node<'_> {
+        Node::new(&self.data).expect("the data was taken from a valid node")
+    }
+}
+
+/// The result of evaluating a selector.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Traversal {
+    /// The matched nodes in the order they were visited.
+    pub matches: Vec<Match>,
+    /// The CIDs of the blocks that were loaded, in the order they were first loaded.
+    pub blocks: Vec<Cid>,
+}
+
+/// Evaluates a selector on the DAG with the given root.
+///
+/// The nodes are visited depth-first. A link is followed whenever the selector explores the
+/// node it is in, the selector is then applied to the root of the linked block. Links that would
+/// lead back to a block that is currently explored are not followed. The evaluation doesn't
+/// recurse, so arbitrarily long chains of links can be traversed. An unlimited
+/// [`Selector::ExploreRecursive`] whose edge is reached without exploring a child would never
+/// end, it's rejected with a [`TraversalError::InvalidSelector`].
+pub fn select<S: Blockstore>(
+    store: &S,
+    root: &Cid,
+    selector: &Selector,
+) -> Result<Traversal, TraversalError> {
+    let mut evaluator = Evaluator {
+        store,
+        traversal: Traversal::default(),
+        loaded: HashSet::new(),
+        active: HashSet::new(),
+        tasks: vec![Task::Block {
+            cid: *root,
+            trail: None,
+            selector,
+            recursions: Rc::from([]),
+        }],
+    };
+    while let Some(task) = evaluator.tasks.pop() {
+        match task {
+            Task::Block {
+                cid,
+                trail,
+                selector,
+                recursions,
+            } => evaluator.visit_block(cid, trail, selector, recursions)?,
+            Task::Node(step) => evaluator.explore(step)?,
+            Task::Leave(cid) => {
+                evaluator.active.remove(&cid);
+            }
+        }
+    }
+    Ok(evaluator.traversal)
+}
+
+/// The path to a node as a list of segments that leads back to the root, so that the paths of
+/// the children share the one of their parent.
+struct Trail {
+    segment: String,
+    parent: Option<Rc<Trail>>,
+    /// The number of segments of the path.
+    len: usize,
+}
+
+fn join(trail: &Option<Rc<Trail>>, segment: String) -> Option<Rc<Trail>> {
+    Some(Rc::new(Trail {
+        segment,
+        parent: trail.clone(),
+        len: trail_len(trail) + 1,
+    }))
+}
+
+fn trail_len(trail: &Option<Rc<Trail>>) -> usize {
+    trail.as_ref().map_or(0, |trail| trail.len)
+}
+
+fn to_path(mut trail: &Option<Rc<Trail>>) -> Path {
+    let mut segments = Vec::with_capacity(trail_len(trail));
+    while let Some(current) = trail {
+        segments.push(current.segment.clone());
+        trail = &current.parent;
+    }
+    segments.reverse();
+    Path::new(segments)
+}
+
+/// An active recursion.
+#[derive(Clone, Copy)]
+struct Recursion<'s> {
+    sequence: &'s Selector,
+    /// The remaining limit.
+    limit: RecursionLimit,
+    /// The length of the path of the node the sequence was last applied to.
+    len: usize,
+}
+
+/// A selector that is applied to a node of a loaded block.
+struct Step<'s> {
+    cid: Cid,
+    block: Rc<[u8]>,
+    /// The range of the node within the block.
+    range: Range<usize>,
+    trail: Option<Rc<Trail>>,
+    selector: &'s Selector,
+    recursions: Rc<[Recursion<'s>]>,
+}
+
+impl<'s> Step<'s> {
+    /// Returns a step that applies another selector to the same node.
+    fn with(&self, selector: &'s Selector, recursions: Rc<[Recursion<'s>]>) -> Self {
+        Step {
+            cid: self.cid,
+            block: self.block.clone(),
+            range: self.range.clone(),
+            trail: self.trail.clone(),
+            selector,
+            recursions,
+        }
+    }
+}
+
+enum Task<'s> {
+    /// Loads a block and applies the selector to its root.
+    Block {
+        cid: Cid,
+        trail: Option<Rc<Trail>>,
+        selector: &'s Selector,
+        recursions: Rc<[Recursion<'s>]>,
+    },
+    Node(Step<'s>),
+    /// Marks the end of the exploration of a block.
+    Leave(Cid),
+}
+
+struct Evaluator<'a, 's, S> {
+    store: &'a S,
+    traversal: Traversal,
+    loaded: HashSet<Cid>,
+    /// The CIDs of the blocks that are currently explored, used to detect cycles.
+    active: HashSet<Cid>,
+    /// The pending work, the next task is at the end.
+    tasks: Vec<Task<'s>>,
+}
+
+impl<'s, S: Blockstore> Evaluator<'_, 's, S> {
+    fn visit_block(
+        &mut self,
+        cid: Cid,
+        trail: Option<Rc<Trail>>,
+        selector: &'s Selector,
+        recursions: Rc<[Recursion<'s>]>,
+    ) -> Result<(), TraversalError> {
+        if self.active.contains(&cid) {
+            return Ok(());
+        }
+        let block: Rc<[u8]> = Rc::from(load(self.store, &cid)?);
+        if self.loaded.insert(cid) {
+            self.traversal.blocks.push(cid);
+        }
+        self.active.insert(cid);
+        self.tasks.push(Task::Leave(cid));
+        self.tasks.push(Task::Node(Step {
+            cid,
+            range: 0..block.len(),
+            block,
+            trail,
+            selector,
+            recursions,
+        }));
+        Ok(())
+    }
+
+    fn explore(&mut self, step: Step<'s>) -> Result<(), TraversalError> {
+        let node = Node::new_unchecked(&step.block[step.range.clone()]);
+        // The tasks in the order they are run, they are pushed in reverse.
+        let mut next_tasks = Vec::new();
+        // Applies a selector to a child node, following it if it's a link.
+        let mut visit = |child: Node<'_>, segment: String, selector: &'s Selector| {
+            let trail = join(&step.trail, segment);
+            let recursions = step.recursions.clone();
+            next_tasks.push(match child.as_link() {
+                Some(cid) => Task::Block {
+                    cid,
+                    trail,
+                    selector,
+                    recursions,
+                },
+                None => Task::Node(Step {
+                    cid: step.cid,
+                    block: step.block.clone(),
+                    range: subrange(&step.block, child.data()),
+                    trail,
+                    selector,
+                    recursions,
+                }),
+            });
+        };
+        match step.selector {
+            Selector::Matcher => self.traversal.matches.push(Match {
+                path: to_path(&step.trail),
+                cid: step.cid,
+                data: node.data().to_vec(),
+            }),
+            Selector::ExploreAll(next) => match node.kind() {
+                Kind::List => {
+                    for (index, child) in node.elements()?.into_iter().enumerate() {
+                        visit(child, index.to_string(), next);
+                    }
+                }
+                Kind::Map => {
+                    for (key, child) in node.entries()? {
+                        visit(child, key.to_string(), next);
+                    }
+                }
+                _ => {}
+            },
+            Selector::ExploreFields(fields) if node.kind() == Kind::Map => {
+                for (key, next) in fields {
+                    if let Some(child) = node.get_field(key)? {
+                        visit(child, key.clone(), next);
+                    }
+                }
+            }
+            Selector::ExploreIndex(index, next) if node.kind() == Kind::List => {
+                if let Some(child) = node.get_index(*index)? {
+                    visit(child, index.to_string(), next);
+                }
+            }
+            Selector::ExploreRange(start, end, next) if node.kind() == Kind::List => {
+                let elements = node.elements()?.into_iter().enumerate();
+                for (index, child) in elements.take(*end).skip(*start) {
+                    visit(child, index.to_string(), next);
+                }
+            }
+            Selector::ExploreFields(_)
+            | Selector::ExploreIndex(..)
+            | Selector::ExploreRange(..) => {}
+            Selector::ExploreUnion(selectors) => {
+                for selector in selectors {
+                    next_tasks.push(Task::Node(step.with(selector, step.recursions.clone())));
+                }
+            }
+            Selector::ExploreRecursive { limit, sequence } => {
+                let mut recursions = step.recursions.to_vec();
+                recursions.push(Recursion {
+                    sequence,
+                    limit: *limit,
+                    len: trail_len(&step.trail),
+                });
+                next_tasks.push(Task::Node(step.with(sequence, recursions.into())));
+            }
+            Selector::ExploreRecursiveEdge => {
+                let recursion = match step.recursions.last() {
+                    Some(recursion) => *recursion,
+                    None => {
+                        return Err(invalid(
+                            "`@` must be within the sequence of an `R` selector",
+                        ))
+                    }
+                };
+                let len = trail_len(&step.trail);
+                let limit =
+                    match recursion.limit {
+                        RecursionLimit::None if len == recursion.len => return Err(invalid(
+                            "`@` of an unlimited `R` selector must be reached by exploring a child",
+                        )),
+                        RecursionLimit::None => RecursionLimit::None,
+                        RecursionLimit::Depth(depth) if depth < 2 => return Ok(()),
+                        RecursionLimit::Depth(depth) => RecursionLimit::Depth(depth - 1),
+                    };
+                let mut recursions = step.recursions.to_vec();
+                *recursions.last_mut().expect("there is an active recursion") = Recursion {
+                    sequence: recursion.sequence,
+                    limit,
+                    len,
+                };
+                next_tasks.push(Task::Node(step.with(recursion.sequence, recursions.into())));
+            }
+        }
+        self.tasks.extend(next_tasks.into_iter().rev());
+        Ok(())
+    }
+}
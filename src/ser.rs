@@ -2,7 +2,7 @@
 #[cfg(not(feature = "std"))]
 use alloc::collections::TryReserveError;
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{boxed::Box, string::ToString, vec, vec::Vec};
 #[cfg(feature = "std")]
 use std::collections::TryReserveError;
 
@@ -11,21 +11,111 @@ pub use cbor4ii::core::utils::BufWriter;
 use cbor4ii::core::utils::IoWriter;
 use cbor4ii::core::{
     enc::{self, Encode},
-    types,
+    major, types,
 };
 use cid::serde::CID_SERDE_PRIVATE_IDENTIFIER;
+use cid::Cid;
+use core::cmp::Ordering;
+#[cfg(feature = "hash")]
+use core::convert::Infallible;
+use core::convert::TryFrom;
+use core::ops::Range;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering as AtomicOrdering};
 use serde::{ser, Serialize};
 
+use crate::cbor4ii_nonpub::{encode_head, marker};
 use crate::error::EncodeError;
+#[cfg(feature = "hash")]
+use crate::hash::{HashWriter, MultihashHasher, Sink};
+use crate::node::Node;
 use crate::CBOR_TAGS_CID;
+#[cfg(feature = "hash")]
+use crate::DAG_CBOR_CODEC;
+
+/// How enum variants are represented.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EnumRepresentation {
+    /// Unit variants are strings of their name, all other variants are maps with a single entry
+    /// from the name to the value, e.g. `{"Variant": 1}`. This is the default.
+    #[default]
+    External,
+    /// Like [`EnumRepresentation::External`], but unit variants are integers of their variant
+    /// index, i.e. their position in declaration order, starting at zero. Explicit discriminants
+    /// are not visible to Serde and are ignored, `enum E { A = 2, B = 7 }` encodes `E::A` as `0`.
+    /// To encode the discriminants, derive the implementations with `#[ipld(repr = "int")]` of
+    /// the `derive` feature instead.
+    IndexedUnit,
+    /// Unit variants are strings of their name, all other variants are lists of the name and the
+    /// value, e.g. `["Variant", 1]`.
+    List,
+    /// Variants are maps that contain the name under the given key. The other entries are the
+    /// fields of struct variants or of the struct or map a newtype variant contains, e.g.
+    /// `{"a": 1, "type": "Variant"}`. The tag is sorted together with the other keys, when decoding
+    /// it may be at any position. Tuple variants are not supported.
+    InternallyTagged(&'static str),
+}
+
+/// Options for encoding.
+///
+/// Data encoded with options has to be decoded with the matching
+/// [`DecodeOptions`](crate::de::DecodeOptions).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EncodeOptions {
+    /// Encode structs as lists of their field values in declaration order, instead of maps from
+    /// the field names to the values. Fields that are skipped during serialization shift the
+    /// position of the following ones, so they can't be decoded again.
+    pub struct_tuples: bool,
+    /// How enum variants are represented.
+    pub enum_representation: EnumRepresentation,
+    /// Omit struct fields whose value is encoded as null, e.g. `None`. Such structs are buffered
+    /// to determine the number of fields. Structs encoded as tuples keep all fields.
+    pub omit_null_fields: bool,
+    /// Write struct fields in canonical DAG-CBOR order, shorter keys first, then bytewise, instead
+    /// of their declaration order. Structs whose fields aren't declared in canonical order encode
+    /// to different bytes, and therefore to different CIDs, than without this option. The order is
+    /// computed once per struct and cached for the lifetime of the program.
+    pub canonical_struct_fields: bool,
+}
 
 /// Serializes a value to a vector.
 pub fn to_vec<T>(value: &T) -> Result<Vec<u8>, EncodeError<TryReserveError>>
+where
+    T: Serialize + ?Sized,
+{
+    to_vec_with_options(value, &EncodeOptions::default())
+}
+
+/// Serializes a value to a vector with the given options.
+///
+/// # Examples
+///
+/// ```
+/// use serde_derive::Serialize;
+/// use serde_ipld_dagcbor::ser::{to_vec_with_options, EncodeOptions};
+///
+/// #[derive(Serialize)]
+/// struct Point {
+///     x: u8,
+///     y: u8,
+/// }
+///
+/// let options = EncodeOptions {
+///     struct_tuples: true,
+///     ..Default::default()
+/// };
+/// let encoded = to_vec_with_options(&Point { x: 1, y: 2 }, &options).unwrap();
+/// assert_eq!(encoded, [0x82, 0x01, 0x02]);
+/// ```
+pub fn to_vec_with_options<T>(
+    value: &T,
+    options: &EncodeOptions,
+) -> Result<Vec<u8>, EncodeError<TryReserveError>>
 where
     T: Serialize + ?Sized,
 {
     let writer = BufWriter::new(Vec::new());
-    let mut serializer = Serializer::new(writer);
+    let mut serializer = Serializer::with_options(writer, *options);
     value.serialize(&mut serializer)?;
     Ok(serializer.into_inner().into_inner())
 }
@@ -37,19 +127,152 @@ where
     W: std::io::Write,
     T: Serialize,
 {
-    let mut serializer = Serializer::new(IoWriter::new(writer));
+    to_writer_with_options(writer, value, &EncodeOptions::default())
+}
+
+/// Serializes a value to a writer with the given options.
+#[cfg(feature = "std")]
+pub fn to_writer_with_options<W, T>(
+    writer: W,
+    value: &T,
+    options: &EncodeOptions,
+) -> Result<(), EncodeError<std::io::Error>>
+where
+    W: std::io::Write,
+    T: Serialize,
+{
+    let mut serializer = Serializer::with_options(IoWriter::new(writer), *options);
     value.serialize(&mut serializer)
 }
 
+/// Writes a CID as DAG-CBOR link.
+///
+/// It's the same as serializing the CID, but the CID is written directly, without allocating.
+///
+/// # Examples
+///
+/// ```
+/// use cid::Cid;
+/// use serde_ipld_dagcbor::ser::{write_cid, BufWriter};
+///
+/// let cid: Cid = "bafyreibvjvcv745gig4mvqs4hctx4zfkono4rjejm2ta6gtyzkqxfjeily"
+///     .parse()
+///     .unwrap();
+/// let mut writer = BufWriter::new(Vec::new());
+/// write_cid(&mut writer, &cid).unwrap();
+/// assert_eq!(writer.buffer(), serde_ipld_dagcbor::to_vec(&cid).unwrap());
+/// ```
+pub fn write_cid<W: enc::Write>(writer: &mut W, cid: &Cid) -> Result<(), EncodeError<W::Error>> {
+    let mut buffer = [0; MAX_CID_LEN];
+    let len = cid_to_bytes(cid, &mut buffer)
+        .map_err(|_| EncodeError::Msg("CID cannot be encoded.".into()))?;
+    encode_cid_bytes(writer, &buffer[..len])?;
+    Ok(())
+}
+
+/// Serializes a CID like its `Serialize` implementation, but without allocating.
+///
+/// It can be used for fields with `#[serde(serialize_with = "...")]`.
+pub fn serialize_cid<S: ser::Serializer>(cid: &Cid, serializer: S) -> Result<S::Ok, S::Error> {
+    /// The bytes of a CID, which are passed on as bytes.
+    struct CidBytes<'a>(&'a [u8]);
+
+    impl Serialize for CidBytes<'_> {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+
+    let mut buffer = [0; MAX_CID_LEN];
+    let len = cid_to_bytes(cid, &mut buffer).map_err(ser::Error::custom)?;
+    serializer.serialize_newtype_struct(CID_SERDE_PRIVATE_IDENTIFIER, &CidBytes(&buffer[..len]))
+}
+
+/// The maximum length of an encoded [`Cid`]. The version, the codec, the multihash code and the
+/// digest size are varints of up to 10 bytes each, the digest has up to 64 bytes.
+const MAX_CID_LEN: usize = 4 * 10 + 64;
+
+/// Writes the bytes of the CID into the buffer and returns their length.
+fn cid_to_bytes(cid: &Cid, buffer: &mut [u8; MAX_CID_LEN]) -> Result<usize, cid::Error> {
+    let mut remaining = &mut buffer[..];
+    cid.write_bytes(&mut remaining)?;
+    let remaining = remaining.len();
+    Ok(MAX_CID_LEN - remaining)
+}
+
+/// Writes the bytes of a CID as DAG-CBOR link.
+pub(crate) fn encode_cid_bytes<W: enc::Write>(writer: &mut W, cid: &[u8]) -> Result<(), W::Error> {
+    // CIDs are serialized with CBOR tag 42.
+    encode_head(writer, major::TAG, CBOR_TAGS_CID)?;
+    // The bytes of the CID is prefixed with a null byte when encoded as CBOR.
+    encode_head(writer, major::BYTES, cid.len() as u64 + 1)?;
+    writer.push(&[0x00])?;
+    writer.push(cid)
+}
+
+/// Serializes a value and returns the CIDv1 of the encoded data, without keeping the data.
+///
+/// The data is hashed while it is serialized.
+///
+/// # Examples
+///
+/// ```
+/// use cid::multihash::Sha2_256;
+/// use serde_ipld_dagcbor::to_cid;
+///
+/// let cid = to_cid::<Sha2_256, _>(&"foobar").unwrap();
+/// assert_eq!(cid.codec(), 0x71);
+/// ```
+#[cfg(feature = "hash")]
+pub fn to_cid<H, T>(value: &T) -> Result<Cid, EncodeError<Infallible>>
+where
+    H: MultihashHasher,
+    T: Serialize + ?Sized,
+{
+    let mut serializer = Serializer::new(HashWriter::<H, _>::new(Sink));
+    value.serialize(&mut serializer)?;
+    let (_, hash) = serializer
+        .into_inner()
+        .finalize()
+        .map_err(|error| EncodeError::Msg(error.to_string()))?;
+    Ok(Cid::new_v1(DAG_CBOR_CODEC, hash))
+}
+
+/// Serializes a value to a vector and returns it together with its CIDv1.
+///
+/// The data is hashed while it is serialized.
+#[cfg(feature = "hash")]
+pub fn to_vec_with_cid<H, T>(value: &T) -> Result<(Vec<u8>, Cid), EncodeError<TryReserveError>>
+where
+    H: MultihashHasher,
+    T: Serialize + ?Sized,
+{
+    let writer = HashWriter::<H, _>::new(BufWriter::new(Vec::new()));
+    let mut serializer = Serializer::new(writer);
+    value.serialize(&mut serializer)?;
+    let (writer, hash) = serializer
+        .into_inner()
+        .finalize()
+        .map_err(|error| EncodeError::Msg(error.to_string()))?;
+    Ok((writer.into_inner(), Cid::new_v1(DAG_CBOR_CODEC, hash)))
+}
+
 /// A structure for serializing Rust values to DAG-CBOR.
 struct Serializer<W> {
     writer: W,
+    options: EncodeOptions,
 }
 
 impl<W> Serializer<W> {
     /// Creates a new CBOR serializer.
+    #[cfg(feature = "hash")]
     pub fn new(writer: W) -> Serializer<W> {
-        Serializer { writer }
+        Self::with_options(writer, EncodeOptions::default())
+    }
+
+    /// Creates a new CBOR serializer with the given options.
+    pub fn with_options(writer: W, options: EncodeOptions) -> Serializer<W> {
+        Serializer { writer, options }
     }
 
     /// Returns the underlying writer.
@@ -58,6 +281,87 @@ impl<W> Serializer<W> {
     }
 }
 
+impl<W: enc::Write> Serializer<W> {
+    /// Writes the start of a tuple or struct variant, up to its value.
+    fn variant_start(&mut self, variant: &'static str) -> Result<(), EncodeError<W::Error>> {
+        match self.options.enum_representation {
+            EnumRepresentation::External | EnumRepresentation::IndexedUnit => {
+                enc::MapStartBounded(1).encode(&mut self.writer)?;
+            }
+            EnumRepresentation::List => {
+                enc::ArrayStartBounded(2).encode(&mut self.writer)?;
+            }
+            EnumRepresentation::InternallyTagged(_) => {
+                return Err(EncodeError::Msg(
+                    "Tuple variants cannot be internally tagged.".into(),
+                ))
+            }
+        }
+        variant.encode(&mut self.writer)?;
+        Ok(())
+    }
+}
+
+/// Returns the number of entries of an encoded map (`None` if it has indefinite length) and the
+/// length of its header, or `None` if it isn't a map.
+fn map_header(bytes: &[u8]) -> Option<(Option<usize>, usize)> {
+    let first = *bytes.first()?;
+    if first >> 5 != 5 {
+        return None;
+    }
+    let size = match first & 0x1f {
+        info @ 0..=23 => return Some((Some(usize::from(info)), 1)),
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        31 => return Some((None, 1)),
+        _ => return None,
+    };
+    let len = bytes
+        .get(1..1 + size)?
+        .iter()
+        .fold(0u64, |len, byte| len << 8 | u64::from(*byte));
+    Some((Some(usize::try_from(len).ok()?), 1 + size))
+}
+
+/// Writes the encoded entries of an internally tagged variant as a map. The tag is sorted together
+/// with the other keys.
+fn write_tagged<W: enc::Write>(
+    writer: &mut W,
+    (tag, variant): (&str, &str),
+    bytes: &[u8],
+    mut entries: Vec<(&str, Range<usize>)>,
+) -> Result<(), EncodeError<W::Error>> {
+    if entries.iter().any(|(key, _)| *key == tag) {
+        return Err(EncodeError::Msg(
+            "Internally tagged variants cannot contain a field with the name of the tag.".into(),
+        ));
+    }
+    entries.sort_by(|(a, _), (b, _)| canonical_order(a, b));
+    let position = entries.partition_point(|(key, _)| canonical_order(key, tag) == Ordering::Less);
+    enc::MapStartBounded(entries.len() + 1).encode(writer)?;
+    for (_, range) in &entries[..position] {
+        writer.push(&bytes[range.clone()])?;
+    }
+    tag.encode(&mut *writer)?;
+    variant.encode(&mut *writer)?;
+    for (_, range) in &entries[position..] {
+        writer.push(&bytes[range.clone()])?;
+    }
+    Ok(())
+}
+
+/// Converts an error of encoding into an in-memory buffer into an error of the actual writer.
+///
+/// Custom errors are passed through, only allocation failures of the buffer become a message.
+fn buffer_error<E>(error: EncodeError<TryReserveError>) -> EncodeError<E> {
+    match error {
+        EncodeError::Msg(msg) => EncodeError::Msg(msg),
+        EncodeError::Write(error) => EncodeError::Msg(error.to_string()),
+    }
+}
+
 impl<'a, W: enc::Write> serde::Serializer for &'a mut Serializer<W> {
     type Ok = ();
     type Error = EncodeError<W::Error>;
@@ -67,8 +371,8 @@ impl<'a, W: enc::Write> serde::Serializer for &'a mut Serializer<W> {
     type SerializeTupleStruct = BoundedCollect<'a, W>;
     type SerializeTupleVariant = BoundedCollect<'a, W>;
     type SerializeMap = Collect<'a, W>;
-    type SerializeStruct = BoundedCollect<'a, W>;
-    type SerializeStructVariant = BoundedCollect<'a, W>;
+    type SerializeStruct = StructCollect<'a, W>;
+    type SerializeStructVariant = StructCollect<'a, W>;
 
     #[inline]
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
@@ -189,10 +493,18 @@ impl<'a, W: enc::Write> serde::Serializer for &'a mut Serializer<W> {
     fn serialize_unit_variant(
         self,
         _name: &'static str,
-        _variant_index: u32,
+        variant_index: u32,
         variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        self.serialize_str(variant)
+        match self.options.enum_representation {
+            EnumRepresentation::External | EnumRepresentation::List => self.serialize_str(variant),
+            EnumRepresentation::IndexedUnit => self.serialize_u32(variant_index),
+            EnumRepresentation::InternallyTagged(tag) => {
+                enc::MapStartBounded(1).encode(&mut self.writer)?;
+                tag.encode(&mut self.writer)?;
+                self.serialize_str(variant)
+            }
+        }
     }
 
     #[inline]
@@ -216,7 +528,40 @@ impl<'a, W: enc::Write> serde::Serializer for &'a mut Serializer<W> {
         variant: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error> {
-        enc::MapStartBounded(1).encode(&mut self.writer)?;
+        match self.options.enum_representation {
+            EnumRepresentation::External | EnumRepresentation::IndexedUnit => {
+                enc::MapStartBounded(1).encode(&mut self.writer)?;
+            }
+            EnumRepresentation::List => {
+                enc::ArrayStartBounded(2).encode(&mut self.writer)?;
+            }
+            EnumRepresentation::InternallyTagged(tag) => {
+                // The tag is added to the map the value is encoded as, hence it's buffered.
+                let mut buffer = BufWriter::new(Vec::new());
+                value
+                    .serialize(&mut Serializer::with_options(&mut buffer, self.options))
+                    .map_err(buffer_error)?;
+                let bytes = buffer.buffer();
+                let not_a_map = || {
+                    EncodeError::Msg(
+                        "Internally tagged newtype variants must contain a map or struct.".into(),
+                    )
+                };
+                let (_, header_len) = map_header(bytes).ok_or_else(not_a_map)?;
+                let mut start = header_len;
+                let mut entries = Vec::new();
+                for (key, value) in Node::new_unchecked(bytes)
+                    .entries()
+                    .map_err(|_| not_a_map())?
+                {
+                    let end = value.data().as_ptr() as usize - bytes.as_ptr() as usize
+                        + value.data().len();
+                    entries.push((key, start..end));
+                    start = end;
+                }
+                return write_tagged(&mut self.writer, (tag, variant), bytes, entries);
+            }
+        }
         variant.encode(&mut self.writer)?;
         value.serialize(self)
     }
@@ -257,10 +602,8 @@ impl<'a, W: enc::Write> serde::Serializer for &'a mut Serializer<W> {
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        enc::MapStartBounded(1).encode(&mut self.writer)?;
-        variant.encode(&mut self.writer)?;
-        enc::ArrayStartBounded(len).encode(&mut self.writer)?;
-        Ok(BoundedCollect { ser: self })
+        self.variant_start(variant)?;
+        self.serialize_tuple(len)
     }
 
     #[inline]
@@ -279,11 +622,10 @@ impl<'a, W: enc::Write> serde::Serializer for &'a mut Serializer<W> {
     #[inline]
     fn serialize_struct(
         self,
-        _name: &'static str,
+        name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        enc::MapStartBounded(len).encode(&mut self.writer)?;
-        Ok(BoundedCollect { ser: self })
+        StructCollect::start(self, name, len, None)
     }
 
     #[inline]
@@ -294,10 +636,12 @@ impl<'a, W: enc::Write> serde::Serializer for &'a mut Serializer<W> {
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        enc::MapStartBounded(1).encode(&mut self.writer)?;
-        variant.encode(&mut self.writer)?;
-        enc::MapStartBounded(len).encode(&mut self.writer)?;
-        Ok(BoundedCollect { ser: self })
+        if let EnumRepresentation::InternallyTagged(tag) = self.options.enum_representation {
+            // The fields are always written with their keys, together with the tag.
+            return StructCollect::start(self, variant, len, Some((tag, variant)));
+        }
+        self.variant_start(variant)?;
+        self.serialize_struct(variant, len)
     }
 
     #[inline]
@@ -321,26 +665,50 @@ impl<'a, W: enc::Write> serde::Serializer for &'a mut Serializer<W> {
         // CBOR RFC-7049 specifies a canonical sort order, where keys are sorted by length first.
         // This was later revised with RFC-8949, but we need to stick to the original order to stay
         // compatible with existing data.
-        // We first serialize each map entry into a buffer and then sort those buffers. Byte-wise
-        // comparison gives us the right order as keys in DAG-CBOR are always strings and prefixed
-        // with the length. Once sorted they are written to the actual output.
+        // All map entries are serialized into a single buffer, then their positions are sorted.
+        // Byte-wise comparison of the keys gives us the right order as keys in DAG-CBOR are always
+        // strings and prefixed with the length. Once sorted they are written to the actual output.
         let mut buffer = BufWriter::new(Vec::new());
-        let mut entries = Vec::new();
-        for (key, value) in iter {
-            let mut mem_serializer = Serializer::new(&mut buffer);
-            key.serialize(&mut mem_serializer)
+        // The positions of the keys and the whole entries within the buffer.
+        let mut entries: Vec<(Range<usize>, Range<usize>)> = Vec::new();
+        // Whether the entries are already in canonical order, e.g. from a `BTreeMap` with keys of
+        // the same length.
+        let mut sorted = true;
+        let mut nested = Serializer::with_options(&mut buffer, self.options);
+        let result: Result<(), Self::Error> = iter.into_iter().try_for_each(|(key, value)| {
+            let start = nested.writer.buffer().len();
+            key.serialize(&mut nested)
                 .map_err(|_| EncodeError::Msg("Map key cannot be serialized.".into()))?;
+            let key_end = nested.writer.buffer().len();
             value
-                .serialize(&mut mem_serializer)
-                .map_err(|_| EncodeError::Msg("Map key cannot be serialized.".into()))?;
-            entries.push(buffer.buffer().to_vec());
-            buffer.clear();
+                .serialize(&mut nested)
+                .map_err(|_| EncodeError::Msg("Map value cannot be serialized.".into()))?;
+            let end = nested.writer.buffer().len();
+
+            if let Some((previous, _)) = entries.last() {
+                let bytes = nested.writer.buffer();
+                sorted = sorted && bytes[previous.clone()] < bytes[start..key_end];
+            }
+            entries.push((start..key_end, start..end));
+            Ok(())
+        });
+        result?;
+
+        let bytes = buffer.buffer();
+        if !sorted {
+            entries.sort_unstable_by(|(a_key, a_entry), (b_key, b_entry)| {
+                bytes[a_key.clone()]
+                    .cmp(&bytes[b_key.clone()])
+                    .then_with(|| bytes[a_entry.clone()].cmp(&bytes[b_entry.clone()]))
+            });
         }
-
         enc::MapStartBounded(entries.len()).encode(&mut self.writer)?;
-        entries.sort_unstable();
-        for entry in entries {
-            self.writer.push(&entry)?;
+        if sorted {
+            self.writer.push(bytes)?;
+        } else {
+            for (_, entry) in entries {
+                self.writer.push(&bytes[entry])?;
+            }
         }
 
         Ok(())
@@ -361,6 +729,318 @@ struct BoundedCollect<'a, W> {
     ser: &'a mut Serializer<W>,
 }
 
+/// The canonical order of the fields of a struct. It's looked up by the struct name and applies to
+/// structs with the same keys.
+struct FieldOrder {
+    name: &'static str,
+    /// The keys of the fields in the order they are serialized or skipped.
+    keys: Vec<&'static str>,
+    /// The position of each field in canonical order.
+    slots: Vec<usize>,
+}
+
+/// The number of struct field orders that can be cached.
+const FIELD_ORDERS_LEN: usize = 1024;
+/// The number of cache slots a field order is looked up in.
+const FIELD_ORDERS_PROBES: usize = 8;
+
+/// The field orders of the structs serialized so far, shared by all serializers. Entries are
+/// written once and never removed, so that they can be read without locking. Structs that find no
+/// free slot are sorted each time.
+static FIELD_ORDERS: [AtomicPtr<FieldOrder>; FIELD_ORDERS_LEN] = {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: AtomicPtr<FieldOrder> = AtomicPtr::new(ptr::null_mut());
+    [EMPTY; FIELD_ORDERS_LEN]
+};
+
+/// Returns the cache slots for the field order of a struct with the given name.
+fn field_order_slots(name: &'static str) -> impl Iterator<Item = &'static AtomicPtr<FieldOrder>> {
+    // Struct names are static, so the address identifies the struct, apart from structs that
+    // share a name.
+    let hash = (name.as_ptr() as usize).wrapping_mul(0x9e37_79b9) >> 4;
+    (0..FIELD_ORDERS_PROBES).map(move |probe| &FIELD_ORDERS[(hash + probe) % FIELD_ORDERS_LEN])
+}
+
+impl FieldOrder {
+    /// Returns the cached field order of the struct with the given name.
+    fn get(name: &'static str) -> Option<&'static FieldOrder> {
+        for slot in field_order_slots(name) {
+            let order = slot.load(AtomicOrdering::Acquire);
+            if order.is_null() {
+                return None;
+            }
+            // SAFETY: Non-null entries point to leaked field orders that are never freed.
+            let order = unsafe { &*order };
+            if ptr::eq(order.name, name) {
+                return Some(order);
+            }
+        }
+        None
+    }
+
+    /// Caches the field order, unless another one for the same struct name is cached already.
+    fn insert(self) {
+        let name = self.name;
+        let order = Box::into_raw(Box::new(self));
+        for slot in field_order_slots(name) {
+            match slot.compare_exchange(
+                ptr::null_mut(),
+                order,
+                AtomicOrdering::AcqRel,
+                AtomicOrdering::Acquire,
+            ) {
+                Ok(_) => return,
+                // SAFETY: Non-null entries point to leaked field orders that are never freed.
+                Err(other) if ptr::eq(unsafe { &*other }.name, name) => break,
+                Err(_) => {}
+            }
+        }
+        // SAFETY: The field order was not inserted into the cache, so it's still owned here.
+        drop(unsafe { Box::from_raw(order) });
+    }
+}
+
+/// Sorts map keys like DAG-CBOR, shorter keys first, then bytewise.
+fn canonical_order(a: &str, b: &str) -> Ordering {
+    (a.len(), a).cmp(&(b.len(), b))
+}
+
+struct StructCollect<'a, W> {
+    ser: &'a mut Serializer<W>,
+    name: &'static str,
+    state: StructState,
+}
+
+enum StructState {
+    /// The field values are written as list.
+    Tuple,
+    /// The fields are written as they come.
+    Direct,
+    /// The fields are buffered, so that they can be sorted or omitted.
+    Buffered(BufferedFields),
+}
+
+/// Struct fields that are buffered to sort them and to omit null fields.
+struct BufferedFields {
+    /// The tag of an internally tagged variant, which is sorted together with the fields.
+    tag: Option<(&'static str, &'static str)>,
+    /// The encoded keys and values.
+    buffer: BufWriter,
+    /// The number of fields serialized or skipped so far.
+    len: usize,
+    layout: FieldsLayout,
+}
+
+/// Where the entries of buffered fields are recorded. The entries are the ranges within the
+/// buffer, `None` if the field is omitted.
+enum FieldsLayout {
+    /// The keys with their entry in the order they are serialized. The field order is cached once
+    /// they are sorted if it isn't cached yet.
+    Listed {
+        fields: Vec<(&'static str, Option<Range<usize>>)>,
+        uncached: bool,
+    },
+    /// The entries at their position in the cached canonical field order.
+    Slotted {
+        order: &'static FieldOrder,
+        entries: Vec<Option<Range<usize>>>,
+    },
+}
+
+impl<'a, W: enc::Write> StructCollect<'a, W> {
+    fn start(
+        ser: &'a mut Serializer<W>,
+        name: &'static str,
+        len: usize,
+        tag: Option<(&'static str, &'static str)>,
+    ) -> Result<Self, EncodeError<W::Error>> {
+        let options = ser.options;
+        let state = if options.struct_tuples && tag.is_none() {
+            enc::ArrayStartBounded(len).encode(&mut ser.writer)?;
+            StructState::Tuple
+        } else if tag.is_none() && !options.omit_null_fields && !options.canonical_struct_fields {
+            enc::MapStartBounded(len).encode(&mut ser.writer)?;
+            StructState::Direct
+        } else {
+            let cached = if tag.is_none() && options.canonical_struct_fields {
+                FieldOrder::get(name)
+            } else {
+                None
+            };
+            let layout = match cached {
+                Some(order) => FieldsLayout::Slotted {
+                    order,
+                    entries: vec![None; order.keys.len()],
+                },
+                None => FieldsLayout::Listed {
+                    fields: Vec::with_capacity(len),
+                    uncached: tag.is_none() && options.canonical_struct_fields,
+                },
+            };
+            StructState::Buffered(BufferedFields {
+                tag,
+                // Room for small values, so that the buffer rarely grows.
+                buffer: BufWriter::new(Vec::with_capacity(len * 16)),
+                len: 0,
+                layout,
+            })
+        };
+        Ok(StructCollect { ser, name, state })
+    }
+
+    fn field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError<W::Error>> {
+        let fields = match &mut self.state {
+            StructState::Tuple => return value.serialize(&mut *self.ser),
+            StructState::Direct => {
+                key.serialize(&mut *self.ser)?;
+                return value.serialize(&mut *self.ser);
+            }
+            StructState::Buffered(fields) => fields,
+        };
+
+        let start = fields.buffer.buffer().len();
+        key.encode(&mut fields.buffer)
+            .map_err(|error| buffer_error(error.into()))?;
+        let value_start = fields.buffer.buffer().len();
+        value
+            .serialize(&mut Serializer::with_options(
+                &mut fields.buffer,
+                self.ser.options,
+            ))
+            .map_err(buffer_error)?;
+
+        let end = fields.buffer.buffer().len();
+        let is_null = fields.buffer.buffer()[value_start..] == [marker::NULL];
+        let range = if is_null && self.ser.options.omit_null_fields {
+            None
+        } else {
+            Some(start..end)
+        };
+        fields.record(key, range);
+        Ok(())
+    }
+
+    fn skip(&mut self, key: &'static str) {
+        // Skipped fields are recorded, so that the keys are the same for all values of a struct.
+        if let StructState::Buffered(fields) = &mut self.state {
+            fields.record(key, None);
+        }
+    }
+
+    fn end_fields(self) -> Result<(), EncodeError<W::Error>> {
+        let StructCollect { ser, name, state } = self;
+        let mut fields = match state {
+            StructState::Buffered(fields) => fields,
+            _ => return Ok(()),
+        };
+        if let FieldsLayout::Slotted { order, .. } = &fields.layout {
+            if fields.len != order.keys.len() {
+                // Another struct with the same name, which has more fields.
+                fields.unslot();
+            }
+        }
+
+        let BufferedFields {
+            tag,
+            buffer,
+            layout,
+            ..
+        } = fields;
+        let buffer = buffer.buffer();
+        let (mut listed, uncached) = match layout {
+            FieldsLayout::Slotted { entries, .. } => {
+                return write_entries(&mut ser.writer, buffer, entries);
+            }
+            FieldsLayout::Listed { fields, uncached } => (fields, uncached),
+        };
+        if let Some(tag) = tag {
+            let entries = listed
+                .into_iter()
+                .filter_map(|(key, range)| Some((key, range?)))
+                .collect();
+            return write_tagged(&mut ser.writer, tag, buffer, entries);
+        }
+        if !ser.options.canonical_struct_fields {
+            let entries = listed.into_iter().map(|(_, range)| range).collect();
+            return write_entries(&mut ser.writer, buffer, entries);
+        }
+
+        let mut canonical: Vec<_> = (0..listed.len()).collect();
+        canonical.sort_by(|a, b| canonical_order(listed[*a].0, listed[*b].0));
+        let entries = canonical
+            .iter()
+            .map(|index| listed[*index].1.take())
+            .collect();
+        if uncached {
+            let mut slots = vec![0; listed.len()];
+            for (slot, index) in canonical.into_iter().enumerate() {
+                slots[index] = slot;
+            }
+            FieldOrder {
+                name,
+                keys: listed.into_iter().map(|(key, _)| key).collect(),
+                slots,
+            }
+            .insert();
+        }
+        write_entries(&mut ser.writer, buffer, entries)
+    }
+}
+
+/// Writes the encoded struct fields as map, the ones that are `None` are omitted.
+fn write_entries<W: enc::Write>(
+    writer: &mut W,
+    bytes: &[u8],
+    entries: Vec<Option<Range<usize>>>,
+) -> Result<(), EncodeError<W::Error>> {
+    let len = entries.iter().filter(|range| range.is_some()).count();
+    enc::MapStartBounded(len).encode(&mut *writer)?;
+    for range in entries.into_iter().flatten() {
+        writer.push(&bytes[range])?;
+    }
+    Ok(())
+}
+
+impl BufferedFields {
+    /// Records the entry of the next field.
+    fn record(&mut self, key: &'static str, range: Option<Range<usize>>) {
+        if let FieldsLayout::Slotted { order, entries } = &mut self.layout {
+            match order.keys.get(self.len) {
+                Some(expected) if ptr::eq(*expected, key) => {
+                    entries[order.slots[self.len]] = range;
+                    self.len += 1;
+                    return;
+                }
+                // Another struct with the same name, the fields are sorted then.
+                _ => self.unslot(),
+            }
+        }
+        if let FieldsLayout::Listed { fields, .. } = &mut self.layout {
+            fields.push((key, range));
+        }
+        self.len += 1;
+    }
+
+    /// Lists the fields recorded at their cached position so far.
+    fn unslot(&mut self) {
+        if let FieldsLayout::Slotted { order, entries } = &mut self.layout {
+            let fields = order.keys[..self.len]
+                .iter()
+                .zip(&order.slots)
+                .map(|(key, slot)| (*key, entries[*slot].take()))
+                .collect();
+            self.layout = FieldsLayout::Listed {
+                fields,
+                uncached: false,
+            };
+        }
+    }
+}
+
 impl<W: enc::Write> serde::ser::SerializeSeq for Collect<'_, W> {
     type Ok = ();
     type Error = EncodeError<W::Error>;
@@ -449,7 +1129,7 @@ impl<W: enc::Write> serde::ser::SerializeMap for Collect<'_, W> {
     }
 }
 
-impl<W: enc::Write> serde::ser::SerializeStruct for BoundedCollect<'_, W> {
+impl<W: enc::Write> serde::ser::SerializeStruct for StructCollect<'_, W> {
     type Ok = ();
     type Error = EncodeError<W::Error>;
 
@@ -459,17 +1139,22 @@ impl<W: enc::Write> serde::ser::SerializeStruct for BoundedCollect<'_, W> {
         key: &'static str,
         value: &T,
     ) -> Result<(), Self::Error> {
-        key.serialize(&mut *self.ser)?;
-        value.serialize(&mut *self.ser)
+        self.field(key, value)
     }
 
     #[inline]
-    fn end(self) -> Result<Self::Ok, Self::Error> {
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.skip(key);
         Ok(())
     }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.end_fields()
+    }
 }
 
-impl<W: enc::Write> serde::ser::SerializeStructVariant for BoundedCollect<'_, W> {
+impl<W: enc::Write> serde::ser::SerializeStructVariant for StructCollect<'_, W> {
     type Ok = ();
     type Error = EncodeError<W::Error>;
 
@@ -479,14 +1164,19 @@ impl<W: enc::Write> serde::ser::SerializeStructVariant for BoundedCollect<'_, W>
         key: &'static str,
         value: &T,
     ) -> Result<(), Self::Error> {
-        key.serialize(&mut *self.ser)?;
-        value.serialize(&mut *self.ser)
+        self.field(key, value)
     }
 
     #[inline]
-    fn end(self) -> Result<Self::Ok, Self::Error> {
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.skip(key);
         Ok(())
     }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.end_fields()
+    }
 }
 
 /// Serializing a CID correctly as DAG-CBOR.
@@ -548,10 +1238,7 @@ where
     }
 
     fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok, Self::Error> {
-        // The bytes of the CID is prefixed with a null byte when encoded as CBOR.
-        let prefixed = [&[0x00], value].concat();
-        // CIDs are serialized with CBOR tag 42.
-        types::Tag(CBOR_TAGS_CID, types::Bytes(&prefixed[..])).encode(&mut self.0.writer)?;
+        encode_cid_bytes(&mut self.0.writer, value)?;
         Ok(())
     }
 
@@ -0,0 +1,356 @@
+//! Transcoding between DAG-CBOR and DAG-JSON.
+//!
+//! The data is converted token by token from the input to the writer, without deserializing it
+//! into an intermediate value and without buffering encoded values. The two codecs sort map keys
+//! differently and DAG-CBOR needs the length of lists and maps upfront. Hence the entries of a map
+//! are scanned first, keeping only their keys and the positions of their values within the input.
+//! The values are then transcoded directly from the input in the order of the output codec.
+//! DAG-JSON lists are counted the same way before their elements are transcoded.
+//!
+//! The memory used is bounded by the number of entries of the maps that are currently open,
+//! independent of the size of the values. As nested maps and lists are scanned once more for
+//! every level they are nested in, the run time grows with the nesting depth.
+use std::borrow::Cow;
+use std::cmp;
+use std::convert::{Infallible, TryFrom};
+use std::io;
+
+use cbor4ii::core::dec::{self, Decode, Read};
+use cbor4ii::core::enc::{self, Encode};
+use cbor4ii::core::utils::IoWriter;
+use cbor4ii::core::{major, types};
+use cid::Cid;
+use serde::{Serialize, Serializer as _};
+
+use crate::block::has_next;
+use crate::cbor4ii_nonpub::{marker, peek_one};
+use crate::error::{DecodeError, EncodeError, TranscodeError};
+use crate::json::de::{Deserializer as JsonDeserializer, Number, Object, Str};
+use crate::json::ser::{check_sorted_keys, Serializer as JsonSerializer};
+use crate::ser::encode_cid_bytes;
+use crate::CBOR_TAGS_CID;
+
+/// The maximum nesting depth of the input, like the one of the readers of cbor4ii.
+const DEPTH_LIMIT: usize = 256;
+
+/// Reads a single DAG-CBOR value and writes it as DAG-JSON.
+///
+/// # Examples
+///
+/// ```
+/// use serde_ipld_dagcbor::transcode::transcode_to_dag_json;
+///
+/// let mut json = Vec::new();
+/// transcode_to_dag_json(b"\xa2\x61\x62\x01\x62\x61\x61\x42\x01\x02", &mut json).unwrap();
+/// assert_eq!(json, br#"{"aa":{"/":{"bytes":"AQI"}},"b":1}"#);
+/// ```
+pub fn transcode_to_dag_json<W>(
+    input: &[u8],
+    writer: W,
+) -> Result<(), TranscodeError<Infallible, io::Error>>
+where
+    W: io::Write,
+{
+    let mut reader = Cursor::new(input);
+    let mut serializer = JsonSerializer::new(IoWriter::new(writer));
+    cbor_to_json(&mut reader, &mut serializer)?;
+    match peek_one(&mut reader) {
+        Ok(_) => Err(DecodeError::TrailingData.into()),
+        Err(DecodeError::Eof) => Ok(()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Reads a single DAG-JSON value and writes it as DAG-CBOR.
+///
+/// # Examples
+///
+/// ```
+/// use serde_ipld_dagcbor::transcode::transcode_from_dag_json;
+///
+/// let mut cbor = Vec::new();
+/// transcode_from_dag_json(br#"{"b": 1, "aa": {"/": {"bytes": "AQI"}}}"#, &mut cbor).unwrap();
+/// assert_eq!(cbor, b"\xa2\x61\x62\x01\x62\x61\x61\x42\x01\x02");
+/// ```
+pub fn transcode_from_dag_json<W>(
+    input: &[u8],
+    writer: W,
+) -> Result<(), TranscodeError<Infallible, io::Error>>
+where
+    W: io::Write,
+{
+    let mut deserializer = JsonDeserializer::from_reader(Cursor::new(input));
+    json_to_cbor(&mut deserializer, &mut IoWriter::new(writer))?;
+    deserializer.end()?;
+    Ok(())
+}
+
+/// A reader of the input, which can be copied to read a value at the same position again later.
+#[derive(Clone, Copy)]
+struct Cursor<'a> {
+    input: &'a [u8],
+    pos: usize,
+    /// The remaining nesting depth.
+    limit: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Cursor {
+            input,
+            pos: 0,
+            limit: DEPTH_LIMIT,
+        }
+    }
+}
+
+impl<'a> Read<'a> for Cursor<'a> {
+    type Error = Infallible;
+
+    #[inline]
+    fn fill<'b>(&'b mut self, want: usize) -> Result<dec::Reference<'a, 'b>, Self::Error> {
+        let input: &'a [u8] = self.input;
+        let buf = &input[self.pos..];
+        let len = cmp::min(buf.len(), want);
+        Ok(dec::Reference::Long(&buf[..len]))
+    }
+
+    #[inline]
+    fn advance(&mut self, n: usize) {
+        self.pos = cmp::min(self.input.len(), self.pos + n);
+    }
+
+    #[inline]
+    fn step_in(&mut self) -> bool {
+        match self.limit.checked_sub(1) {
+            Some(limit) => {
+                self.limit = limit;
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[inline]
+    fn step_out(&mut self) {
+        self.limit += 1;
+    }
+}
+
+/// Makes sure the input isn't nested deeper than the reader allows.
+fn step_in<'de, R: dec::Read<'de>, W>(reader: &mut R) -> Result<(), TranscodeError<R::Error, W>> {
+    if reader.step_in() {
+        Ok(())
+    } else {
+        Err(DecodeError::DepthLimit.into())
+    }
+}
+
+fn cbor_to_json<W: enc::Write>(
+    reader: &mut Cursor<'_>,
+    serializer: &mut JsonSerializer<W>,
+) -> Result<(), TranscodeError<Infallible, W::Error>> {
+    let byte = peek_one(reader)?;
+    match dec::if_major(byte) {
+        major::UNSIGNED => serializer.serialize_u64(u64::decode(reader)?)?,
+        major::NEGATIVE => serializer.serialize_i128(i128::decode(reader)?)?,
+        major::BYTES => {
+            serializer.serialize_bytes(&<types::Bytes<Cow<[u8]>>>::decode(reader)?.0)?
+        }
+        major::STRING => serializer.serialize_str(&<Cow<str>>::decode(reader)?)?,
+        major::ARRAY => {
+            step_in(reader)?;
+            let len = dec::ArrayStart::decode(reader)?.0;
+            serializer.write(b"[")?;
+            let mut count = 0;
+            while has_next(reader, len, count)? {
+                if count > 0 {
+                    serializer.write(b",")?;
+                }
+                cbor_to_json(reader, serializer)?;
+                count += 1;
+            }
+            serializer.write(b"]")?;
+            reader.step_out();
+        }
+        major::MAP => {
+            step_in(reader)?;
+            let len = dec::MapStart::decode(reader)?.0;
+            // The keys are borrowed from the input, the values are skipped and transcoded once
+            // the order of the keys is known.
+            let mut entries = Vec::new();
+            while has_next(reader, len, entries.len())? {
+                let key = <&str>::decode(reader)?;
+                entries.push((key, *reader));
+                dec::IgnoredAny::decode(reader)?;
+            }
+            // DAG-JSON sorts the keys by their UTF-8 bytes, which is the order of Rust strings.
+            entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            check_sorted_keys(entries.iter().map(|(key, _)| *key))?;
+            serializer.write(b"{")?;
+            for (ii, (key, mut value)) in entries.into_iter().enumerate() {
+                serializer.write_key(ii == 0, key)?;
+                cbor_to_json(&mut value, serializer)?;
+            }
+            serializer.write(b"}")?;
+            reader.step_out();
+        }
+        major::TAG => match dec::TagStart::decode(reader)?.0 {
+            CBOR_TAGS_CID => {
+                let bytes = <types::Bytes<Cow<[u8]>>>::decode(reader)?.0;
+                // CBOR encoded CIDs have a zero byte prefix we have to remove.
+                let cid = match bytes.split_first() {
+                    Some((0, cid)) => Cid::try_from(cid).ok(),
+                    _ => None,
+                }
+                .ok_or_else(|| DecodeError::Msg("Invalid CID".into()))?;
+                cid.serialize(&mut *serializer)?;
+            }
+            tag => return Err(DecodeError::Msg(format!("Unsupported CBOR tag {}", tag)).into()),
+        },
+        major::SIMPLE => match byte {
+            marker::FALSE | marker::TRUE => serializer.serialize_bool(bool::decode(reader)?)?,
+            marker::NULL => {
+                reader.advance(1);
+                serializer.serialize_none()?
+            }
+            marker::F32 => serializer.serialize_f32(f32::decode(reader)?)?,
+            marker::F64 => serializer.serialize_f64(f64::decode(reader)?)?,
+            _ => return Err(DecodeError::Unsupported { byte }.into()),
+        },
+        _ => return Err(DecodeError::Unsupported { byte }.into()),
+    }
+    Ok(())
+}
+
+fn json_to_cbor<W: enc::Write>(
+    deserializer: &mut JsonDeserializer<Cursor<'_>>,
+    writer: &mut W,
+) -> Result<(), TranscodeError<Infallible, W::Error>> {
+    match deserializer.peek()? {
+        b'n' => {
+            deserializer.expect_literal(b"null")?;
+            types::Null.encode(writer)?;
+        }
+        b't' => {
+            deserializer.expect_literal(b"true")?;
+            true.encode(writer)?;
+        }
+        b'f' => {
+            deserializer.expect_literal(b"false")?;
+            false.encode(writer)?;
+        }
+        b'"' => match deserializer.parse_str()? {
+            Str::Borrowed(string) => string.encode(writer)?,
+            Str::Owned(string) => string.as_str().encode(writer)?,
+        },
+        b'[' => {
+            step_in(&mut deserializer.reader)?;
+            deserializer.expect(b'[')?;
+            // DAG-CBOR lists must have a definite length, hence the elements are counted first.
+            let start = deserializer.reader;
+            let mut count = 0;
+            while deserializer.has_next(b']', count == 0)? {
+                skip_json(deserializer)?;
+                count += 1;
+            }
+            enc::ArrayStartBounded(count).encode(writer)?;
+            let mut elements = JsonDeserializer::from_reader(start);
+            for ii in 0..count {
+                elements.has_next(b']', ii == 0)?;
+                json_to_cbor(&mut elements, writer)?;
+            }
+            deserializer.reader.step_out();
+        }
+        b'{' => {
+            step_in(&mut deserializer.reader)?;
+            match deserializer.parse_object()? {
+                Object::Link(cid) => {
+                    encode_cid_bytes(writer, &cid[..]).map_err(EncodeError::Write)?
+                }
+                Object::Bytes(bytes) => types::Bytes(&bytes[..]).encode(writer)?,
+                Object::Map(first_key) => {
+                    // Only the keys and the positions of the values are kept, the values are
+                    // skipped and transcoded once the order of the keys is known.
+                    let mut entries = Vec::new();
+                    let mut key = first_key;
+                    while let Some(current) = key.take() {
+                        entries.push((current, deserializer.reader));
+                        skip_json(deserializer)?;
+                        if deserializer.has_next(b'}', false)? {
+                            key = Some(deserializer.parse_str()?.as_str().into());
+                            deserializer.expect(b':')?;
+                        }
+                    }
+                    // DAG-CBOR sorts the keys by length first, then bytewise.
+                    entries.sort_unstable_by(|a, b| (a.0.len(), &a.0).cmp(&(b.0.len(), &b.0)));
+                    if entries.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+                        return Err(DecodeError::Msg("Duplicate map key".into()).into());
+                    }
+                    enc::MapStartBounded(entries.len()).encode(writer)?;
+                    for (key, value) in entries {
+                        key.as_str().encode(writer)?;
+                        json_to_cbor(&mut JsonDeserializer::from_reader(value), writer)?;
+                    }
+                }
+            }
+            deserializer.reader.step_out();
+        }
+        b'-' | b'0'..=b'9' => match deserializer.parse_number()? {
+            Number::Unsigned(value) => value.encode(writer)?,
+            Number::Signed(value) => value.encode(writer)?,
+            Number::Float(value) => value.encode(writer)?,
+            // Negative numbers down to -2^64 can be encoded in DAG-CBOR.
+            Number::BigSigned(value) if value >= -(1 << 64) => value.encode(writer)?,
+            Number::BigUnsigned(_) | Number::BigSigned(_) => {
+                return Err(DecodeError::Overflow { name: "integer" }.into())
+            }
+        },
+        byte => return Err(DecodeError::Unsupported { byte }.into()),
+    }
+    Ok(())
+}
+
+/// Skips a DAG-JSON value, checking that it's valid.
+fn skip_json(
+    deserializer: &mut JsonDeserializer<Cursor<'_>>,
+) -> Result<(), DecodeError<Infallible>> {
+    match deserializer.peek()? {
+        b'n' => deserializer.expect_literal(b"null"),
+        b't' => deserializer.expect_literal(b"true"),
+        b'f' => deserializer.expect_literal(b"false"),
+        b'"' => deserializer.parse_str().map(drop),
+        b'[' => {
+            if !deserializer.reader.step_in() {
+                return Err(DecodeError::DepthLimit);
+            }
+            deserializer.expect(b'[')?;
+            let mut first = true;
+            while deserializer.has_next(b']', first)? {
+                skip_json(deserializer)?;
+                first = false;
+            }
+            deserializer.reader.step_out();
+            Ok(())
+        }
+        b'{' => {
+            if !deserializer.reader.step_in() {
+                return Err(DecodeError::DepthLimit);
+            }
+            if let Object::Map(Some(_)) = deserializer.parse_object()? {
+                loop {
+                    skip_json(deserializer)?;
+                    if !deserializer.has_next(b'}', false)? {
+                        break;
+                    }
+                    deserializer.parse_str()?;
+                    deserializer.expect(b':')?;
+                }
+            }
+            deserializer.reader.step_out();
+            Ok(())
+        }
+        b'-' | b'0'..=b'9' => deserializer.parse_number().map(drop),
+        byte => Err(DecodeError::Unsupported { byte }),
+    }
+}
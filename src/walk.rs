@@ -0,0 +1,146 @@
+//! Traversal of DAGs stored in a [`Blockstore`].
+//!
+//! The [`Walker`] starts at a root CID and follows the links (CBOR tag 42) of every DAG-CBOR
+//! block. Blocks of other codecs are yielded, but not descended into. Every block is visited only
+//! once, hence DAGs that link to the same block several times are handled efficiently.
+//!
+//! # Examples
+//!
+//! ```
+//! use serde_ipld_dagcbor::blockstore::{Blockstore, MemoryBlockstore};
+//! use serde_ipld_dagcbor::walk::{Order, Walker};
+//!
+//! let mut store = MemoryBlockstore::new();
+//! let leaf = store.put_value(&"leaf").unwrap();
+//! let root = store.put_value(&vec![leaf, leaf]).unwrap();
+//!
+//! let mut walker = Walker::new(&store, root);
+//! walker.set_order(Order::DepthFirst);
+//! let cids: Vec<_> = walker.map(|block| block.unwrap().0).collect();
+//! assert_eq!(cids, [root, leaf]);
+//! ```
+use std::collections::{HashSet, VecDeque};
+
+use cid::Cid;
+
+use crate::block::links;
+use crate::blockstore::Blockstore;
+use crate::error::BlockstoreError;
+use crate::DAG_CBOR_CODEC;
+
+/// The order in which the blocks are visited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    /// All blocks of one depth are visited before the blocks of the next depth.
+    BreadthFirst,
+    /// The links of a block are followed before its siblings are visited.
+    DepthFirst,
+}
+
+/// Walks a DAG, yielding the CID and the data of every block.
+///
+/// The root has depth 0. Blocks are yielded at the first depth they are encountered in the
+/// traversal order, with breadth-first traversal that's the shortest path from the root. If a
+/// block cannot be loaded, an error is yielded and the traversal continues with the other blocks.
+#[derive(Debug)]
+pub struct Walker<'a, S> {
+    store: &'a S,
+    /// The CIDs to visit together with their depth.
+    pending: VecDeque<(Cid, usize)>,
+    visited: HashSet<Cid>,
+    order: Order,
+    max_depth: Option<usize>,
+    codecs: Option<Vec<u64>>,
+}
+
+impl<'a, S: Blockstore> Walker<'a, S> {
+    /// Creates a walker that starts at the given root.
+    ///
+    /// By default the DAG is walked breadth-first, without a depth limit and all codecs are
+    /// visited.
+    pub fn new(store: &'a S, root: Cid) -> Self {
+        let mut pending = VecDeque::new();
+        pending.push_back((root, 0));
+        Walker {
+            store,
+            pending,
+            visited: HashSet::new(),
+            order: Order::BreadthFirst,
+            max_depth: None,
+            codecs: None,
+        }
+    }
+
+    /// Sets the traversal order.
+    pub fn set_order(&mut self, order: Order) {
+        self.order = order;
+    }
+
+    /// Sets the maximum depth, blocks that are deeper are not visited.
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    /// Only visits blocks with one of the given codecs, `None` visits all blocks.
+    ///
+    /// Blocks with other codecs are neither loaded nor yielded, hence their links are not
+    /// followed.
+    pub fn set_codecs(&mut self, codecs: Option<Vec<u64>>) {
+        self.codecs = codecs;
+    }
+
+    /// Returns the CIDs of the blocks that were visited so far.
+    pub fn visited(&self) -> &HashSet<Cid> {
+        &self.visited
+    }
+
+    fn is_wanted(&self, cid: &Cid, depth: usize) -> bool {
+        let within_depth = match self.max_depth {
+            Some(max_depth) => depth <= max_depth,
+            None => true,
+        };
+        let with_codec = match &self.codecs {
+            Some(codecs) => codecs.contains(&cid.codec()),
+            None => true,
+        };
+        within_depth && with_codec && !self.visited.contains(cid)
+    }
+
+    fn load(&mut self, cid: Cid, depth: usize) -> Result<(Cid, Vec<u8>), BlockstoreError> {
+        let data = self
+            .store
+            .get(&cid)?
+            .ok_or(BlockstoreError::NotFound(cid))?;
+        if cid.codec() == DAG_CBOR_CODEC {
+            let children: Vec<_> = links(&data)?
+                .into_iter()
+                .filter(|child| self.is_wanted(child, depth + 1))
+                .map(|child| (child, depth + 1))
+                .collect();
+            match self.order {
+                Order::BreadthFirst => self.pending.extend(children),
+                // The first link is on top of the stack, so that it's visited first.
+                Order::DepthFirst => self.pending.extend(children.into_iter().rev()),
+            }
+        }
+        Ok((cid, data))
+    }
+}
+
+impl<S: Blockstore> Iterator for Walker<'_, S> {
+    type Item = Result<(Cid, Vec<u8>), BlockstoreError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (cid, depth) = match self.order {
+                Order::BreadthFirst => self.pending.pop_front()?,
+                Order::DepthFirst => self.pending.pop_back()?,
+            };
+            // A block might have been queued several times before it was visited.
+            if self.is_wanted(&cid, depth) {
+                self.visited.insert(cid);
+                return Some(self.load(cid, depth));
+            }
+        }
+    }
+}
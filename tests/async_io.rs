@@ -0,0 +1,145 @@
+#![cfg(any(feature = "futures", feature = "tokio"))]
+
+use std::collections::BTreeMap;
+
+use futures::executor::block_on;
+use libipld_core::ipld::Ipld;
+use serde_derive::{Deserialize, Serialize};
+use serde_ipld_dagcbor::{to_vec, DecodeError};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Block {
+    name: String,
+    data: Vec<u32>,
+    meta: BTreeMap<String, Option<bool>>,
+}
+
+fn block() -> Block {
+    let mut meta = BTreeMap::new();
+    meta.insert("a".to_string(), Some(true));
+    meta.insert("b".to_string(), None);
+    Block {
+        name: "block".to_string(),
+        data: vec![1, 1000, 100000],
+        meta,
+    }
+}
+
+#[cfg(feature = "futures")]
+mod futures_io {
+    use super::*;
+    use serde_ipld_dagcbor::async_io::futures::{from_async_reader, to_async_writer};
+
+    #[test]
+    fn test_roundtrip() {
+        let value = block();
+        let mut encoded = Vec::new();
+        block_on(to_async_writer(&mut encoded, &value)).unwrap();
+        assert_eq!(encoded, to_vec(&value).unwrap());
+
+        let decoded: Block = block_on(from_async_reader(&encoded[..], 1024)).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn test_flushes_writer() {
+        let value = block();
+        let mut writer = futures::io::BufWriter::new(Vec::new());
+        block_on(to_async_writer(&mut writer, &value)).unwrap();
+        assert_eq!(writer.get_ref(), &to_vec(&value).unwrap());
+    }
+
+    #[test]
+    fn test_reads_only_one_value() {
+        let mut stream = to_vec(&"first").unwrap();
+        stream.extend(to_vec(&[1u8, 2, 3]).unwrap());
+        stream.extend(to_vec(&block()).unwrap());
+
+        let mut reader = &stream[..];
+        let first: String = block_on(from_async_reader(&mut reader, 1024)).unwrap();
+        assert_eq!(first, "first");
+        let second: Vec<u8> = block_on(from_async_reader(&mut reader, 1024)).unwrap();
+        assert_eq!(second, [1, 2, 3]);
+        let third: Block = block_on(from_async_reader(&mut reader, 1024)).unwrap();
+        assert_eq!(third, block());
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_indefinite_length() {
+        // [_ 1, {_ "a": 2}]
+        let stream = [0x9f, 0x01, 0xbf, 0x61, 0x61, 0x02, 0xff, 0xff, 0x00];
+        let mut reader = &stream[..];
+        let value: Ipld = block_on(from_async_reader(&mut reader, 1024)).unwrap();
+        let mut map = BTreeMap::new();
+        map.insert("a".to_string(), Ipld::Integer(2));
+        assert_eq!(value, Ipld::List(vec![Ipld::Integer(1), Ipld::Map(map)]));
+        assert_eq!(reader, [0x00]);
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let encoded = to_vec(&block()).unwrap();
+        let result: Result<Block, _> = block_on(from_async_reader(&encoded[..], encoded.len()));
+        assert!(result.is_ok());
+        let result: Result<Block, _> = block_on(from_async_reader(&encoded[..], encoded.len() - 1));
+        assert!(matches!(result.unwrap_err(), DecodeError::SizeLimit));
+
+        // A byte string claiming a length of 2^32 bytes.
+        let huge = [0x5b, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00];
+        let result: Result<Vec<u8>, _> = block_on(from_async_reader(&huge[..], 1024));
+        assert!(matches!(result.unwrap_err(), DecodeError::SizeLimit));
+    }
+
+    #[test]
+    fn test_truncated() {
+        let encoded = to_vec(&block()).unwrap();
+        let result: Result<Block, _> =
+            block_on(from_async_reader(&encoded[..encoded.len() - 1], 1024));
+        assert!(matches!(result.unwrap_err(), DecodeError::Eof));
+    }
+}
+
+#[cfg(feature = "tokio")]
+mod tokio_io {
+    use super::*;
+    use serde_ipld_dagcbor::async_io::tokio::{from_async_reader, to_async_writer};
+
+    #[test]
+    fn test_roundtrip() {
+        let value = block();
+        let mut encoded = Vec::new();
+        block_on(to_async_writer(&mut encoded, &value)).unwrap();
+        assert_eq!(encoded, to_vec(&value).unwrap());
+
+        let decoded: Block = block_on(from_async_reader(&encoded[..], 1024)).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn test_flushes_writer() {
+        let value = block();
+        let mut writer = tokio::io::BufWriter::new(Vec::new());
+        block_on(to_async_writer(&mut writer, &value)).unwrap();
+        assert_eq!(writer.get_ref(), &to_vec(&value).unwrap());
+    }
+
+    #[test]
+    fn test_reads_only_one_value() {
+        let mut stream = to_vec(&block()).unwrap();
+        stream.extend(to_vec(&"second").unwrap());
+
+        let mut reader = &stream[..];
+        let first: Block = block_on(from_async_reader(&mut reader, 1024)).unwrap();
+        assert_eq!(first, block());
+        let second: String = block_on(from_async_reader(&mut reader, 1024)).unwrap();
+        assert_eq!(second, "second");
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let encoded = to_vec(&block()).unwrap();
+        let result: Result<Block, _> = block_on(from_async_reader(&encoded[..], encoded.len() - 1));
+        assert!(matches!(result.unwrap_err(), DecodeError::SizeLimit));
+    }
+}
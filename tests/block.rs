@@ -0,0 +1,98 @@
+#![cfg(feature = "hash")]
+
+use cid::multihash::{Code, MultihashDigest};
+use cid::Cid;
+use serde_derive::{Deserialize, Serialize};
+use serde_ipld_dagcbor::{to_cid, to_vec, Block, VerifyError, DAG_CBOR_CODEC};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Entry {
+    name: String,
+    size: u64,
+}
+
+fn entry() -> Entry {
+    Entry {
+        name: "entry".to_string(),
+        size: 1024,
+    }
+}
+
+#[test]
+fn test_encode_decode() {
+    let block = Block::encode(&entry(), Code::Sha2_256).unwrap();
+    assert_eq!(block.data(), to_vec(&entry()).unwrap());
+    assert_eq!(
+        *block.cid(),
+        to_cid::<cid::multihash::Sha2_256, _>(&entry()).unwrap()
+    );
+    assert!(block.verify().is_ok());
+    assert_eq!(block.decode::<Entry>().unwrap(), entry());
+}
+
+#[test]
+fn test_verify_blake2b() {
+    let block = Block::encode(&entry(), Code::Blake2b256).unwrap();
+    assert_eq!(block.cid().hash().code(), 0xb220);
+    assert!(block.verify().is_ok());
+}
+
+#[test]
+fn test_verify_hash_mismatch() {
+    let block = Block::encode(&entry(), Code::Sha2_256).unwrap();
+    let (cid, mut data) = block.into_parts();
+    data.push(0x00);
+    let tampered = Block::new(cid, data);
+    assert!(matches!(tampered.verify(), Err(VerifyError::HashMismatch)));
+}
+
+#[test]
+fn test_verify_wrong_codec() {
+    let data = to_vec(&entry()).unwrap();
+    let raw_cid = Cid::new_v1(0x55, Code::Sha2_256.digest(&data));
+    let block = Block::new(raw_cid, data.clone());
+    assert!(matches!(block.verify(), Err(VerifyError::Codec(0x55))));
+
+    let cid = Cid::new_v1(DAG_CBOR_CODEC, Code::Sha2_256.digest(&data));
+    assert!(Block::new(cid, data).verify().is_ok());
+}
+
+#[test]
+fn test_verify_unsupported_hash() {
+    let data = to_vec(&entry()).unwrap();
+    // The identity hash is not supported.
+    let hash = cid::multihash::Multihash::wrap(0x00, &data).unwrap();
+    let block = Block::new(Cid::new_v1(DAG_CBOR_CODEC, hash), data);
+    assert!(matches!(
+        block.verify(),
+        Err(VerifyError::UnsupportedHash(0x00))
+    ));
+}
+
+#[test]
+fn test_links() {
+    let cid = Block::encode(&entry(), Code::Sha2_256)
+        .unwrap()
+        .cid()
+        .to_owned();
+    let other = Block::encode(&1, Code::Sha2_256).unwrap().cid().to_owned();
+    let data = serde_ipld_dagcbor::diag::from_diag(&format!(
+        r#"{{_ "a": [42({}), 1], "b": {{"c": 42({})}}}}"#,
+        cid, other
+    ))
+    .unwrap();
+    assert_eq!(
+        serde_ipld_dagcbor::block::links(&data).unwrap(),
+        [cid, other]
+    );
+
+    let block = Block::encode(&vec![Some(other), None], Code::Sha2_256).unwrap();
+    assert_eq!(block.links().unwrap(), [other]);
+
+    assert!(serde_ipld_dagcbor::block::links(b"\xd8\x2a\x41\x01").is_err());
+    assert!(serde_ipld_dagcbor::block::links(b"\xc1\x01").is_err());
+    // Tags beyond a single byte are reported in full.
+    let error = serde_ipld_dagcbor::block::links(b"\xd9\x01\x02\x01").unwrap_err();
+    assert!(error.to_string().contains("258"));
+    assert!(serde_ipld_dagcbor::block::links(b"\x80\x01").is_err());
+}
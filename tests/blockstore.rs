@@ -0,0 +1,112 @@
+#![cfg(feature = "hash")]
+
+use std::convert::TryFrom;
+
+use cid::multihash::Code;
+use cid::Cid;
+use serde_derive::{Deserialize, Serialize};
+use serde_ipld_dagcbor::blockstore::{Blockstore, FsBlockstore, MemoryBlockstore};
+use serde_ipld_dagcbor::{Block, BlockstoreError};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Entry {
+    name: String,
+    size: u64,
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("blockstore-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn check_blockstore<S: Blockstore>(store: &mut S) {
+    let block = Block::encode(&"hello", Code::Sha2_256).unwrap();
+    assert!(!store.has(block.cid()).unwrap());
+    assert_eq!(store.get(block.cid()).unwrap(), None);
+
+    store.put(block.cid(), block.data()).unwrap();
+    assert!(store.has(block.cid()).unwrap());
+    assert_eq!(store.get(block.cid()).unwrap().unwrap(), block.data());
+    // Storing the same block again is fine.
+    store.put(block.cid(), block.data()).unwrap();
+
+    store.delete(block.cid()).unwrap();
+    assert!(!store.has(block.cid()).unwrap());
+    store.delete(block.cid()).unwrap();
+
+    let entry = Entry {
+        name: "entry".to_string(),
+        size: 3,
+    };
+    let cid = store.put_value(&entry).unwrap();
+    assert_eq!(cid, *Block::encode(&entry, Code::Sha2_256).unwrap().cid());
+    assert_eq!(store.get_value::<Entry>(&cid).unwrap(), entry);
+    assert!(matches!(
+        store.get_value::<Entry>(block.cid()),
+        Err(BlockstoreError::NotFound(missing)) if missing == *block.cid()
+    ));
+    assert!(matches!(
+        store.get_value::<String>(&cid),
+        Err(BlockstoreError::Decode(_))
+    ));
+}
+
+#[test]
+fn test_memory_blockstore() {
+    let mut store = MemoryBlockstore::new();
+    check_blockstore(&mut store);
+    assert_eq!(store.len(), 1);
+}
+
+#[test]
+fn test_fs_blockstore() {
+    let dir = temp_dir("fs");
+    let mut store = FsBlockstore::new(&dir).unwrap();
+    check_blockstore(&mut store);
+
+    let cid = Cid::try_from("bafyreih4jspvxgh5xrp3wboocaajx5ym4ky7ok3gs5dsdkizgnegtanfxu").unwrap();
+    assert_eq!(
+        store.path(&cid),
+        dir.join("LP")
+            .join("CIQPYTE7LOMP3PC7XMC44EAATP3QZYVR64VWNF2HEGURSM2INGA2LPI.data")
+    );
+
+    // Blocks are found by their multihash, independent of the codec.
+    store.put(&cid, b"data").unwrap();
+    let raw = Cid::new_v1(0x55, *cid.hash());
+    assert_eq!(store.get(&raw).unwrap().unwrap(), b"data");
+
+    // The blocks are persisted.
+    let reopened = FsBlockstore::new(&dir).unwrap();
+    assert!(reopened.has(&cid).unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_fs_blockstore_concurrent_put() {
+    let dir = temp_dir("concurrent");
+    let store = FsBlockstore::new(&dir).unwrap();
+    let block = Block::encode(&"hello", Code::Sha2_256).unwrap();
+
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let mut store = store.clone();
+            let block = block.clone();
+            std::thread::spawn(move || {
+                for _ in 0..10 {
+                    store.put(block.cid(), block.data()).unwrap();
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(store.get(block.cid()).unwrap().unwrap(), block.data());
+    // No temporary files are left behind.
+    let shard = store.path(block.cid()).parent().unwrap().to_path_buf();
+    assert_eq!(std::fs::read_dir(shard).unwrap().count(), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,278 @@
+#![cfg(feature = "hash")]
+
+use std::io::{Cursor, Seek};
+
+use cid::multihash::Code;
+use cid::Cid;
+use serde_derive::{Deserialize, Serialize};
+use serde_ipld_dagcbor::car::{
+    CarHeader, CarReader, CarV2Header, CarV2Writer, CarWriter, Index, IndexCodec, IndexedCarReader,
+    PRAGMA,
+};
+use serde_ipld_dagcbor::{to_vec, Block, CarError, VerifyError};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Node {
+    name: String,
+    links: Vec<Cid>,
+}
+
+/// Generates a small DAG: a root linking to two leaves, one of them larger than 127 bytes so that
+/// its section length needs a multi-byte varint.
+fn fixture() -> Vec<Block> {
+    let small = Block::encode(&"leaf", Code::Sha2_256).unwrap();
+    let large = Block::encode(&"x".repeat(300), Code::Blake2b256).unwrap();
+    let root = Block::encode(
+        &Node {
+            name: "root".to_string(),
+            links: vec![*small.cid(), *large.cid()],
+        },
+        Code::Sha2_256,
+    )
+    .unwrap();
+    vec![root, small, large]
+}
+
+fn write_car(blocks: &[Block]) -> Vec<u8> {
+    let mut writer = CarWriter::new(Vec::new(), vec![*blocks[0].cid()]).unwrap();
+    for block in blocks {
+        writer.write(block.cid(), block.data()).unwrap();
+    }
+    writer.into_inner()
+}
+
+#[test]
+fn test_roundtrip() {
+    let blocks = fixture();
+    let car = write_car(&blocks);
+
+    let reader = CarReader::new(&car[..]).unwrap();
+    assert_eq!(reader.header(), &CarHeader::new(vec![*blocks[0].cid()]));
+    let sections = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(sections.len(), 3);
+    for ((cid, data), block) in sections.into_iter().zip(&blocks) {
+        assert_eq!(&cid, block.cid());
+        assert_eq!(data, block.data());
+    }
+
+    let root: Node = blocks[0].decode().unwrap();
+    assert_eq!(root.links, [*blocks[1].cid(), *blocks[2].cid()]);
+}
+
+#[test]
+fn test_layout() {
+    let blocks = fixture();
+    let car = write_car(&blocks[1..2]);
+
+    let header = to_vec(&CarHeader::new(vec![*blocks[1].cid()])).unwrap();
+    // {"roots": [42(h'00...')], "version": 1}
+    assert_eq!(&header[..8], b"\xa2\x65roots\x81");
+    assert_eq!(&header[header.len() - 9..], b"\x67version\x01");
+
+    let cid = blocks[1].cid().to_bytes();
+    let data = blocks[1].data();
+    let mut expected = vec![header.len() as u8];
+    expected.extend(&header);
+    expected.push((cid.len() + data.len()) as u8);
+    expected.extend(&cid);
+    expected.extend(data);
+    assert_eq!(car, expected);
+}
+
+#[test]
+fn test_multi_byte_varint() {
+    let blocks = fixture();
+    let car = write_car(&blocks[2..]);
+    let header_len = usize::from(car[0]) + 1;
+    let section_len = blocks[2].cid().to_bytes().len() + blocks[2].data().len();
+    assert!(section_len > 127);
+    assert_eq!(car[header_len], (section_len as u8 & 0x7f) | 0x80);
+    assert_eq!(car[header_len + 1], (section_len >> 7) as u8);
+
+    let mut reader = CarReader::new(&car[..]).unwrap();
+    let (cid, data) = reader.next_block().unwrap().unwrap();
+    assert_eq!(&cid, blocks[2].cid());
+    assert_eq!(data, blocks[2].data());
+    assert!(reader.next_block().unwrap().is_none());
+}
+
+#[test]
+fn test_verify() {
+    let blocks = fixture();
+    let mut car = write_car(&blocks);
+
+    let mut reader = CarReader::new(&car[..]).unwrap();
+    reader.set_verify(true);
+    assert_eq!(reader.count(), 3);
+
+    // Flip the last byte of the last block.
+    let last = car.len() - 1;
+    car[last] ^= 0xff;
+
+    let reader = CarReader::new(&car[..]).unwrap();
+    assert!(reader.collect::<Result<Vec<_>, _>>().is_ok());
+
+    let mut reader = CarReader::new(&car[..]).unwrap();
+    reader.set_verify(true);
+    let results = reader.collect::<Vec<_>>();
+    assert!(results[0].is_ok());
+    assert!(results[1].is_ok());
+    match &results[2] {
+        Err(CarError::Verify(cid, VerifyError::HashMismatch)) => assert_eq!(cid, blocks[2].cid()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn test_truncated() {
+    let blocks = fixture();
+    let car = write_car(&blocks);
+    let mut reader = CarReader::new(&car[..car.len() - 1]).unwrap();
+    assert!(reader.next_block().is_ok());
+    assert!(reader.next_block().is_ok());
+    assert!(matches!(reader.next_block(), Err(CarError::Io(_))));
+
+    assert!(matches!(CarReader::new(&car[..5]), Err(CarError::Io(_))));
+}
+
+#[test]
+fn test_unsupported_version() {
+    let header = to_vec(&CarHeader {
+        version: 3,
+        roots: Vec::new(),
+    })
+    .unwrap();
+    let mut car = vec![header.len() as u8];
+    car.extend(header);
+    assert!(matches!(
+        CarReader::new(&car[..]),
+        Err(CarError::UnsupportedVersion(3))
+    ));
+}
+
+fn write_car_v2(blocks: &[Block], codec: IndexCodec) -> Vec<u8> {
+    let mut writer =
+        CarV2Writer::new(Cursor::new(Vec::new()), vec![*blocks[0].cid()], codec).unwrap();
+    for block in blocks {
+        writer.write(block.cid(), block.data()).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+#[test]
+fn test_v2_layout() {
+    let blocks = fixture();
+    let car = write_car_v2(&blocks, IndexCodec::MultihashIndexSorted);
+    assert_eq!(car[..11], PRAGMA);
+
+    let mut reader = CarReader::new(&car[..]).unwrap();
+    let v2_header = reader.v2_header().unwrap().clone();
+    assert_eq!(v2_header.data_offset, 51);
+    assert!(!v2_header.is_fully_indexed());
+    assert_eq!(
+        v2_header.index_offset,
+        v2_header.data_offset + v2_header.data_size
+    );
+
+    // The payload is a valid CARv1 file on its own.
+    let start = v2_header.data_offset as usize;
+    let payload = &car[start..start + v2_header.data_size as usize];
+    assert_eq!(payload, &write_car(&blocks)[..]);
+
+    // Reading sequentially stops at the end of the payload, before the index.
+    assert_eq!(reader.header(), &CarHeader::new(vec![*blocks[0].cid()]));
+    for block in &blocks {
+        let (cid, data) = reader.next_block().unwrap().unwrap();
+        assert_eq!(&cid, block.cid());
+        assert_eq!(data, block.data());
+    }
+    assert!(reader.next_block().unwrap().is_none());
+}
+
+#[test]
+fn test_v2_header_characteristics() {
+    let mut characteristics = [0; 16];
+    characteristics[7] = 0x80;
+    let header = CarV2Header {
+        characteristics,
+        data_offset: 51,
+        data_size: 0,
+        index_offset: 0,
+    };
+    assert!(header.is_fully_indexed());
+}
+
+#[test]
+fn test_v2_random_access() {
+    let blocks = fixture();
+    for codec in [IndexCodec::IndexSorted, IndexCodec::MultihashIndexSorted] {
+        let car = write_car_v2(&blocks, codec);
+        let mut reader = IndexedCarReader::new(Cursor::new(car)).unwrap();
+        assert_eq!(reader.index().codec(), codec);
+        assert_eq!(reader.index().len(), 3);
+        // Look the blocks up in a different order than they were written.
+        for block in blocks.iter().rev() {
+            assert_eq!(reader.get(block.cid()).unwrap().unwrap(), block.data());
+        }
+        let missing = Block::encode(&"missing", Code::Sha2_256).unwrap();
+        assert!(reader.get(missing.cid()).unwrap().is_none());
+    }
+}
+
+#[test]
+fn test_v1_random_access() {
+    let blocks = fixture();
+    let car = write_car(&blocks);
+    let mut reader = IndexedCarReader::new(Cursor::new(car)).unwrap();
+    assert!(reader.v2_header().is_none());
+    for block in &blocks {
+        assert_eq!(reader.get(block.cid()).unwrap().unwrap(), block.data());
+    }
+}
+
+#[test]
+fn test_index_roundtrip() {
+    let blocks = fixture();
+    for codec in [IndexCodec::IndexSorted, IndexCodec::MultihashIndexSorted] {
+        let car = write_car_v2(&blocks, codec);
+        let generated = Index::generate(&car[..], codec).unwrap();
+
+        let v2_header = CarReader::new(&car[..])
+            .unwrap()
+            .v2_header()
+            .unwrap()
+            .clone();
+        let mut cursor = Cursor::new(&car[..]);
+        cursor
+            .seek(std::io::SeekFrom::Start(v2_header.index_offset))
+            .unwrap();
+        let read = Index::read(&mut cursor).unwrap();
+        assert_eq!(read, generated);
+        assert_eq!(cursor.position() as usize, car.len());
+
+        let mut written = Vec::new();
+        let len = read.write(&mut written).unwrap();
+        assert_eq!(len as usize, written.len());
+        assert_eq!(written, car[v2_header.index_offset as usize..]);
+    }
+}
+
+#[test]
+fn test_index_sorted_layout() {
+    let block = Block::encode(&"leaf", Code::Sha2_256).unwrap();
+    let mut index = Index::new(IndexCodec::IndexSorted);
+    index.insert(block.cid().hash(), 0x0102);
+    let mut bytes = Vec::new();
+    index.write(&mut bytes).unwrap();
+
+    let mut expected = vec![0x80, 0x08];
+    // One bucket.
+    expected.extend(1i32.to_le_bytes());
+    // Width of the bucket: digest length plus 8 bytes offset.
+    expected.extend(40u32.to_le_bytes());
+    // Length of the bucket in bytes.
+    expected.extend(40u64.to_le_bytes());
+    expected.extend(block.cid().hash().digest());
+    expected.extend(0x0102u64.to_le_bytes());
+    assert_eq!(bytes, expected);
+}
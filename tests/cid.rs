@@ -254,3 +254,37 @@ fn test_cid_non_minimally_encoded() {
     let tag_8_bytes_decoded: Cid = from_slice(&tag_8_bytes_encoded).unwrap();
     assert_eq!(tag_8_bytes_decoded, cid);
 }
+
+#[test]
+fn test_write_cid() {
+    use cid::multihash::{Code, Multihash, MultihashDigest};
+    use serde_ipld_dagcbor::ser::{serialize_cid, write_cid, BufWriter};
+
+    #[derive(Serialize)]
+    struct Node {
+        #[serde(serialize_with = "serialize_cid")]
+        link: Cid,
+    }
+
+    let cids = [
+        Cid::from_str("QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n").unwrap(),
+        Cid::from_str("bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy").unwrap(),
+        // A digest of the maximum size.
+        Cid::new_v1(0x71, Code::Sha2_512.digest(b"data")),
+        // Codes that are encoded as the longest varints.
+        Cid::new_v1(u64::MAX, Multihash::wrap(u64::MAX, &[1; 64]).unwrap()),
+    ];
+    for cid in cids {
+        let expected = to_vec(&cid).unwrap();
+
+        let mut writer = BufWriter::new(Vec::new());
+        write_cid(&mut writer, &cid).unwrap();
+        assert_eq!(writer.buffer(), expected);
+
+        let node = to_vec(&Node { link: cid }).unwrap();
+        assert!(node.ends_with(&expected));
+        let link = serde_ipld_dagcbor::link::Link::<()>::new(cid);
+        assert_eq!(to_vec(&link).unwrap(), expected);
+        assert_eq!(from_slice::<Cid>(&expected).unwrap(), cid);
+    }
+}
@@ -0,0 +1,198 @@
+#![cfg(feature = "cli")]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use serde_ipld_dagcbor::car::{CarReader, CarWriter, PRAGMA};
+use serde_ipld_dagcbor::diag::from_diag;
+
+const CID: &str = "bafyreih4jspvxgh5xrp3wboocaajx5ym4ky7ok3gs5dsdkizgnegtanfxu";
+
+fn dagcbor(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_dagcbor"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+#[test]
+fn test_diag_and_inspect() {
+    let data = from_diag(&format!(r#"{{"a": 42({})}}"#, CID)).unwrap();
+    let output = dagcbor(&["diag", "--cid-links"], &data);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), format!("{{\"a\": 42({})}}\n", CID));
+
+    let output = dagcbor(&["inspect", "-"], &data);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains(&format!("link({})", CID)));
+}
+
+#[test]
+fn test_json() {
+    let data = from_diag(r#"{"b": h'01', "aa": [1, -1.5]}"#).unwrap();
+    let output = dagcbor(&["to-json"], &data);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "{\"aa\":[1,-1.5],\"b\":{\"/\":{\"bytes\":\"AQ\"}}}\n"
+    );
+
+    let output = dagcbor(&["from-json"], &output.stdout);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, data);
+}
+
+#[test]
+fn test_validate() {
+    let output = dagcbor(&["validate"], &from_diag("[1, 2]").unwrap());
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "valid\n");
+
+    let output = dagcbor(&["validate"], b"\x82\x18\x01\xf9\x3c\x00");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        "1: NonMinimalArgument\n3: NonDoublePrecisionFloat\n"
+    );
+
+    let output = dagcbor(&["validate"], b"\x82\x01");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "2: Eof\n");
+}
+
+#[test]
+fn test_cid_and_links() {
+    let data = from_diag(&format!("[42({}), 42({})]", CID, CID)).unwrap();
+    let output = dagcbor(&["links"], &data);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), format!("{}\n{}\n", CID, CID));
+
+    let output = dagcbor(&["cid"], &data);
+    let expected = serde_ipld_dagcbor::to_cid::<cid::multihash::Sha2_256, _>(
+        &serde_ipld_dagcbor::from_slice::<Vec<cid::Cid>>(&data).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(stdout(&output), format!("{}\n", expected));
+
+    let output = dagcbor(&["cid", "--hash", "blake2b-256"], &data);
+    assert!(output.status.success());
+    assert_ne!(stdout(&output), format!("{}\n", expected));
+}
+
+#[test]
+fn test_errors() {
+    let output = dagcbor(&["unknown"], b"");
+    assert_eq!(output.status.code(), Some(2));
+    let output = dagcbor(&["cid", "--hash"], b"");
+    assert_eq!(output.status.code(), Some(2));
+    let output = dagcbor(&["diag", "--unknown"], b"");
+    assert_eq!(output.status.code(), Some(2));
+    let output = dagcbor(&["diag", "does-not-exist.cbor"], b"");
+    assert_eq!(output.status.code(), Some(2));
+    let output = dagcbor(&[], b"");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+/// Writes blocks into temporary files and returns their paths.
+fn block_files(name: &str, blocks: &[&str]) -> Vec<String> {
+    let dir = std::env::temp_dir().join(format!("dagcbor-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    blocks
+        .iter()
+        .enumerate()
+        .map(|(ii, diag)| {
+            let path = dir.join(format!("{}.cbor", ii));
+            std::fs::write(&path, from_diag(diag).unwrap()).unwrap();
+            path.to_str().unwrap().to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn test_car() {
+    let files = block_files("car", &[r#"{"name": "root"}"#, "[1, 2, 3]"]);
+    let cids: Vec<String> = files
+        .iter()
+        .map(|file| stdout(&dagcbor(&["cid", file], b"")).trim().to_string())
+        .collect();
+
+    let car = dagcbor(&["car", "create", "--root", &files[0], &files[1]], b"");
+    assert!(car.status.success());
+    let reader = CarReader::new(&car.stdout[..]).unwrap();
+    assert_eq!(reader.header().roots.len(), 1);
+    assert_eq!(reader.header().roots[0].to_string(), cids[0]);
+
+    let output = dagcbor(&["car", "ls"], &car.stdout);
+    assert_eq!(stdout(&output), format!("{} 11\n{} 4\n", cids[0], cids[1]));
+
+    let output = dagcbor(&["car", "extract", &cids[1]], &car.stdout);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, from_diag("[1, 2, 3]").unwrap());
+    let output = dagcbor(&["car", "extract", CID], &car.stdout);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = dagcbor(&["car", "verify"], &car.stdout);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "2 blocks verified\n");
+
+    // Converting to CARv2 keeps the blocks and adds an index.
+    let v2 = dagcbor(&["car", "index", "--codec", "index-sorted"], &car.stdout);
+    assert!(v2.status.success());
+    assert_eq!(&v2.stdout[..11], PRAGMA);
+    assert_eq!(
+        dagcbor(&["car", "ls"], &v2.stdout).stdout,
+        dagcbor(&["car", "ls"], &car.stdout).stdout
+    );
+    let output = dagcbor(&["car", "extract", &cids[0], "-"], &v2.stdout);
+    assert_eq!(output.stdout, from_diag(r#"{"name": "root"}"#).unwrap());
+
+    let v2 = dagcbor(&["car", "create", "--v2", "--root", &files[1]], b"");
+    assert!(v2.status.success());
+    assert_eq!(
+        stdout(&dagcbor(&["car", "ls"], &v2.stdout)),
+        format!("{} 4\n", cids[1])
+    );
+}
+
+#[test]
+fn test_car_verify_errors() {
+    let files = block_files("verify", &["1", "2"]);
+    let cids: Vec<cid::Cid> = files
+        .iter()
+        .map(|file| {
+            stdout(&dagcbor(&["cid", file], b""))
+                .trim()
+                .parse()
+                .unwrap()
+        })
+        .collect();
+
+    // The second block is a root, but it's not contained in the file.
+    let mut writer = CarWriter::new(Vec::new(), cids.clone()).unwrap();
+    writer
+        .write(&cids[0], &std::fs::read(&files[0]).unwrap())
+        .unwrap();
+    let output = dagcbor(&["car", "verify"], &writer.into_inner());
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), format!("{}: root not found\n", cids[1]));
+
+    // The data doesn't match the CID.
+    let mut corrupt = dagcbor(&["car", "create", &files[0]], b"").stdout;
+    *corrupt.last_mut().unwrap() = 0x03;
+    let output = dagcbor(&["car", "verify"], &corrupt);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("HashMismatch"));
+
+    assert_eq!(dagcbor(&["car"], b"").status.code(), Some(2));
+    assert_eq!(dagcbor(&["car", "unknown"], b"").status.code(), Some(2));
+    assert_eq!(dagcbor(&["car", "ls"], b"\x01").status.code(), Some(2));
+}
@@ -0,0 +1,228 @@
+#![cfg(all(feature = "std", feature = "hash"))]
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_bytes::ByteBuf;
+use serde_ipld_dagcbor::{
+    diag::to_diag,
+    from_slice,
+    link::Link,
+    schema::{codegen::Generator, Schema},
+    to_vec, SchemaError,
+};
+
+#[allow(dead_code)]
+mod generated {
+    include!("codegen/generated.rs");
+}
+
+use generated::*;
+
+fn schema() -> Schema {
+    Schema::parse(&fs::read_to_string("tests/codegen/schema.ipldsch").unwrap()).unwrap()
+}
+
+/// Encodes the value, checks it against the schema and the expected diagnostic notation and
+/// decodes it again.
+fn roundtrip<T>(type_name: &str, value: &T, diag: &str)
+where
+    T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+{
+    let encoded = to_vec(value).unwrap();
+    assert_eq!(to_diag(&encoded).unwrap(), diag);
+    schema().validate(type_name, &encoded).unwrap();
+    let decoded: T = from_slice(&encoded).unwrap();
+    assert_eq!(&decoded, value);
+}
+
+#[test]
+fn test_generated_code_is_up_to_date() {
+    let schema = schema();
+    let mut generator = Generator::new(&schema);
+    generator.set_derive_crate("serde_derive");
+    let code = generator.generate().unwrap();
+    let expected = fs::read_to_string("tests/codegen/generated.rs").unwrap();
+    assert!(
+        code == expected,
+        "tests/codegen/generated.rs is outdated, the generated code is:\n{}",
+        code
+    );
+}
+
+#[test]
+fn test_structs() {
+    let person = Person {
+        age: 42,
+        name: "Ferris".to_string(),
+        tags: BTreeMap::from([("lang".to_string(), Some("rust".to_string()))]),
+        avatar: None,
+        email_address: Some("ferris@example.com".to_string()),
+    };
+    roundtrip(
+        "Person",
+        &person,
+        r#"{"age": 42, "name": "Ferris", "tags": {"lang": "rust"}, "email": "ferris@example.com", "avatar": null}"#,
+    );
+    let person = Person {
+        email_address: None,
+        ..person
+    };
+    roundtrip(
+        "Person",
+        &person,
+        r#"{"age": 42, "name": "Ferris", "tags": {"lang": "rust"}, "avatar": null}"#,
+    );
+
+    roundtrip(
+        "Point",
+        &Point {
+            x: 1.5,
+            y: -2.0,
+            label: None,
+        },
+        "[1.5, -2.0]",
+    );
+    roundtrip(
+        "Point",
+        &Point {
+            x: 1.5,
+            y: -2.0,
+            label: Some("a".to_string()),
+        },
+        r#"[1.5, -2.0, "a"]"#,
+    );
+    assert!(from_slice::<Point>(&to_vec(&(1.0, 2.0, "a", 4)).unwrap()).is_err());
+    assert!(from_slice::<Point>(&to_vec(&(1.0,)).unwrap()).is_err());
+
+    roundtrip(
+        "Version",
+        &Version {
+            major: 1,
+            minor: 2,
+            patch: 3,
+        },
+        r#""1.2.3""#,
+    );
+    assert!(from_slice::<Version>(&to_vec("1.2").unwrap()).is_err());
+
+    roundtrip(
+        "Options",
+        &Options {
+            verbose: true,
+            level: Some(3),
+        },
+        r#""verbose=true,level=3""#,
+    );
+    let options: Options = from_slice(&to_vec("verbose=false").unwrap()).unwrap();
+    assert_eq!(options.level, None);
+    assert!(from_slice::<Options>(&to_vec("level=1").unwrap()).is_err());
+}
+
+#[test]
+fn test_maps_and_enums() {
+    let mut headers = BTreeMap::new();
+    headers.insert("a".to_string(), "1".to_string());
+    headers.insert("b".to_string(), "2".to_string());
+    roundtrip("Headers", &Headers(headers), r#""a:1;b:2""#);
+
+    let mut pairs = BTreeMap::new();
+    pairs.insert("x".to_string(), 1);
+    pairs.insert("y".to_string(), 2);
+    roundtrip("Pairs", &Pairs(pairs), r#"[["x", 1], ["y", 2]]"#);
+
+    roundtrip("Color", &Color::Green, r#""green""#);
+    roundtrip("Color", &Color::Blue, r#""Blue""#);
+    assert_eq!(Color::Red.to_string(), "red");
+    assert_eq!("green".parse::<Color>().unwrap(), Color::Green);
+
+    roundtrip("Level", &Level::High, "10");
+    assert!(from_slice::<Level>(&to_vec(&2).unwrap()).is_err());
+}
+
+#[test]
+fn test_unions() {
+    roundtrip(
+        "Shape",
+        &Shape::Version(Version {
+            major: 0,
+            minor: 1,
+            patch: 0,
+        }),
+        r#"{"version": "0.1.0"}"#,
+    );
+
+    roundtrip("Value", &Value::Int(-3), "-3");
+    roundtrip("Value", &Value::String("hi".to_string()), r#""hi""#);
+    roundtrip("Value", &Value::Scores(vec![1, 2]), "[1, 2]");
+    roundtrip("Value", &Value::Bytes(ByteBuf::from(vec![1, 2])), "h'0102'");
+    let person = Person {
+        age: 1,
+        name: "a".to_string(),
+        tags: BTreeMap::new(),
+        avatar: Some(ByteBuf::from(vec![0])),
+        email_address: None,
+    };
+    roundtrip(
+        "Value",
+        &Value::Person(person.clone()),
+        r#"{"age": 1, "name": "a", "tags": {}, "avatar": h'00'}"#,
+    );
+    let link = Link::<Person>::new(
+        "bafyreibvjvcv745gig4mvqs4hctx4zfkono4rjejm2ta6gtyzkqxfjeily"
+            .parse()
+            .unwrap(),
+    );
+    let diag = to_diag(&to_vec(link.cid()).unwrap()).unwrap();
+    roundtrip("Value", &Value::Parent(link), &diag);
+    assert!(from_slice::<Value>(&to_vec(&true).unwrap()).is_err());
+
+    roundtrip(
+        "Message",
+        &Message::Color(Color::Red),
+        r#"{"tag": "color", "content": "red"}"#,
+    );
+    roundtrip(
+        "Message",
+        &Message::Point(Point {
+            x: 0.0,
+            y: 1.0,
+            label: None,
+        }),
+        r#"{"tag": "point", "content": [0.0, 1.0]}"#,
+    );
+
+    roundtrip(
+        "Event",
+        &Event::Ping(Ping {
+            seq: 7,
+            latency: Some(0.5),
+        }),
+        r#"{"seq": 7, "type": "ping", "latency": 0.5}"#,
+    );
+    roundtrip(
+        "Event",
+        &Event::Pong(Pong { seq: 8 }),
+        r#"{"seq": 8, "type": "pong"}"#,
+    );
+}
+
+#[test]
+fn test_unsupported() {
+    for dsl in [
+        "type Foo any",
+        "type Foo {Int:String}",
+        "type Foo struct { a optional nullable Int }",
+        "type Foo struct { a Int } representation listpairs",
+        "type Foo struct { a optional Int b Int } representation tuple",
+        "type Foo struct { a [Int] } representation stringjoin { join \",\" }",
+        "type Foo union { | &Foo \"a\" } representation envelope { discriminantKey \"t\" contentKey \"c\" }",
+    ] {
+        let schema = Schema::parse(dsl).unwrap();
+        match Generator::new(&schema).generate() {
+            Err(SchemaError::Unsupported(_)) => {}
+            other => panic!("unexpected result for `{}`: {:?}", dsl, other),
+        }
+    }
+}
@@ -0,0 +1,418 @@
+// Generated by serde_ipld_dagcbor from an IPLD Schema, do not edit.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde_derive::Serialize, serde_derive::Deserialize)]
+pub enum Color {
+    #[serde(rename = "red")]
+    Red,
+    #[serde(rename = "green")]
+    Green,
+    Blue,
+}
+
+impl ::std::fmt::Display for Color {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        formatter.write_str(match self {
+            Color::Red => "red",
+            Color::Green => "green",
+            Color::Blue => "Blue",
+        })
+    }
+}
+
+impl ::std::str::FromStr for Color {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "red" => Ok(Color::Red),
+            "green" => Ok(Color::Green),
+            "Blue" => Ok(Color::Blue),
+            _ => Err(format!("unknown member `{}`", value)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde_derive::Deserialize)]
+#[serde(tag = "type")]
+pub enum Event {
+    #[serde(rename = "ping")]
+    Ping(Ping),
+    #[serde(rename = "pong")]
+    Pong(Pong),
+}
+
+impl ::serde::Serialize for Event {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ::serde::ser::SerializeMap;
+        match self {
+            Event::Ping(value) => {
+                let mut len = 2;
+                if value.latency.is_some() {
+                    len += 1;
+                }
+                let mut map = serializer.serialize_map(Some(len))?;
+                map.serialize_entry("seq", &value.seq)?;
+                map.serialize_entry("type", "ping")?;
+                if let Some(field) = &value.latency {
+                    map.serialize_entry("latency", field)?;
+                }
+                map.end()
+            }
+            Event::Pong(value) => {
+                let len = 2;
+                let mut map = serializer.serialize_map(Some(len))?;
+                map.serialize_entry("seq", &value.seq)?;
+                map.serialize_entry("type", "pong")?;
+                map.end()
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Headers(pub ::std::collections::BTreeMap<String, String>);
+
+impl ::serde::Serialize for Headers {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries: Vec<String> = self
+            .0
+            .iter()
+            .map(|(key, value)| format!("{}{}{}", key, ":", value))
+            .collect();
+        serializer.serialize_str(&entries.join(";"))
+    }
+}
+
+impl<'de> ::serde::Deserialize<'de> for Headers {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = <String as ::serde::Deserialize>::deserialize(deserializer)?;
+        let mut map = ::std::collections::BTreeMap::new();
+        if !string.is_empty() {
+            for entry in string.split(";") {
+                let (key, value) = entry.split_once(":").ok_or_else(|| {
+                    ::serde::de::Error::custom(format!("entry `{}` has no delimiter", entry))
+                })?;
+                let value = value.parse().map_err(::serde::de::Error::custom)?;
+                map.insert(key.to_string(), value);
+            }
+        }
+        Ok(Headers(map))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Low,
+    High,
+}
+
+impl ::serde::Serialize for Level {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(match self {
+            Level::Low => 1,
+            Level::High => 10,
+        })
+    }
+}
+
+impl<'de> ::serde::Deserialize<'de> for Level {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match <i64 as ::serde::Deserialize>::deserialize(deserializer)? {
+            1 => Ok(Level::Low),
+            10 => Ok(Level::High),
+            other => Err(::serde::de::Error::invalid_value(
+                ::serde::de::Unexpected::Signed(other),
+                &"a member of Level",
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde_derive::Deserialize)]
+#[serde(tag = "tag", content = "content")]
+pub enum Message {
+    #[serde(rename = "point")]
+    Point(Point),
+    #[serde(rename = "color")]
+    Color(Color),
+}
+
+impl ::serde::Serialize for Message {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ::serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(2))?;
+        match self {
+            Message::Point(value) => {
+                map.serialize_entry("tag", "point")?;
+                map.serialize_entry("content", value)?;
+            }
+            Message::Color(value) => {
+                map.serialize_entry("tag", "color")?;
+                map.serialize_entry("content", value)?;
+            }
+        }
+        map.end()
+    }
+}
+
+pub type Name = String;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    pub verbose: bool,
+    pub level: Option<i64>,
+}
+
+impl ::serde::Serialize for Options {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut entries: Vec<String> = Vec::new();
+        entries.push(format!("{}{}{}", "verbose", "=", self.verbose));
+        if let Some(value) = &self.level {
+            entries.push(format!("{}{}{}", "level", "=", value));
+        }
+        serializer.serialize_str(&entries.join(","))
+    }
+}
+
+impl<'de> ::serde::Deserialize<'de> for Options {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = <String as ::serde::Deserialize>::deserialize(deserializer)?;
+        let mut field_0 = None;
+        let mut field_1 = None;
+        if !string.is_empty() {
+            for entry in string.split(",") {
+                let (key, value) = entry.split_once("=").ok_or_else(|| {
+                    ::serde::de::Error::custom(format!("entry `{}` has no delimiter", entry))
+                })?;
+                match key {
+                    "verbose" => field_0 = Some(value.parse().map_err(::serde::de::Error::custom)?),
+                    "level" => field_1 = Some(value.parse().map_err(::serde::de::Error::custom)?),
+                    _ => return Err(::serde::de::Error::unknown_field(key, &["verbose", "level"])),
+                }
+            }
+        }
+        Ok(Options { verbose: field_0.ok_or_else(|| ::serde::de::Error::missing_field("verbose"))?, level: field_1 })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pairs(pub ::std::collections::BTreeMap<String, i64>);
+
+impl ::serde::Serialize for Pairs {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter())
+    }
+}
+
+impl<'de> ::serde::Deserialize<'de> for Pairs {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pairs: Vec<(String, i64)> = ::serde::Deserialize::deserialize(deserializer)?;
+        Ok(Pairs(pairs.into_iter().collect()))
+    }
+}
+
+pub type Parent = ::serde_ipld_dagcbor::link::Link<Person>;
+
+#[derive(Clone, Debug, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Person {
+    pub age: i64,
+    pub name: Name,
+    pub tags: Tags,
+    #[serde(rename = "email")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email_address: Option<String>,
+    pub avatar: Option<::serde_bytes::ByteBuf>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ping {
+    pub seq: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+    pub label: Option<String>,
+}
+
+impl ::serde::Serialize for Point {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ::serde::ser::SerializeSeq;
+        let mut len = 2;
+        if self.label.is_some() {
+            len = 3;
+        }
+        let mut seq = serializer.serialize_seq(Some(len))?;
+        seq.serialize_element(&self.x)?;
+        seq.serialize_element(&self.y)?;
+        if len > 2 {
+            match &self.label {
+                Some(value) => seq.serialize_element(value)?,
+                None => {
+                    return Err(::serde::ser::Error::custom(
+                        "only trailing optional fields may be absent",
+                    ))
+                }
+            }
+        }
+        seq.end()
+    }
+}
+
+impl<'de> ::serde::Deserialize<'de> for Point {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TupleVisitor;
+
+        impl<'de> ::serde::de::Visitor<'de> for TupleVisitor {
+            type Value = Point;
+
+            fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                formatter.write_str("a list of 2 to 3 elements")
+            }
+
+            fn visit_seq<A: ::serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<Point, A::Error> {
+                let field_0 = seq
+                    .next_element()?
+                    .ok_or_else(|| ::serde::de::Error::invalid_length(0, &self))?;
+                let field_1 = seq
+                    .next_element()?
+                    .ok_or_else(|| ::serde::de::Error::invalid_length(1, &self))?;
+                let field_2 = seq.next_element()?;
+                if seq.next_element::<::serde::de::IgnoredAny>()?.is_some() {
+                    return Err(::serde::de::Error::invalid_length(4, &self));
+                }
+                Ok(Point { x: field_0, y: field_1, label: field_2 })
+            }
+        }
+
+        deserializer.deserialize_seq(TupleVisitor)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pong {
+    pub seq: i64,
+}
+
+pub type Scores = Vec<i64>;
+
+#[derive(Clone, Debug, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+pub enum Shape {
+    #[serde(rename = "point")]
+    Point(Point),
+    #[serde(rename = "version")]
+    Version(Version),
+}
+
+pub type Tags = ::std::collections::BTreeMap<String, Option<String>>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Int(i64),
+    String(String),
+    Scores(Scores),
+    Person(Person),
+    Parent(Parent),
+    Bytes(::serde_bytes::ByteBuf),
+}
+
+impl ::serde::Serialize for Value {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Int(value) => ::serde::Serialize::serialize(value, serializer),
+            Value::String(value) => ::serde::Serialize::serialize(value, serializer),
+            Value::Scores(value) => ::serde::Serialize::serialize(value, serializer),
+            Value::Person(value) => ::serde::Serialize::serialize(value, serializer),
+            Value::Parent(value) => ::serde::Serialize::serialize(value, serializer),
+            Value::Bytes(value) => ::serde::Serialize::serialize(value, serializer),
+        }
+    }
+}
+
+impl<'de> ::serde::Deserialize<'de> for Value {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KindVisitor;
+
+        impl<'de> ::serde::de::Visitor<'de> for KindVisitor {
+            type Value = Value;
+
+            fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                formatter.write_str("a value of kind int, string, list, map, link, bytes")
+            }
+
+            fn visit_i64<E: ::serde::de::Error>(self, value: i64) -> Result<Value, E> {
+                ::serde::Deserialize::deserialize(::serde::de::IntoDeserializer::into_deserializer(value)).map(Value::Int)
+            }
+
+            fn visit_u64<E: ::serde::de::Error>(self, value: u64) -> Result<Value, E> {
+                ::serde::Deserialize::deserialize(::serde::de::IntoDeserializer::into_deserializer(value)).map(Value::Int)
+            }
+
+            fn visit_str<E: ::serde::de::Error>(self, value: &str) -> Result<Value, E> {
+                ::serde::Deserialize::deserialize(::serde::de::IntoDeserializer::into_deserializer(value)).map(Value::String)
+            }
+
+            fn visit_seq<A: ::serde::de::SeqAccess<'de>>(self, seq: A) -> Result<Value, A::Error> {
+                let deserializer = ::serde::de::value::SeqAccessDeserializer::new(seq);
+                ::serde::Deserialize::deserialize(deserializer).map(Value::Scores)
+            }
+
+            fn visit_map<A: ::serde::de::MapAccess<'de>>(self, map: A) -> Result<Value, A::Error> {
+                let deserializer = ::serde::de::value::MapAccessDeserializer::new(map);
+                ::serde::Deserialize::deserialize(deserializer).map(Value::Person)
+            }
+
+            fn visit_newtype_struct<D: ::serde::Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Value, D::Error> {
+                ::serde::Deserialize::deserialize(deserializer).map(Value::Parent)
+            }
+
+            fn visit_bytes<E: ::serde::de::Error>(self, value: &[u8]) -> Result<Value, E> {
+                ::serde::Deserialize::deserialize(::serde::de::value::BytesDeserializer::new(value)).map(Value::Bytes)
+            }
+        }
+
+        deserializer.deserialize_any(KindVisitor)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Version {
+    pub major: i64,
+    pub minor: i64,
+    pub patch: i64,
+}
+
+impl ::serde::Serialize for Version {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&[self.major.to_string(), self.minor.to_string(), self.patch.to_string()].join("."))
+    }
+}
+
+impl<'de> ::serde::Deserialize<'de> for Version {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = <String as ::serde::Deserialize>::deserialize(deserializer)?;
+        let parts: Vec<&str> = string.split(".").collect();
+        if parts.len() != 3 {
+            return Err(::serde::de::Error::invalid_length(
+                parts.len(),
+                &"3 values joined by .",
+            ));
+        }
+        let field_0 = parts[0].parse().map_err(::serde::de::Error::custom)?;
+        let field_1 = parts[1].parse().map_err(::serde::de::Error::custom)?;
+        let field_2 = parts[2].parse().map_err(::serde::de::Error::custom)?;
+        Ok(Version { major: field_0, minor: field_1, patch: field_2 })
+    }
+}
@@ -1,26 +1,23 @@
 use std::collections::BTreeMap;
 
 use libipld_core::ipld::Ipld;
-use serde_ipld_dagcbor::{de, to_vec, DecodeError};
+use serde_ipld_dagcbor::{de, diag::from_diag, to_vec, DecodeError};
 
 #[test]
 fn test_string1() {
-    let ipld: Result<Ipld, _> = de::from_slice(&[0x66, 0x66, 0x6f, 0x6f, 0x62, 0x61, 0x72]);
+    let ipld: Result<Ipld, _> = de::from_slice(&from_diag(r#""foobar""#).unwrap());
     assert_eq!(ipld.unwrap(), Ipld::String("foobar".to_string()));
 }
 
 #[test]
 fn test_string2() {
-    let ipld: Result<Ipld, _> = de::from_slice(&[
-        0x71, 0x49, 0x20, 0x6d, 0x65, 0x74, 0x20, 0x61, 0x20, 0x74, 0x72, 0x61, 0x76, 0x65, 0x6c,
-        0x6c, 0x65, 0x72,
-    ]);
+    let ipld: Result<Ipld, _> = de::from_slice(&from_diag(r#""I met a traveller""#).unwrap());
     assert_eq!(ipld.unwrap(), Ipld::String("I met a traveller".to_string()));
 }
 
 #[test]
 fn test_string3() {
-    let slice = b"\x78\x2fI met a traveller from an antique land who said";
+    let slice = &from_diag(r#""I met a traveller from an antique land who said""#).unwrap();
     let ipld: Result<Ipld, _> = de::from_slice(slice);
     assert_eq!(
         ipld.unwrap(),
@@ -30,31 +27,31 @@ fn test_string3() {
 
 #[test]
 fn test_byte_string() {
-    let ipld: Result<Ipld, _> = de::from_slice(&[0x46, 0x66, 0x6f, 0x6f, 0x62, 0x61, 0x72]);
+    let ipld: Result<Ipld, _> = de::from_slice(&from_diag("h'666f6f626172'").unwrap());
     assert_eq!(ipld.unwrap(), Ipld::Bytes(b"foobar".to_vec()));
 }
 
 #[test]
 fn test_numbers1() {
-    let ipld: Result<Ipld, _> = de::from_slice(&[0x00]);
+    let ipld: Result<Ipld, _> = de::from_slice(&from_diag("0").unwrap());
     assert_eq!(ipld.unwrap(), Ipld::Integer(0));
 }
 
 #[test]
 fn test_numbers2() {
-    let ipld: Result<Ipld, _> = de::from_slice(&[0x1a, 0x00, 0xbc, 0x61, 0x4e]);
+    let ipld: Result<Ipld, _> = de::from_slice(&from_diag("12345678").unwrap());
     assert_eq!(ipld.unwrap(), Ipld::Integer(12345678));
 }
 
 #[test]
 fn test_numbers3() {
-    let ipld: Result<Ipld, _> = de::from_slice(&[0x39, 0x07, 0xde]);
+    let ipld: Result<Ipld, _> = de::from_slice(&from_diag("-2015").unwrap());
     assert_eq!(ipld.unwrap(), Ipld::Integer(-2015));
 }
 
 #[test]
 fn test_bool() {
-    let ipld: Result<Ipld, _> = de::from_slice(b"\xf4");
+    let ipld: Result<Ipld, _> = de::from_slice(&from_diag("false").unwrap());
     assert_eq!(ipld.unwrap(), Ipld::Bool(false));
 }
 
@@ -66,7 +63,7 @@ fn test_trailing_bytes() {
 
 #[test]
 fn test_list1() {
-    let ipld: Result<Ipld, _> = de::from_slice(b"\x83\x01\x02\x03");
+    let ipld: Result<Ipld, _> = de::from_slice(&from_diag("[1, 2, 3]").unwrap());
     assert_eq!(
         ipld.unwrap(),
         Ipld::List(vec![Ipld::Integer(1), Ipld::Integer(2), Ipld::Integer(3)])
@@ -75,7 +72,7 @@ fn test_list1() {
 
 #[test]
 fn test_list2() {
-    let ipld: Result<Ipld, _> = de::from_slice(b"\x82\x01\x82\x02\x81\x03");
+    let ipld: Result<Ipld, _> = de::from_slice(&from_diag("[1, [2, [3]]]").unwrap());
     assert_eq!(
         ipld.unwrap(),
         Ipld::List(vec![
@@ -87,7 +84,9 @@ fn test_list2() {
 
 #[test]
 fn test_object() {
-    let ipld: Result<Ipld, _> = de::from_slice(b"\xa5aaaAabaBacaCadaDaeaE");
+    let ipld: Result<Ipld, _> = de::from_slice(
+        &from_diag(r#"{"a": "A", "b": "B", "c": "C", "d": "D", "e": "E"}"#).unwrap(),
+    );
     let mut object = BTreeMap::new();
     object.insert("a".to_string(), Ipld::String("A".to_string()));
     object.insert("b".to_string(), Ipld::String("B".to_string()));
@@ -99,7 +98,7 @@ fn test_object() {
 
 #[test]
 fn test_indefinite_object() {
-    let ipld: Result<Ipld, _> = de::from_slice(b"\xbfaa\x01ab\x9f\x02\x03\xff\xff");
+    let ipld: Result<Ipld, _> = de::from_slice(&from_diag(r#"{_ "a": 1, "b": [_ 2, 3]}"#).unwrap());
     let mut object = BTreeMap::new();
     object.insert("a".to_string(), Ipld::Integer(1));
     object.insert(
@@ -111,7 +110,7 @@ fn test_indefinite_object() {
 
 #[test]
 fn test_indefinite_list() {
-    let ipld: Result<Ipld, _> = de::from_slice(b"\x9f\x01\x02\x03\xff");
+    let ipld: Result<Ipld, _> = de::from_slice(&from_diag("[_ 1, 2, 3]").unwrap());
     assert_eq!(
         ipld.unwrap(),
         Ipld::List(vec![Ipld::Integer(1), Ipld::Integer(2), Ipld::Integer(3)])
@@ -120,8 +119,8 @@ fn test_indefinite_list() {
 
 #[test]
 fn test_indefinite_string() {
-    let ipld: Result<Ipld, _> =
-        de::from_slice(b"\x7f\x65Mary \x64Had \x62a \x67Little \x60\x64Lamb\xff");
+    let input = from_diag(r#"(_ "Mary ", "Had ", "a ", "Little ", "", "Lamb")"#).unwrap();
+    let ipld: Result<Ipld, _> = de::from_slice(&input);
     assert_eq!(
         ipld.unwrap(),
         Ipld::String("Mary Had a Little Lamb".to_string())
@@ -130,14 +129,16 @@ fn test_indefinite_string() {
 
 #[test]
 fn test_indefinite_byte_string() {
-    let ipld: Result<Ipld, _> = de::from_slice(b"\x5f\x42\x01\x23\x42\x45\x67\xff");
+    let ipld: Result<Ipld, _> = de::from_slice(&from_diag("(_ h'0123', h'4567')").unwrap());
     assert_eq!(ipld.unwrap(), Ipld::Bytes(b"\x01#Eg".to_vec()));
 }
 
 #[test]
 fn test_multiple_indefinite_strings() {
-    let input = b"\x82\x7f\x65Mary \x64Had \x62a \x67Little \x60\x64Lamb\xff\x5f\x42\x01\x23\x42\x45\x67\xff";
-    let ipld: Result<Ipld, _> = de::from_slice(input);
+    let input =
+        from_diag(r#"[(_ "Mary ", "Had ", "a ", "Little ", "", "Lamb"), (_ h'0123', h'4567')]"#)
+            .unwrap();
+    let ipld: Result<Ipld, _> = de::from_slice(&input);
     assert_eq!(
         ipld.unwrap(),
         Ipld::List(vec![
@@ -149,14 +150,13 @@ fn test_multiple_indefinite_strings() {
 
 #[test]
 fn test_float() {
-    let ipld: Result<Ipld, _> = de::from_slice(b"\xfa\x47\xc3\x50\x00");
+    let ipld: Result<Ipld, _> = de::from_slice(&from_diag("100000.0_2").unwrap());
     assert_eq!(ipld.unwrap(), Ipld::Float(100000.0));
 }
 
 #[test]
 fn test_rejected_tag() {
-    let ipld: Result<Ipld, _> =
-        de::from_slice(&[0xd9, 0xd9, 0xf7, 0x66, 0x66, 0x6f, 0x6f, 0x62, 0x61, 0x72]);
+    let ipld: Result<Ipld, _> = de::from_slice(&from_diag(r#"55799("foobar")"#).unwrap());
     assert!(matches!(
         ipld.unwrap_err(),
         DecodeError::TypeMismatch {
@@ -168,8 +168,9 @@ fn test_rejected_tag() {
 
 #[test]
 fn test_crazy_list() {
-    let slice = b"\x86\x1b\x00\x00\x00\x1c\xbe\x99\x1d\xc7\x3b\x00\x7a\xcf\x51\xdc\x51\x70\xdb\x3a\x1b\x3a\x06\xdd\xf5\xf6\xfb\x41\x76\x5e\xb1\xf8\x00\x00\x00";
-    let ipld: Vec<Ipld> = de::from_slice(slice).unwrap();
+    let slice = from_diag("[123456789959, -34567897654325468, -456787678, true, null, 23456543.5]")
+        .unwrap();
+    let ipld: Vec<Ipld> = de::from_slice(&slice).unwrap();
     assert_eq!(
         ipld,
         vec![
@@ -185,7 +186,7 @@ fn test_crazy_list() {
 
 #[test]
 fn test_nan() {
-    let ipld: Result<f64, _> = de::from_slice(b"\xf9\x7e\x00");
+    let ipld: Result<f64, _> = de::from_slice(&from_diag("NaN_1").unwrap());
     assert!(matches!(
         ipld.unwrap_err(),
         DecodeError::TypeMismatch { .. }
@@ -234,8 +235,8 @@ fn test_unit() {
 
 #[test]
 fn test_variable_length_map() {
-    let slice = b"\xbf\x67\x6d\x65\x73\x73\x61\x67\x65\x64\x70\x6f\x6e\x67\xff";
-    let ipld: Ipld = de::from_slice(slice).unwrap();
+    let slice = from_diag(r#"{_ "message": "pong"}"#).unwrap();
+    let ipld: Ipld = de::from_slice(&slice).unwrap();
     let mut map = BTreeMap::new();
     map.insert("message".to_string(), Ipld::String("pong".to_string()));
     assert_eq!(ipld, Ipld::Map(map))
@@ -243,12 +244,12 @@ fn test_variable_length_map() {
 
 #[test]
 fn test_object_determinism_roundtrip() {
-    let expected = b"\xa2aa\x01ab\x82\x02\x03";
+    let expected = from_diag(r#"{"a": 1, "b": [2, 3]}"#).unwrap();
 
     // 0.1% chance of not catching failure
     for _ in 0..10 {
         assert_eq!(
-            &to_vec(&de::from_slice::<Ipld>(expected).unwrap()).unwrap(),
+            to_vec(&de::from_slice::<Ipld>(&expected).unwrap()).unwrap(),
             expected
         );
     }
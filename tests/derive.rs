@@ -0,0 +1,215 @@
+#![cfg(feature = "std")]
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use cid::Cid;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_bytes::ByteBuf;
+use serde_ipld_dagcbor::{diag::to_diag, from_slice, to_vec};
+use serde_ipld_dagcbor_derive::{DagCborDeserialize, DagCborSerialize};
+
+/// Checks the encoding of the value in diagnostic notation and that it decodes to the same value.
+fn roundtrip<T>(value: &T, diag: &str)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let encoded = to_vec(value).unwrap();
+    assert_eq!(to_diag(&encoded).unwrap(), diag);
+    assert_eq!(&from_slice::<T>(&encoded).unwrap(), value);
+}
+
+#[derive(Debug, PartialEq, DagCborSerialize, DagCborDeserialize)]
+struct Person {
+    name: String,
+    #[ipld(rename = "a")]
+    age: u8,
+    #[ipld(optional)]
+    email: Option<String>,
+    nickname: Option<String>,
+}
+
+#[derive(Debug, PartialEq, DagCborSerialize, DagCborDeserialize)]
+#[ipld(repr = "tuple")]
+struct Message<T> {
+    nonce: u64,
+    params: T,
+    #[ipld(optional)]
+    gas: Option<i64>,
+    #[ipld(optional)]
+    memo: Option<String>,
+}
+
+#[derive(Debug, PartialEq, DagCborSerialize, DagCborDeserialize)]
+#[ipld(repr = "tuple")]
+struct Pair(String, #[ipld(optional)] Option<bool>);
+
+#[derive(Debug, PartialEq, DagCborSerialize, DagCborDeserialize)]
+#[ipld(repr = "stringjoin", join = ".")]
+struct Version {
+    major: u32,
+    minor: u32,
+    patch: u32,
+}
+
+#[derive(Debug, PartialEq, DagCborSerialize, DagCborDeserialize)]
+enum Shape {
+    #[ipld(rename = "circle")]
+    Circle(f64),
+    Rectangle(Pair),
+}
+
+#[derive(Debug, PartialEq, DagCborSerialize, DagCborDeserialize)]
+#[ipld(repr = "kinded")]
+enum Value {
+    #[ipld(kind = "null")]
+    Null,
+    #[ipld(kind = "bool")]
+    Bool(bool),
+    #[ipld(kind = "int")]
+    Int(i64),
+    #[ipld(kind = "float")]
+    Float(f64),
+    #[ipld(kind = "string")]
+    String(String),
+    #[ipld(kind = "bytes")]
+    Bytes(ByteBuf),
+    #[ipld(kind = "list")]
+    List(Vec<Value>),
+    #[ipld(kind = "map")]
+    Map(BTreeMap<String, Value>),
+    #[ipld(kind = "link")]
+    Link(Cid),
+}
+
+#[derive(Debug, PartialEq, DagCborSerialize, DagCborDeserialize)]
+#[ipld(repr = "int")]
+#[repr(u16)]
+enum Method {
+    Constructor = 1,
+    Send = 2,
+    Exec = 1000,
+}
+
+#[derive(Debug, PartialEq, DagCborSerialize, DagCborDeserialize)]
+#[ipld(repr = "int")]
+#[repr(i64)]
+enum Signed {
+    Negative = -3,
+    Large = i64::MAX,
+}
+
+#[test]
+fn test_map() {
+    let mut person = Person {
+        name: "Ferris".to_string(),
+        age: 7,
+        email: None,
+        nickname: None,
+    };
+    roundtrip(&person, r#"{"a": 7, "name": "Ferris", "nickname": null}"#);
+    person.email = Some("ferris@example.com".to_string());
+    roundtrip(
+        &person,
+        r#"{"a": 7, "name": "Ferris", "email": "ferris@example.com", "nickname": null}"#,
+    );
+
+    // Required fields must be present, unknown or duplicate fields are rejected.
+    let missing = to_vec(&BTreeMap::from([("name", "Ferris")])).unwrap();
+    assert!(from_slice::<Person>(&missing).is_err());
+    let unknown = serde_ipld_dagcbor::diag::from_diag(
+        r#"{"a": 7, "x": 1, "name": "Ferris", "nickname": null}"#,
+    )
+    .unwrap();
+    assert!(from_slice::<Person>(&unknown).is_err());
+}
+
+#[test]
+fn test_tuple() {
+    let mut message = Message {
+        nonce: 1,
+        params: vec![1u8, 2],
+        gas: None,
+        memo: None,
+    };
+    roundtrip(&message, "[1, [1, 2]]");
+    message.gas = Some(-5);
+    roundtrip(&message, "[1, [1, 2], -5]");
+    message.memo = Some("hi".to_string());
+    roundtrip(&message, r#"[1, [1, 2], -5, "hi"]"#);
+
+    // Only trailing optional fields may be absent.
+    message.gas = None;
+    assert!(to_vec(&message).is_err());
+
+    assert!(from_slice::<Message<u8>>(&to_vec(&(1,)).unwrap()).is_err());
+    assert!(from_slice::<Message<u8>>(&to_vec(&(1, 2, 3, "a", 5)).unwrap()).is_err());
+
+    roundtrip(&Pair("a".to_string(), None), r#"["a"]"#);
+    roundtrip(&Pair("a".to_string(), Some(true)), r#"["a", true]"#);
+}
+
+#[test]
+fn test_stringjoin() {
+    roundtrip(
+        &Version {
+            major: 1,
+            minor: 12,
+            patch: 0,
+        },
+        r#""1.12.0""#,
+    );
+    assert!(from_slice::<Version>(&to_vec("1.2").unwrap()).is_err());
+    assert!(from_slice::<Version>(&to_vec("1.2.x").unwrap()).is_err());
+}
+
+#[test]
+fn test_keyed() {
+    roundtrip(&Shape::Circle(1.5), r#"{"circle": 1.5}"#);
+    roundtrip(
+        &Shape::Rectangle(Pair("b".to_string(), Some(false))),
+        r#"{"Rectangle": ["b", false]}"#,
+    );
+    assert!(from_slice::<Shape>(&to_vec(&BTreeMap::from([("square", 1)])).unwrap()).is_err());
+    assert!(from_slice::<Shape>(&to_vec(&BTreeMap::<String, u8>::new()).unwrap()).is_err());
+}
+
+#[test]
+fn test_kinded() {
+    let cid: Cid = "bafyreibvjvcv745gig4mvqs4hctx4zfkono4rjejm2ta6gtyzkqxfjeily"
+        .parse()
+        .unwrap();
+    let cid_diag = to_diag(&to_vec(&cid).unwrap()).unwrap();
+
+    roundtrip(&Value::Null, "null");
+    roundtrip(&Value::Bool(true), "true");
+    roundtrip(&Value::Int(-1), "-1");
+    roundtrip(&Value::Int(24), "24");
+    roundtrip(&Value::Float(0.5), "0.5");
+    roundtrip(&Value::String("a".to_string()), r#""a""#);
+    roundtrip(&Value::Bytes(ByteBuf::from(vec![1])), "h'01'");
+    roundtrip(&Value::Link(cid), &cid_diag);
+    roundtrip(
+        &Value::List(vec![Value::Int(1), Value::Link(cid)]),
+        &format!("[1, {}]", cid_diag),
+    );
+    roundtrip(
+        &Value::Map(BTreeMap::from([("k".to_string(), Value::Null)])),
+        r#"{"k": null}"#,
+    );
+}
+
+#[test]
+fn test_int() {
+    roundtrip(&Method::Constructor, "1");
+    roundtrip(&Method::Send, "2");
+    roundtrip(&Method::Exec, "1000");
+    roundtrip(&Signed::Negative, "-3");
+    roundtrip(&Signed::Large, "9223372036854775807");
+    let error = from_slice::<Method>(&to_vec(&0).unwrap()).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("unknown discriminant 0 of enum Method"));
+    assert!(from_slice::<Method>(&to_vec(&-1).unwrap()).is_err());
+    assert!(from_slice::<Method>(&to_vec(&"Send").unwrap()).is_err());
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use cid::Cid;
+use libipld_core::ipld::Ipld;
+use serde_ipld_dagcbor::diag::{from_diag, to_diag, to_diag_with_options, DiagOptions};
+use serde_ipld_dagcbor::{DecodeError, DiagError};
+
+const CID: &str = "bafyreih4jspvxgh5xrp3wboocaajx5ym4ky7ok3gs5dsdkizgnegtanfxu";
+
+#[test]
+fn test_ipld() {
+    let cid = Cid::try_from(CID).unwrap();
+    let mut map = BTreeMap::new();
+    map.insert("link".to_string(), Ipld::Link(cid));
+    map.insert(
+        "a".to_string(),
+        Ipld::List(vec![Ipld::Null, Ipld::Bool(true)]),
+    );
+    map.insert("bytes".to_string(), Ipld::Bytes(vec![0xca, 0xfe]));
+    map.insert("float".to_string(), Ipld::Float(-0.5));
+    map.insert("int".to_string(), Ipld::Integer(-(1 << 64)));
+    let cbor = serde_ipld_dagcbor::to_vec(&Ipld::Map(map)).unwrap();
+
+    let expected = format!(
+        r#"{{"a": [null, true], "int": -18446744073709551616, "link": 42({}), "bytes": h'cafe', "float": -0.5}}"#,
+        CID
+    );
+    let options = DiagOptions { cid_links: true };
+    assert_eq!(to_diag_with_options(&cbor, &options).unwrap(), expected);
+    assert_eq!(from_diag(&expected).unwrap(), cbor);
+
+    // By default the bytes of the link are shown.
+    let diag = to_diag(&cbor).unwrap();
+    let cid_hex: String = cid
+        .to_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    assert!(diag.contains(&format!("42(h'00{}')", cid_hex)));
+    assert_eq!(from_diag(&diag).unwrap(), cbor);
+}
+
+#[test]
+fn test_roundtrip() {
+    for diag in [
+        "0",
+        "23",
+        "24",
+        "18446744073709551615",
+        "-1",
+        "-25",
+        r#""""#,
+        r#""a\"b\n\u0001""#,
+        "h''",
+        "[]",
+        "{}",
+        "[_ 1, [2]]",
+        r#"{_ "a": {_ }}"#,
+        r#"(_ "a", "b")"#,
+        "(_ h'01', h'')",
+        "1(1363896240)",
+        "1.0",
+        "1e100",
+        "Infinity",
+        "-Infinity",
+        "NaN",
+        "1.5_2",
+        "-0.0_1",
+        "65504.0_1",
+        "5.960464477539063e-8_1",
+        "undefined",
+        "simple(16)",
+        "simple(255)",
+    ] {
+        let bytes = from_diag(diag).unwrap();
+        assert_eq!(to_diag(&bytes).unwrap(), diag);
+    }
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(from_diag("[1, 2]").unwrap(), b"\x82\x01\x02");
+    assert_eq!(from_diag("[_ 1, 2]").unwrap(), b"\x9f\x01\x02\xff");
+    assert_eq!(from_diag("1.5_1").unwrap(), b"\xf9\x3e\x00");
+    assert_eq!(from_diag("1.5_2").unwrap(), b"\xfa\x3f\xc0\x00\x00");
+    assert_eq!(from_diag("1.5_3").unwrap(), from_diag("1.5").unwrap());
+    assert_eq!(
+        from_diag("-18446744073709551616").unwrap(),
+        b"\x3b\xff\xff\xff\xff\xff\xff\xff\xff"
+    );
+    assert_eq!(from_diag("h'01 02\n03'").unwrap(), b"\x43\x01\x02\x03");
+    assert_eq!(
+        from_diag(" / a comment / {\"a\" : [ ] , / another / \"b\":1 } ").unwrap(),
+        b"\xa2\x61a\x80\x61b\x01"
+    );
+    assert_eq!(from_diag("24(h'01')").unwrap(), b"\xd8\x18\x41\x01");
+}
+
+#[test]
+fn test_parse_errors() {
+    for invalid in [
+        "",
+        "[1, 2",
+        "[1 2]",
+        "{1}",
+        "h'0'",
+        "h'0g'",
+        "18446744073709551616",
+        "-18446744073709551617",
+        "1.1_1",
+        "1.1_2",
+        "1.0_4",
+        "simple(20)",
+        "42(notacid)",
+        "foo",
+        "(_ h'', \"\")",
+        "1 2",
+        "/ unterminated",
+    ] {
+        assert!(from_diag(invalid).is_err(), "{:?}", invalid);
+    }
+    assert!(matches!(
+        from_diag("[1,]"),
+        Err(DiagError::Syntax { position: 3, .. })
+    ));
+    assert!(matches!(
+        from_diag("[1, h'0']"),
+        Err(DiagError::Invalid { position: 4, .. })
+    ));
+    let nested = "[".repeat(1000) + &"]".repeat(1000);
+    assert!(from_diag(&nested).is_err());
+}
+
+#[test]
+fn test_print_errors() {
+    assert!(matches!(to_diag(b""), Err(DecodeError::Eof)));
+    assert!(matches!(to_diag(b"\x82\x01"), Err(DecodeError::Eof)));
+    assert!(matches!(
+        to_diag(b"\x01\x02"),
+        Err(DecodeError::TrailingData)
+    ));
+    assert!(matches!(
+        to_diag(b"\x1c"),
+        Err(DecodeError::Unsupported { byte: 0x1c })
+    ));
+    assert!(matches!(
+        to_diag(b"\x62\xff\xfe"),
+        Err(DecodeError::InvalidUtf8(_))
+    ));
+    // Chunks of indefinite length strings must be of the same type.
+    assert!(to_diag(b"\x7f\x41a\xff").is_err());
+    let nested = vec![0x81; 1000];
+    assert!(matches!(to_diag(&nested), Err(DecodeError::DepthLimit)));
+
+    // Invalid CIDs are shown as bytes.
+    let options = DiagOptions { cid_links: true };
+    assert_eq!(
+        to_diag_with_options(b"\xd8\x2a\x42\x00\x01", &options).unwrap(),
+        "42(h'0001')"
+    );
+}
@@ -1,6 +1,14 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
-use serde_ipld_dagcbor::{from_slice, to_vec};
+use serde_ipld_dagcbor::{
+    de::{from_reader_with_options, from_slice_with_options, DecodeOptions},
+    diag::{from_diag, to_diag},
+    from_slice,
+    ser::{to_vec_with_options, EncodeOptions, EnumRepresentation},
+    to_vec,
+};
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 enum Enum {
@@ -81,8 +89,8 @@ enum Foo {
 
 #[test]
 fn test_variable_length_array() {
-    let slice = b"\x9F\x67\x72\x65\x71\x75\x69\x72\x65\xFF";
-    let value: Vec<Foo> = from_slice(slice).unwrap();
+    let slice = from_diag(r#"[_ "require"]"#).unwrap();
+    let value: Vec<Foo> = from_slice(&slice).unwrap();
     assert_eq!(value, [Foo::Require]);
 }
 
@@ -93,3 +101,204 @@ enum Bar {
     Flag(String, bool),
     Point { x: i32, y: i32 },
 }
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Circle {
+    radius: u8,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+enum Shape {
+    Empty,
+    Circle(Circle),
+    Line(u8, u8),
+    Square { side: u8 },
+}
+
+/// A value that always fails to serialize.
+#[derive(Debug)]
+struct Failing;
+
+impl Serialize for Failing {
+    fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom("failing value"))
+    }
+}
+
+#[derive(Serialize, Debug)]
+enum Wrapper {
+    Failing(Failing),
+}
+
+/// Checks the encoding of the value with the given representation and that it decodes again.
+fn roundtrip_repr(value: &Shape, repr: EnumRepresentation, diag: &str) {
+    let encode_options = EncodeOptions {
+        enum_representation: repr,
+        ..Default::default()
+    };
+    let decode_options = DecodeOptions {
+        enum_representation: repr,
+        ..Default::default()
+    };
+    let encoded = to_vec_with_options(value, &encode_options).unwrap();
+    assert_eq!(to_diag(&encoded).unwrap(), diag);
+    let decoded: Shape = from_slice_with_options(&encoded, &decode_options).unwrap();
+    assert_eq!(&decoded, value);
+}
+
+#[test]
+fn test_enum_representations() {
+    let circle = Shape::Circle(Circle { radius: 2 });
+    let line = Shape::Line(1, 2);
+    let square = Shape::Square { side: 3 };
+
+    let repr = EnumRepresentation::IndexedUnit;
+    roundtrip_repr(&Shape::Empty, repr, "0");
+    roundtrip_repr(&circle, repr, r#"{"Circle": {"radius": 2}}"#);
+    roundtrip_repr(&line, repr, r#"{"Line": [1, 2]}"#);
+    roundtrip_repr(&square, repr, r#"{"Square": {"side": 3}}"#);
+
+    let repr = EnumRepresentation::List;
+    roundtrip_repr(&Shape::Empty, repr, r#""Empty""#);
+    roundtrip_repr(&circle, repr, r#"["Circle", {"radius": 2}]"#);
+    roundtrip_repr(&line, repr, r#"["Line", [1, 2]]"#);
+    roundtrip_repr(&square, repr, r#"["Square", {"side": 3}]"#);
+
+    let repr = EnumRepresentation::InternallyTagged("type");
+    roundtrip_repr(&Shape::Empty, repr, r#"{"type": "Empty"}"#);
+    roundtrip_repr(&circle, repr, r#"{"type": "Circle", "radius": 2}"#);
+    roundtrip_repr(&square, repr, r#"{"side": 3, "type": "Square"}"#);
+    let options = EncodeOptions {
+        enum_representation: repr,
+        ..Default::default()
+    };
+    assert!(to_vec_with_options(&line, &options).is_err());
+    assert!(to_vec_with_options(&Bar::Number(1), &options).is_err());
+    // Errors of the variant value are passed through.
+    let error = to_vec_with_options(&Wrapper::Failing(Failing), &options).unwrap_err();
+    assert!(error.to_string().contains("failing value"));
+
+    // Decoding only accepts the configured representation.
+    let options = DecodeOptions {
+        enum_representation: EnumRepresentation::InternallyTagged("kind"),
+        ..Default::default()
+    };
+    let tagged = from_diag(r#"{"type": "Square", "side": 3}"#).unwrap();
+    assert!(from_slice_with_options::<Shape>(&tagged, &options).is_err());
+    assert!(from_slice::<Shape>(&tagged).is_err());
+    assert!(from_slice::<Shape>(&from_diag("1").unwrap()).is_err());
+    assert!(from_slice::<Shape>(&from_diag("4").unwrap()).is_err());
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+enum Event {
+    Moved { shape: Shape, by: u8 },
+    Resized(BTreeMap<String, u8>),
+}
+
+#[test]
+fn test_internally_tagged_canonical_order() {
+    let encode_options = EncodeOptions {
+        enum_representation: EnumRepresentation::InternallyTagged("type"),
+        ..Default::default()
+    };
+    let decode_options = DecodeOptions {
+        enum_representation: EnumRepresentation::InternallyTagged("type"),
+        ..Default::default()
+    };
+
+    // The tag is sorted between the other keys, also in nested variants.
+    let moved = Event::Moved {
+        shape: Shape::Square { side: 3 },
+        by: 1,
+    };
+    let diag = r#"{"by": 1, "type": "Moved", "shape": {"side": 3, "type": "Square"}}"#;
+    let encoded = to_vec_with_options(&moved, &encode_options).unwrap();
+    assert_eq!(to_diag(&encoded).unwrap(), diag);
+    let decoded: Event =
+        from_slice_with_options(&from_diag(diag).unwrap(), &decode_options).unwrap();
+    assert_eq!(decoded, moved);
+    let decoded: Event = from_reader_with_options(&encoded[..], &decode_options).unwrap();
+    assert_eq!(decoded, moved);
+
+    let resized = Event::Resized(BTreeMap::from([("width".to_string(), 2)]));
+    let diag = r#"{"type": "Resized", "width": 2}"#;
+    let encoded = to_vec_with_options(&resized, &encode_options).unwrap();
+    assert_eq!(to_diag(&encoded).unwrap(), diag);
+    let decoded: Event = from_slice_with_options(&encoded, &decode_options).unwrap();
+    assert_eq!(decoded, resized);
+
+    // The tag is found at any position, also in maps of indefinite length.
+    let decoded: Event = from_slice_with_options(
+        &from_diag(r#"{_ "shape": {"type": "Square", "side": 3}, "type": "Moved", "by": 1}"#)
+            .unwrap(),
+        &decode_options,
+    )
+    .unwrap();
+    assert_eq!(decoded, moved);
+    let missing = from_diag(r#"{"by": 1, "shape": "Empty"}"#).unwrap();
+    let error = from_slice_with_options::<Event>(&missing, &decode_options).unwrap_err();
+    assert!(error.to_string().contains("missing field `type`"));
+
+    // A field with the name of the tag can't be encoded.
+    let mut conflicting = BTreeMap::new();
+    conflicting.insert("type".to_string(), 1);
+    assert!(to_vec_with_options(&Event::Resized(conflicting), &encode_options).is_err());
+}
+
+#[test]
+fn test_repr_enum_index() {
+    let options = EncodeOptions {
+        enum_representation: EnumRepresentation::IndexedUnit,
+        ..Default::default()
+    };
+    let repr_enum_struct = ReprEnumStruct { e: ReprEnum::B };
+    let encoded = to_vec_with_options(&repr_enum_struct, &options).unwrap();
+    assert_eq!(to_diag(&encoded).unwrap(), r#"{"e": 1}"#);
+
+    // Integers are resolved to the variant at that index.
+    let options = DecodeOptions {
+        enum_representation: EnumRepresentation::IndexedUnit,
+        ..Default::default()
+    };
+    let re: ReprEnumStruct = from_slice_with_options(&encoded, &options).unwrap();
+    assert_eq!(re, repr_enum_struct);
+    let re: ReprEnum = from_slice_with_options(&[0x00], &options).unwrap();
+    assert_eq!(re, ReprEnum::A);
+    assert!(from_slice_with_options::<ReprEnum>(&[0x02], &options).is_err());
+    assert!(from_slice_with_options::<ReprEnum>(&[0x20], &options).is_err());
+
+    // Other representations don't accept integers.
+    assert!(from_slice::<ReprEnumStruct>(&encoded).is_err());
+    let options = DecodeOptions {
+        enum_representation: EnumRepresentation::List,
+        ..Default::default()
+    };
+    assert!(from_slice_with_options::<ReprEnum>(&[0x00], &options).is_err());
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[repr(u16)]
+enum DiscriminantEnum {
+    A = 2,
+    B = 7,
+}
+
+#[test]
+fn test_repr_enum_index_ignores_discriminants() {
+    let options = EncodeOptions {
+        enum_representation: EnumRepresentation::IndexedUnit,
+        ..Default::default()
+    };
+    let encoded = to_vec_with_options(&DiscriminantEnum::A, &options).unwrap();
+    assert_eq!(to_diag(&encoded).unwrap(), "0");
+    let encoded = to_vec_with_options(&DiscriminantEnum::B, &options).unwrap();
+    assert_eq!(to_diag(&encoded).unwrap(), "1");
+    let options = DecodeOptions {
+        enum_representation: EnumRepresentation::IndexedUnit,
+        ..Default::default()
+    };
+    let decoded: DiscriminantEnum = from_slice_with_options(&encoded, &options).unwrap();
+    assert_eq!(decoded, DiscriminantEnum::B);
+    assert!(from_slice_with_options::<DiscriminantEnum>(&[0x07], &options).is_err());
+}
@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use cid::Cid;
+use libipld_core::ipld::Ipld;
+use serde_ipld_dagcbor::diag::from_diag;
+use serde_ipld_dagcbor::explain::{explain, Violation};
+use serde_ipld_dagcbor::DecodeError;
+
+const CID: &str = "bafyreih4jspvxgh5xrp3wboocaajx5ym4ky7ok3gs5dsdkizgnegtanfxu";
+
+fn violations(diag: &str) -> Vec<(usize, Violation)> {
+    let explanation = explain(&from_diag(diag).unwrap());
+    assert!(explanation.error.is_none(), "{}", explanation);
+    explanation
+        .violations()
+        .map(|(offset, violation)| (offset, violation.clone()))
+        .collect()
+}
+
+#[test]
+fn test_valid() {
+    let mut map = BTreeMap::new();
+    map.insert("link".to_string(), Ipld::Link(Cid::try_from(CID).unwrap()));
+    map.insert(
+        "list".to_string(),
+        Ipld::List(vec![Ipld::Integer(-1000), Ipld::Float(0.5)]),
+    );
+    map.insert("bytes".to_string(), Ipld::Bytes(vec![1, 2, 3]));
+    map.insert("a".to_string(), Ipld::Null);
+    let cbor = serde_ipld_dagcbor::to_vec(&Ipld::Map(map)).unwrap();
+
+    let explanation = explain(&cbor);
+    assert!(explanation.is_valid(), "{}", explanation);
+    let dump = explanation.to_string();
+    assert!(dump.contains(&format!("link({})", CID)));
+    assert!(dump.contains("negative(-1000)"));
+    assert!(dump.contains("float(0.5)"));
+    assert!(dump.contains("\"bytes\""));
+    // Every byte belongs to exactly one line.
+    let len: usize = explanation.lines.iter().map(|line| line.bytes.len()).sum();
+    assert_eq!(len, cbor.len());
+}
+
+#[test]
+fn test_non_minimal() {
+    // 23 encoded in one additional byte.
+    assert_eq!(
+        explain(b"\x18\x17").violations().collect::<Vec<_>>(),
+        [(0, &Violation::NonMinimalArgument)]
+    );
+    // Array length encoded in two additional bytes.
+    assert_eq!(
+        explain(b"\x81\x99\x00\x01\x01")
+            .violations()
+            .collect::<Vec<_>>(),
+        [(1, &Violation::NonMinimalArgument)]
+    );
+    assert!(explain(b"\x1b\x00\x00\x00\x01\x00\x00\x00\x00").is_valid());
+}
+
+#[test]
+fn test_map_keys() {
+    assert_eq!(violations(r#"{"b": 1, "aa": 2}"#), []);
+    assert_eq!(
+        violations(r#"{"aa": 1, "b": 2}"#),
+        [(5, Violation::UnsortedKey)]
+    );
+    assert_eq!(
+        violations(r#"{"a": 1, "a": 2}"#),
+        [(4, Violation::DuplicateKey)]
+    );
+    assert_eq!(violations(r#"{1: 2}"#), [(1, Violation::NonStringKey)]);
+}
+
+#[test]
+fn test_other_violations() {
+    assert_eq!(
+        violations("[_ 1.5_2, undefined, NaN, 1(1), 42(h'01')]"),
+        [
+            (0, Violation::IndefiniteLength),
+            (1, Violation::NonDoublePrecisionFloat),
+            (6, Violation::UnsupportedSimpleValue),
+            (7, Violation::NonFiniteFloat),
+            (16, Violation::UnsupportedTag(1)),
+            (18, Violation::InvalidCid),
+        ]
+    );
+    assert_eq!(
+        violations(r#"(_ "a", "b")"#),
+        [(0, Violation::IndefiniteLength)]
+    );
+    assert_eq!(
+        explain(b"\x62\xff\xfe").violations().collect::<Vec<_>>(),
+        [(1, &Violation::InvalidUtf8)]
+    );
+}
+
+#[test]
+fn test_errors() {
+    let explanation = explain(b"\x82\x01");
+    assert!(matches!(explanation.error, Some((2, DecodeError::Eof))));
+    assert_eq!(explanation.lines.len(), 2);
+    assert!(explanation.to_string().ends_with("00000002  !! Eof\n"));
+
+    assert!(matches!(
+        explain(b"\x01\x02").error,
+        Some((1, DecodeError::TrailingData))
+    ));
+    assert!(matches!(
+        explain(b"\x1c").error,
+        Some((1, DecodeError::Unsupported { byte: 0x1c }))
+    ));
+    assert!(matches!(
+        explain(&[0x81; 1000]).error,
+        Some((_, DecodeError::DepthLimit))
+    ));
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "hash")]
+
+use std::collections::BTreeMap;
+
+use cid::multihash::{Blake2b256, Code, MultihashDigest, Sha2_256};
+use cid::Cid;
+use serde_derive::Serialize;
+use serde_ipld_dagcbor::{to_cid, to_vec, to_vec_with_cid, DAG_CBOR_CODEC};
+
+#[derive(Serialize)]
+struct Node {
+    name: String,
+    links: Vec<Cid>,
+    attributes: BTreeMap<String, u64>,
+}
+
+fn node() -> Node {
+    let link = to_cid::<Sha2_256, _>(&"leaf").unwrap();
+    let mut attributes = BTreeMap::new();
+    attributes.insert("size".to_string(), 4);
+    attributes.insert("mode".to_string(), 0o644);
+    Node {
+        name: "node".to_string(),
+        links: vec![link, link],
+        attributes,
+    }
+}
+
+#[test]
+fn test_to_cid() {
+    let encoded = to_vec(&node()).unwrap();
+    let expected = Cid::new_v1(DAG_CBOR_CODEC, Code::Sha2_256.digest(&encoded));
+    assert_eq!(to_cid::<Sha2_256, _>(&node()).unwrap(), expected);
+
+    let expected = Cid::new_v1(DAG_CBOR_CODEC, Code::Blake2b256.digest(&encoded));
+    assert_eq!(to_cid::<Blake2b256, _>(&node()).unwrap(), expected);
+}
+
+#[test]
+fn test_to_cid_known_value() {
+    // The empty map `{}`.
+    let cid = to_cid::<Sha2_256, _>(&BTreeMap::<String, u8>::new()).unwrap();
+    assert_eq!(
+        cid.to_string(),
+        "bafyreigbtj4x7ip5legnfznufuopl4sg4knzc2cof6duas4b3q2fy6swua"
+    );
+}
+
+#[test]
+fn test_to_vec_with_cid() {
+    let (encoded, cid) = to_vec_with_cid::<Sha2_256, _>(&node()).unwrap();
+    assert_eq!(encoded, to_vec(&node()).unwrap());
+    assert_eq!(cid, to_cid::<Sha2_256, _>(&node()).unwrap());
+    assert_eq!(cid.codec(), DAG_CBOR_CODEC);
+}
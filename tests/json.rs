@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use cid::Cid;
+use libipld_core::ipld::Ipld;
+use serde_bytes::ByteBuf;
+use serde_derive::{Deserialize, Serialize};
+use serde_ipld_dagcbor::json;
+
+const CID: &str = "bafyreih4jspvxgh5xrp3wboocaajx5ym4ky7ok3gs5dsdkizgnegtanfxu";
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Node {
+    name: String,
+    data: ByteBuf,
+    link: Option<Cid>,
+    size: u64,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+enum Shape {
+    Empty,
+    Circle(f64),
+    Point(i32, i32),
+    Rect { width: u32, height: u32 },
+}
+
+#[test]
+fn test_links_and_bytes() {
+    let node = Node {
+        name: "root".to_string(),
+        data: ByteBuf::from(b"hello".to_vec()),
+        link: Some(Cid::try_from(CID).unwrap()),
+        size: 3,
+    };
+    let encoded = json::to_vec(&node).unwrap();
+    let expected = format!(
+        r#"{{"data":{{"/":{{"bytes":"aGVsbG8"}}}},"link":{{"/":"{}"}},"name":"root","size":3}}"#,
+        CID
+    );
+    assert_eq!(String::from_utf8(encoded.clone()).unwrap(), expected);
+
+    let decoded: Node = json::from_slice(&encoded).unwrap();
+    assert_eq!(decoded, node);
+}
+
+#[test]
+fn test_base64() {
+    for (bytes, encoded) in [
+        (&b""[..], r#"{"/":{"bytes":""}}"#),
+        (b"f", r#"{"/":{"bytes":"Zg"}}"#),
+        (b"fo", r#"{"/":{"bytes":"Zm8"}}"#),
+        (b"foo", r#"{"/":{"bytes":"Zm9v"}}"#),
+        (b"foob", r#"{"/":{"bytes":"Zm9vYg"}}"#),
+        (&[0xfb, 0xff], r#"{"/":{"bytes":"+/8"}}"#),
+    ] {
+        assert_eq!(
+            json::to_vec(&ByteBuf::from(bytes)).unwrap(),
+            encoded.as_bytes()
+        );
+        let decoded: ByteBuf = json::from_slice(encoded.as_bytes()).unwrap();
+        assert_eq!(decoded, bytes);
+    }
+
+    // Padding and non-zero trailing bits are not allowed.
+    assert!(json::from_slice::<ByteBuf>(br#"{"/":{"bytes":"Zg=="}}"#).is_err());
+    assert!(json::from_slice::<ByteBuf>(br#"{"/":{"bytes":"Zh"}}"#).is_err());
+}
+
+#[test]
+fn test_sorted_keys() {
+    let mut map = BTreeMap::new();
+    map.insert("b", 1);
+    map.insert("aa", 2);
+    map.insert("a", 3);
+    // Unlike DAG-CBOR, keys are sorted bytewise and not by length first.
+    assert_eq!(json::to_vec(&map).unwrap(), br#"{"a":3,"aa":2,"b":1}"#);
+
+    let ipld = Ipld::Map(
+        map.into_iter()
+            .map(|(key, value)| (key.to_string(), Ipld::Integer(value)))
+            .collect(),
+    );
+    let cbor = serde_ipld_dagcbor::to_vec(&ipld).unwrap();
+    let from_cbor: Ipld = serde_ipld_dagcbor::from_slice(&cbor).unwrap();
+    assert_eq!(
+        json::to_vec(&from_cbor).unwrap(),
+        br#"{"a":3,"aa":2,"b":1}"#
+    );
+}
+
+#[test]
+fn test_reserved_key() {
+    let mut map = BTreeMap::new();
+    map.insert("/", "value");
+    assert!(json::to_vec(&map).is_err());
+
+    assert!(json::from_slice::<Ipld>(br#"{"/":1}"#).is_err());
+    assert!(json::from_slice::<Ipld>(br#"{"/":"not a cid"}"#).is_err());
+    assert!(json::from_slice::<Ipld>(br#"{"/":{"other":"Zg"}}"#).is_err());
+    let link_with_extra_key = format!(r#"{{"/":"{}","a":1}}"#, CID);
+    assert!(json::from_slice::<Ipld>(link_with_extra_key.as_bytes()).is_err());
+
+    // Other keys starting with a slash are fine.
+    let decoded: BTreeMap<String, u8> = json::from_slice(br#"{"//":1}"#).unwrap();
+    assert_eq!(decoded["//"], 1);
+}
+
+#[test]
+fn test_ipld_roundtrip() {
+    let encoded = format!(
+        r#"{{"bytes":{{"/":{{"bytes":"AAEC"}}}},"false":false,"float":-1.5,"int":-7,"link":{{"/":"{}"}},"list":[1,[],{{}}],"null":null,"string":"a\"b"}}"#,
+        CID
+    );
+    let ipld: Ipld = json::from_slice(encoded.as_bytes()).unwrap();
+    match &ipld {
+        Ipld::Map(map) => {
+            assert_eq!(map["bytes"], Ipld::Bytes(vec![0, 1, 2]));
+            assert_eq!(map["float"], Ipld::Float(-1.5));
+            assert_eq!(map["int"], Ipld::Integer(-7));
+            assert_eq!(map["link"], Ipld::Link(Cid::try_from(CID).unwrap()));
+            assert_eq!(map["null"], Ipld::Null);
+        }
+        other => panic!("unexpected value: {:?}", other),
+    }
+    assert_eq!(json::to_vec(&ipld).unwrap(), encoded.as_bytes());
+
+    // The same data model value can be encoded as DAG-CBOR.
+    let cbor = serde_ipld_dagcbor::to_vec(&ipld).unwrap();
+    let from_cbor: Ipld = serde_ipld_dagcbor::from_slice(&cbor).unwrap();
+    assert_eq!(from_cbor, ipld);
+}
+
+#[test]
+fn test_numbers() {
+    assert_eq!(json::to_vec(&1.0f64).unwrap(), b"1.0");
+    assert_eq!(json::to_vec(&1e100f64).unwrap(), b"1e100");
+    assert_eq!(json::to_vec(&u64::MAX).unwrap(), b"18446744073709551615");
+    assert!(json::to_vec(&f64::NAN).is_err());
+    assert!(json::to_vec(&f64::INFINITY).is_err());
+
+    assert_eq!(json::from_slice::<f64>(b"1.0").unwrap(), 1.0);
+    assert_eq!(json::from_slice::<f64>(b"-2.5e-3").unwrap(), -2.5e-3);
+    assert_eq!(json::from_slice::<i64>(b"-42").unwrap(), -42);
+    assert_eq!(json::from_slice::<Ipld>(b"1.0").unwrap(), Ipld::Float(1.0));
+    assert_eq!(json::from_slice::<Ipld>(b"1").unwrap(), Ipld::Integer(1));
+    for invalid in [&b"01"[..], b"1.", b".5", b"-", b"1e", b"+1"] {
+        assert!(json::from_slice::<Ipld>(invalid).is_err(), "{:?}", invalid);
+    }
+}
+
+#[test]
+fn test_strings() {
+    let string = "quote \" backslash \\ newline \n control \u{1} emoji \u{1f980}";
+    let encoded = json::to_vec(&string).unwrap();
+    assert_eq!(
+        encoded,
+        "\"quote \\\" backslash \\\\ newline \\n control \\u0001 emoji \u{1f980}\"".as_bytes()
+    );
+    assert_eq!(json::from_slice::<String>(&encoded).unwrap(), string);
+
+    // Escaped surrogate pairs.
+    assert_eq!(
+        json::from_slice::<String>(br#""\ud83e\udd80\/""#).unwrap(),
+        "\u{1f980}/"
+    );
+    assert!(json::from_slice::<String>(br#""\ud83e""#).is_err());
+    assert!(json::from_slice::<String>(b"\"\n\"").is_err());
+
+    // Strings without escapes are borrowed.
+    let borrowed: &str = json::from_slice(b" \"borrowed\" ").unwrap();
+    assert_eq!(borrowed, "borrowed");
+}
+
+#[test]
+fn test_enums() {
+    for (shape, encoded) in [
+        (Shape::Empty, r#""Empty""#),
+        (Shape::Circle(0.5), r#"{"Circle":0.5}"#),
+        (Shape::Point(1, -2), r#"{"Point":[1,-2]}"#),
+        (
+            Shape::Rect {
+                width: 3,
+                height: 4,
+            },
+            r#"{"Rect":{"height":4,"width":3}}"#,
+        ),
+    ] {
+        assert_eq!(json::to_vec(&shape).unwrap(), encoded.as_bytes());
+        assert_eq!(
+            json::from_slice::<Shape>(encoded.as_bytes()).unwrap(),
+            shape
+        );
+    }
+}
+
+#[test]
+fn test_whitespace_and_trailing_data() {
+    let decoded: BTreeMap<String, Vec<u8>> =
+        json::from_slice(b" { \"a\" : [ 1 , 2 ] ,\n\t\"b\":[] } ").unwrap();
+    assert_eq!(decoded["a"], [1, 2]);
+    assert!(decoded["b"].is_empty());
+
+    assert!(json::from_slice::<Vec<u8>>(b"[1,]").is_err());
+    assert!(json::from_slice::<Vec<u8>>(b"[1] 2").is_err());
+    assert!(json::from_slice::<(u8, u8)>(b"[1,2,3]").is_err());
+
+    let decoded: Vec<u8> = json::from_reader(&b"[1, 2]"[..]).unwrap();
+    assert_eq!(decoded, [1, 2]);
+}
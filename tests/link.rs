@@ -0,0 +1,80 @@
+#![cfg(feature = "hash")]
+
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+use cid::Cid;
+use serde_derive::{Deserialize, Serialize};
+use serde_ipld_dagcbor::blockstore::{Blockstore, MemoryBlockstore};
+use serde_ipld_dagcbor::link::Link;
+use serde_ipld_dagcbor::{from_slice, json, to_vec, BlockstoreError};
+
+const CID: &str = "bafyreih4jspvxgh5xrp3wboocaajx5ym4ky7ok3gs5dsdkizgnegtanfxu";
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Leaf {
+    value: u64,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Tree {
+    leaves: Vec<Link<Leaf>>,
+}
+
+#[test]
+fn test_encoded_like_cid() {
+    let cid = Cid::try_from(CID).unwrap();
+    let link: Link<Leaf> = Link::new(cid);
+    assert_eq!(to_vec(&link).unwrap(), to_vec(&cid).unwrap());
+    assert_eq!(
+        from_slice::<Link<Leaf>>(&to_vec(&cid).unwrap()).unwrap(),
+        link
+    );
+    assert_eq!(json::to_vec(&link).unwrap(), json::to_vec(&cid).unwrap());
+    assert_eq!(
+        json::from_slice::<Link<Leaf>>(&json::to_vec(&cid).unwrap()).unwrap(),
+        link
+    );
+
+    // Only CIDs can be decoded as links.
+    assert!(from_slice::<Link<Leaf>>(&to_vec(&b"bytes".to_vec()).unwrap()).is_err());
+    assert!(from_slice::<Link<Leaf>>(&to_vec(&1).unwrap()).is_err());
+
+    assert_eq!(link.to_string(), CID);
+    assert_eq!(Cid::from(link), cid);
+    assert_eq!(*Link::<Leaf>::from(cid).cid(), cid);
+}
+
+#[test]
+fn test_load_and_store() {
+    let mut store = MemoryBlockstore::new();
+    let leaves = (0..3)
+        .map(|value| Link::store(&Leaf { value }, &mut store).unwrap())
+        .collect();
+    let tree = Link::store(&Tree { leaves }, &mut store).unwrap();
+    assert_eq!(store.len(), 4);
+
+    let tree = tree.load(&store).unwrap();
+    let values: Vec<u64> = tree
+        .leaves
+        .iter()
+        .map(|leaf| leaf.load(&store).unwrap().value)
+        .collect();
+    assert_eq!(values, [0, 1, 2]);
+
+    // Links can be used as keys, no matter what they point to.
+    let unique: HashSet<_> = tree.leaves.iter().copied().collect();
+    assert_eq!(unique.len(), 3);
+
+    store.delete(tree.leaves[0].cid()).unwrap();
+    assert!(matches!(
+        tree.leaves[0].load(&store),
+        Err(BlockstoreError::NotFound(_))
+    ));
+    // Loading a block of a different type fails.
+    let wrong: Link<Tree> = Link::new(*tree.leaves[1].cid());
+    assert!(matches!(
+        wrong.load(&store),
+        Err(BlockstoreError::Decode(_))
+    ));
+}
@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+
+use serde_derive::{Deserialize, Serialize};
+use serde_ipld_dagcbor::{
+    de::{from_reader_with_options, from_slice_with_options, DecodeOptions},
+    diag::to_diag,
+    from_slice,
+    ser::{to_vec_with_options, EncodeOptions},
+    to_vec, DecodeError,
+};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Line { from: Point, to: Point },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Drawing {
+    name: String,
+    points: BTreeMap<String, Point>,
+    shapes: Vec<Shape>,
+}
+
+#[test]
+fn test_struct_tuples() {
+    let encode_options = EncodeOptions {
+        struct_tuples: true,
+        ..Default::default()
+    };
+    let decode_options = DecodeOptions {
+        struct_tuples: true,
+        ..Default::default()
+    };
+
+    let drawing = Drawing {
+        name: "a".to_string(),
+        points: BTreeMap::from([("origin".to_string(), Point { x: 0, y: 0 })]),
+        shapes: vec![Shape::Line {
+            from: Point { x: 1, y: 2 },
+            to: Point { x: -3, y: 4 },
+        }],
+    };
+    let encoded = to_vec_with_options(&drawing, &encode_options).unwrap();
+    assert_eq!(
+        to_diag(&encoded).unwrap(),
+        r#"["a", {"origin": [0, 0]}, [{"Line": [[1, 2], [-3, 4]]}]]"#
+    );
+    let decoded: Drawing = from_slice_with_options(&encoded, &decode_options).unwrap();
+    assert_eq!(decoded, drawing);
+
+    // The same types still use maps without the option.
+    let encoded = to_vec(&drawing).unwrap();
+    assert_eq!(
+        to_diag(&encoded).unwrap(),
+        r#"{"name": "a", "points": {"origin": {"x": 0, "y": 0}}, "shapes": [{"Line": {"from": {"x": 1, "y": 2}, "to": {"x": -3, "y": 4}}}]}"#
+    );
+    assert_eq!(from_slice::<Drawing>(&encoded).unwrap(), drawing);
+
+    // The number of values must match the number of fields.
+    let encoded = to_vec(&(1, 2, 3)).unwrap();
+    assert!(matches!(
+        from_slice_with_options::<Point>(&encoded, &decode_options),
+        Err(DecodeError::RequireLength { .. })
+    ));
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Profile {
+    name: String,
+    email: Option<String>,
+    // Without a default for missing fields, like types with custom implementations.
+    #[serde(deserialize_with = "Option::deserialize")]
+    avatar: Option<Point>,
+    friends: Vec<Option<String>>,
+}
+
+#[test]
+fn test_omit_null_fields() {
+    let encode_options = EncodeOptions {
+        omit_null_fields: true,
+        ..Default::default()
+    };
+    let decode_options = DecodeOptions {
+        missing_fields_as_none: true,
+        ..Default::default()
+    };
+
+    let mut profile = Profile {
+        name: "a".to_string(),
+        email: None,
+        avatar: None,
+        friends: vec![None, Some("b".to_string())],
+    };
+    let encoded = to_vec_with_options(&profile, &encode_options).unwrap();
+    // Nulls that aren't struct fields are kept.
+    assert_eq!(
+        to_diag(&encoded).unwrap(),
+        r#"{"name": "a", "friends": [null, "b"]}"#
+    );
+    let decoded: Profile = from_slice_with_options(&encoded, &decode_options).unwrap();
+    assert_eq!(decoded, profile);
+    assert!(from_slice::<Profile>(&encoded).is_err());
+
+    profile.avatar = Some(Point { x: 1, y: 2 });
+    let encoded = to_vec_with_options(&profile, &encode_options).unwrap();
+    assert_eq!(
+        to_diag(&encoded).unwrap(),
+        r#"{"name": "a", "avatar": {"x": 1, "y": 2}, "friends": [null, "b"]}"#
+    );
+    let decoded: Profile = from_slice_with_options(&encoded, &decode_options).unwrap();
+    assert_eq!(decoded, profile);
+
+    // Fields that aren't optional are still required.
+    let encoded = to_vec(&BTreeMap::from([("x", 1)])).unwrap();
+    assert!(matches!(
+        from_slice_with_options::<Point>(&encoded, &decode_options),
+        Err(DecodeError::Msg(msg)) if msg == "missing field `y`"
+    ));
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Counter {
+    #[serde(default)]
+    count: u32,
+    #[serde(alias = "bb")]
+    a: Option<u32>,
+    #[serde(deserialize_with = "Option::deserialize")]
+    b: Option<u32>,
+}
+
+#[test]
+fn test_missing_fields_as_none_defaults_and_aliases() {
+    let decode_options = DecodeOptions {
+        missing_fields_as_none: true,
+        ..Default::default()
+    };
+
+    // Defaults of fields that aren't optional apply.
+    let encoded = to_vec(&BTreeMap::from([("a", 1), ("b", 2)])).unwrap();
+    let decoded: Counter = from_slice_with_options(&encoded, &decode_options).unwrap();
+    assert_eq!(
+        decoded,
+        Counter {
+            count: 0,
+            a: Some(1),
+            b: Some(2)
+        }
+    );
+
+    // Fields given by their alias aren't missing.
+    let encoded = to_vec(&BTreeMap::from([("bb", 1), ("count", 3)])).unwrap();
+    let decoded: Counter = from_slice_with_options(&encoded, &decode_options).unwrap();
+    assert_eq!(
+        decoded,
+        Counter {
+            count: 3,
+            a: Some(1),
+            b: None
+        }
+    );
+
+    // Only the instances that miss a field decode it as null.
+    let encoded = to_vec(&vec![
+        BTreeMap::from([("bb", 1), ("b", 2)]),
+        BTreeMap::from([("a", 3)]),
+    ])
+    .unwrap();
+    let expected = vec![
+        Counter {
+            count: 0,
+            a: Some(1),
+            b: Some(2),
+        },
+        Counter {
+            count: 0,
+            a: Some(3),
+            b: None,
+        },
+    ];
+    let decoded: Vec<Counter> = from_slice_with_options(&encoded, &decode_options).unwrap();
+    assert_eq!(decoded, expected);
+    let decoded: Vec<Counter> = from_reader_with_options(&encoded[..], &decode_options).unwrap();
+    assert_eq!(decoded, expected);
+}
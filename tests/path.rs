@@ -0,0 +1,98 @@
+#![cfg(feature = "hash")]
+
+use cid::multihash::{Code, MultihashDigest};
+use cid::Cid;
+use serde_ipld_dagcbor::blockstore::{Blockstore, MemoryBlockstore};
+use serde_ipld_dagcbor::diag::from_diag;
+use serde_ipld_dagcbor::node::{Kind, Node};
+use serde_ipld_dagcbor::path::{resolve, Path};
+use serde_ipld_dagcbor::{BlockstoreError, TraversalError, DAG_CBOR_CODEC};
+
+fn put(store: &mut MemoryBlockstore, diag: &str) -> Cid {
+    let data = from_diag(diag).unwrap();
+    let cid = Cid::new_v1(DAG_CBOR_CODEC, Code::Sha2_256.digest(&data));
+    store.put(&cid, &data).unwrap();
+    cid
+}
+
+#[test]
+fn test_node() {
+    let data = from_diag(r#"{"a": [_ 1, -2.5, true], "b": h'0102', "c": null}"#).unwrap();
+    let node = Node::new(&data).unwrap();
+    assert_eq!(node.kind(), Kind::Map);
+    let keys: Vec<_> = node
+        .entries()
+        .unwrap()
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect();
+    assert_eq!(keys, ["a", "b", "c"]);
+
+    let list = node.get_field("a").unwrap().unwrap();
+    let elements = list.elements().unwrap();
+    assert_eq!(elements[0].as_integer(), Some(1));
+    assert_eq!(elements[1].as_float(), Some(-2.5));
+    assert_eq!(elements[2].as_bool(), Some(true));
+    assert_eq!(list.get_index(3).unwrap(), None);
+    assert_eq!(
+        node.get_field("b").unwrap().unwrap().as_bytes(),
+        Some(&[1, 2][..])
+    );
+    assert_eq!(node.get_field("c").unwrap().unwrap().kind(), Kind::Null);
+    assert_eq!(node.get_field("d").unwrap(), None);
+    assert_eq!(node.get_field("c").unwrap().unwrap().as_str(), None);
+    assert!(node.get_index(0).is_err());
+
+    assert!(Node::new(b"\x82\x01").is_err());
+    assert!(Node::new(b"\x01\x02").is_err());
+}
+
+#[test]
+fn test_resolve_across_blocks() {
+    let mut store = MemoryBlockstore::new();
+    let file = put(&mut store, r#"{"name": "file", "size": 3}"#);
+    let root = put(
+        &mut store,
+        &format!(r#"{{"links": [{{"Hash": 42({}), "Tsize": 3}}]}}"#, file),
+    );
+
+    let resolved = resolve(&store, &root, &Path::from("links/0/Hash/name")).unwrap();
+    assert_eq!(resolved.node().as_str(), Some("file"));
+    assert_eq!(resolved.cid, file);
+    assert_eq!(resolved.blocks, [root, file]);
+
+    // A link at the end is not followed.
+    let resolved = resolve(&store, &root, &Path::from("/links/0/Hash/")).unwrap();
+    assert_eq!(resolved.node().as_link(), Some(file));
+    assert_eq!(resolved.blocks, [root]);
+
+    let resolved = resolve(&store, &root, &Path::default()).unwrap();
+    assert_eq!(resolved.node().kind(), Kind::Map);
+    assert_eq!(resolved.cid, root);
+}
+
+#[test]
+fn test_resolve_errors() {
+    let mut store = MemoryBlockstore::new();
+    let missing = Cid::new_v1(DAG_CBOR_CODEC, Code::Sha2_256.digest(b"missing"));
+    let root = put(
+        &mut store,
+        &format!(r#"{{"a": [1], "b": 42({})}}"#, missing),
+    );
+
+    for (path, not_found) in [
+        ("a/1", "a/1"),
+        ("a/x", "a/x"),
+        ("a/0/c", "a/0/c"),
+        ("c/d", "c"),
+    ] {
+        match resolve(&store, &root, &Path::from(path)) {
+            Err(TraversalError::NotFound(path)) => assert_eq!(path.to_string(), not_found),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+    assert!(matches!(
+        resolve(&store, &root, &Path::from("b/c")),
+        Err(TraversalError::Blockstore(BlockstoreError::NotFound(cid))) if cid == missing
+    ));
+}
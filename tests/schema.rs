@@ -0,0 +1,382 @@
+#![cfg(feature = "hash")]
+
+use std::collections::BTreeMap;
+
+use serde_derive::Serialize;
+use serde_ipld_dagcbor::blockstore::{Blockstore, MemoryBlockstore};
+use serde_ipld_dagcbor::diag::from_diag;
+use serde_ipld_dagcbor::link::Link;
+use serde_ipld_dagcbor::schema::{Schema, StructRepresentation, Type, TypeTerm};
+use serde_ipld_dagcbor::SchemaError;
+
+const SCHEMA: &str = r#"
+# A file system like structure.
+type Directory struct {
+    name String (rename "n")
+    entries {String:Entry}
+    parent optional nullable &Directory
+}
+
+type Entry union {
+    | &Directory link
+    | File list
+    | Int int
+} representation kinded
+
+type File struct {
+    size Int
+    mode Mode
+    tags optional [String]
+} representation tuple
+
+type Mode enum {
+    | Read ("r")
+    | Write ("w")
+}
+
+type Point struct {
+    x Int
+    y Int
+} representation stringjoin { join "," }
+
+type Labels {String:Float} representation stringpairs { innerDelim "=" entryDelim "&" }
+
+type Pairs struct {
+    a Bool
+    b optional String
+} representation listpairs
+
+type Shape union {
+    | Point "point"
+    | Labels "labels"
+} representation keyed
+
+type Message union {
+    | Ping "ping"
+    | Pong "pong"
+} representation envelope { discriminantKey "type" contentKey "body" }
+
+type Event union {
+    | Ping "ping"
+    | Pong "pong"
+} representation inline { discriminantKey "type" }
+
+type Ping struct {
+    seq Int
+}
+
+type Pong struct {
+    seq Int
+    latency optional Float
+}
+
+type Level enum {
+    | Low ("1")
+    | High ("10")
+} representation int
+"#;
+
+fn schema() -> Schema {
+    Schema::parse(SCHEMA).unwrap()
+}
+
+/// Returns the path and reason of a mismatch.
+fn mismatch(schema: &Schema, type_name: &str, diag: &str) -> (String, String) {
+    match schema.validate(type_name, &from_diag(diag).unwrap()) {
+        Err(SchemaError::Mismatch { path, reason }) => (path.to_string(), reason),
+        other => panic!("unexpected result for {}: {:?}", diag, other),
+    }
+}
+
+fn valid(schema: &Schema, type_name: &str, diag: &str) {
+    if let Err(error) = schema.validate(type_name, &from_diag(diag).unwrap()) {
+        panic!("{} is not a valid {}: {}", diag, type_name, error);
+    }
+}
+
+#[test]
+fn test_parse() {
+    let schema = schema();
+    assert_eq!(schema.types().len(), 13);
+    match &schema.types()["Directory"] {
+        Type::Struct(directory) => {
+            let names: Vec<_> = directory.fields.iter().map(|field| &field.name).collect();
+            assert_eq!(names, ["name", "entries", "parent"]);
+            assert!(directory.fields[2].optional && directory.fields[2].nullable);
+            assert!(matches!(
+                directory.fields[1].value_type,
+                TypeTerm::Inline(_)
+            ));
+            let mut renames = BTreeMap::new();
+            renames.insert("name".to_string(), "n".to_string());
+            assert_eq!(
+                directory.representation,
+                StructRepresentation::Map { renames }
+            );
+        }
+        other => panic!("unexpected type {:?}", other),
+    }
+
+    assert!(matches!(
+        Schema::parse("type A struct { a Missing }"),
+        Err(SchemaError::Invalid(_))
+    ));
+    assert!(matches!(
+        Schema::parse("type A int\ntype A string"),
+        Err(SchemaError::Invalid(_))
+    ));
+    assert!(matches!(
+        Schema::parse(
+            "type A union { | Int \"i\" } representation inline { discriminantKey \"t\" }"
+        ),
+        Err(SchemaError::Invalid(_))
+    ));
+    assert!(matches!(
+        Schema::parse("type A struct { a Int"),
+        Err(SchemaError::Syntax {
+            position: 21,
+            expected: "field name or `}`"
+        })
+    ));
+    assert!(matches!(
+        Schema::parse("type A copy B"),
+        Err(SchemaError::Syntax { .. })
+    ));
+}
+
+#[test]
+fn test_dmt() {
+    let dsl = Schema::parse(
+        r#"
+        type Person struct {
+            name String (rename "n")
+            age optional Int
+            friends [&Person]
+        }
+        type Pair struct {
+            second String
+            first Int
+        } representation tuple
+        type Value union {
+            | String string
+            | Person map
+        } representation kinded
+        "#,
+    )
+    .unwrap();
+    let json = r#"{"types": {
+        "Person": {"struct": {
+            "fields": {
+                "name": {"type": "String"},
+                "age": {"type": "Int", "optional": true},
+                "friends": {"type": {"list": {"valueType": {"link": {"expectedType": "Person"}}}}}
+            },
+            "representation": {"map": {"fields": {"name": {"rename": "n"}}}}
+        }},
+        "Pair": {"struct": {
+            "fields": {"second": {"type": "String"}, "first": {"type": "Int"}},
+            "representation": {"tuple": {}}
+        }},
+        "Value": {"union": {
+            "members": ["String", "Person"],
+            "representation": {"kinded": {"string": "String", "map": "Person"}}
+        }}
+    }}"#;
+    assert_eq!(Schema::from_dmt_json(json.as_bytes()).unwrap(), dsl);
+
+    // DAG-CBOR sorts the keys, the explicit field order restores it.
+    let cbor = from_diag(
+        r#"{"types": {"Pair": {"struct": {
+            "fields": {"first": {"type": "Int"}, "second": {"type": "String"}},
+            "representation": {"tuple": {"fieldOrder": ["second", "first"]}}
+        }}}}"#,
+    )
+    .unwrap();
+    let schema = Schema::from_dmt(&cbor).unwrap();
+    assert_eq!(schema.types()["Pair"], dsl.types()["Pair"]);
+}
+
+#[test]
+fn test_structs() {
+    let schema = schema();
+    valid(&schema, "Directory", r#"{"n": "root", "entries": {}}"#);
+    valid(
+        &schema,
+        "Directory",
+        r#"{"n": "root", "entries": {"a": 1, "b": [3, "r", ["x"]]}, "parent": null}"#,
+    );
+    assert_eq!(
+        mismatch(&schema, "Directory", r#"{"name": "root", "entries": {}}"#),
+        ("name".to_string(), "unknown field".to_string())
+    );
+    assert_eq!(
+        mismatch(&schema, "Directory", r#"{"n": "root"}"#),
+        ("".to_string(), "missing field `entries`".to_string())
+    );
+    assert_eq!(
+        mismatch(
+            &schema,
+            "Directory",
+            r#"{"n": "root", "entries": {"a": [3, "x"]}}"#
+        ),
+        ("entries/a/1".to_string(), "unknown enum member".to_string())
+    );
+    assert_eq!(
+        mismatch(&schema, "File", r#"[3]"#),
+        (
+            "".to_string(),
+            "expected 2 to 3 elements, found 1".to_string()
+        )
+    );
+    assert_eq!(
+        mismatch(&schema, "File", r#"[3, "w", [1]]"#),
+        ("2/0".to_string(), "expected String, found Int".to_string())
+    );
+
+    valid(&schema, "Point", r#""1,-2""#);
+    assert_eq!(
+        mismatch(&schema, "Point", r#""1,2,3""#),
+        (
+            "".to_string(),
+            "expected 2 values joined by `,`, found 3".to_string()
+        )
+    );
+    assert_eq!(
+        mismatch(&schema, "Point", r#""1,a""#),
+        ("y".to_string(), "invalid value `a`".to_string())
+    );
+
+    valid(&schema, "Pairs", r#"[["a", true], ["b", "x"]]"#);
+    assert_eq!(
+        mismatch(&schema, "Pairs", r#"[["b", "x"]]"#),
+        ("".to_string(), "missing field `a`".to_string())
+    );
+    assert_eq!(
+        mismatch(&schema, "Pairs", r#"[["a"]]"#),
+        (
+            "0".to_string(),
+            "expected a list of two elements".to_string()
+        )
+    );
+}
+
+#[test]
+fn test_unions_and_enums() {
+    let schema = schema();
+    valid(&schema, "Shape", r#"{"point": "0,0"}"#);
+    valid(&schema, "Shape", r#"{"labels": "a=1.5&b=2"}"#);
+    valid(&schema, "Shape", r#"{"labels": ""}"#);
+    assert_eq!(
+        mismatch(&schema, "Shape", r#"{"labels": "a=x"}"#),
+        ("labels/a".to_string(), "invalid value `x`".to_string())
+    );
+    assert_eq!(
+        mismatch(&schema, "Shape", r#"{"circle": 1}"#),
+        (
+            "circle".to_string(),
+            "unknown union key `circle`".to_string()
+        )
+    );
+    assert_eq!(
+        mismatch(&schema, "Entry", r#""file""#),
+        ("".to_string(), "no union member of kind string".to_string())
+    );
+
+    valid(
+        &schema,
+        "Message",
+        r#"{"type": "ping", "body": {"seq": 1}}"#,
+    );
+    assert_eq!(
+        mismatch(
+            &schema,
+            "Message",
+            r#"{"type": "pong", "body": {"seq": "1"}}"#
+        ),
+        (
+            "body/seq".to_string(),
+            "expected Int, found String".to_string()
+        )
+    );
+    assert_eq!(
+        mismatch(&schema, "Message", r#"{"type": "pang", "body": {}}"#),
+        (
+            "type".to_string(),
+            "unknown discriminant `pang`".to_string()
+        )
+    );
+
+    valid(
+        &schema,
+        "Event",
+        r#"{"type": "pong", "seq": 1, "latency": 0.5}"#,
+    );
+    assert_eq!(
+        mismatch(
+            &schema,
+            "Event",
+            r#"{"type": "ping", "seq": 1, "latency": 0.5}"#
+        ),
+        ("latency".to_string(), "unknown field".to_string())
+    );
+
+    valid(&schema, "Level", "10");
+    assert_eq!(
+        mismatch(&schema, "Level", "2"),
+        ("".to_string(), "unknown enum member".to_string())
+    );
+    valid(&schema, "Mode", r#""w""#);
+    assert_eq!(
+        mismatch(&schema, "Mode", r#""Write""#),
+        ("".to_string(), "unknown enum member".to_string())
+    );
+}
+
+#[derive(Serialize)]
+struct Directory {
+    n: String,
+    entries: BTreeMap<String, Link<Directory>>,
+    parent: Option<Link<Directory>>,
+}
+
+#[test]
+fn test_typed_links() {
+    let schema = schema();
+    let mut store = MemoryBlockstore::new();
+    let leaf = store
+        .put_value(&Directory {
+            n: "leaf".into(),
+            entries: BTreeMap::new(),
+            parent: None,
+        })
+        .unwrap();
+    let file = store.put_value(&(3, "x")).unwrap();
+    let mut entries = BTreeMap::new();
+    entries.insert("leaf".to_string(), Link::new(leaf));
+    let root = Directory {
+        n: "root".into(),
+        entries,
+        parent: None,
+    };
+    schema.validate_value("Directory", &root).unwrap();
+    let root = store.put_value(&root).unwrap();
+    schema.validate_dag(&store, "Directory", &root).unwrap();
+
+    // The link points to a block of the wrong type.
+    let mut entries = BTreeMap::new();
+    entries.insert("file".to_string(), Link::new(file));
+    let broken = Directory {
+        n: "broken".into(),
+        entries,
+        parent: Some(Link::new(root)),
+    };
+    schema.validate_value("Directory", &broken).unwrap();
+    let broken = store.put_value(&broken).unwrap();
+    match schema.validate_dag(&store, "Directory", &broken) {
+        Err(SchemaError::Mismatch { path, reason }) => {
+            assert_eq!(path.to_string(), "entries/file");
+            assert_eq!(reason, "expected Map, found List");
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
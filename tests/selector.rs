@@ -0,0 +1,182 @@
+#![cfg(feature = "hash")]
+
+use cid::multihash::{Code, MultihashDigest};
+use cid::Cid;
+use serde_ipld_dagcbor::blockstore::{Blockstore, MemoryBlockstore};
+use serde_ipld_dagcbor::diag::from_diag;
+use serde_ipld_dagcbor::selector::{select, RecursionLimit, Selector, Traversal};
+use serde_ipld_dagcbor::{TraversalError, DAG_CBOR_CODEC};
+
+fn put(store: &mut MemoryBlockstore, diag: &str) -> Cid {
+    let data = from_diag(diag).unwrap();
+    let cid = Cid::new_v1(DAG_CBOR_CODEC, Code::Sha2_256.digest(&data));
+    store.put(&cid, &data).unwrap();
+    cid
+}
+
+fn selector(diag: &str) -> Selector {
+    Selector::from_slice(&from_diag(diag).unwrap()).unwrap()
+}
+
+fn paths(traversal: &Traversal) -> Vec<String> {
+    traversal
+        .matches
+        .iter()
+        .map(|matched| matched.path.to_string())
+        .collect()
+}
+
+/// Creates a linked list of three blocks, each with a name and a link to the next one.
+fn chain(store: &mut MemoryBlockstore) -> [Cid; 3] {
+    let c = put(store, r#"{"name": "c", "next": null}"#);
+    let b = put(store, &format!(r#"{{"name": "b", "next": 42({})}}"#, c));
+    let a = put(store, &format!(r#"{{"name": "a", "next": 42({})}}"#, b));
+    [a, b, c]
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(
+        selector(r#"{"R": {"l": {"depth": 3}, ":>": {"f": {"f>": {"next": {"@": {}}}}}}}"#),
+        Selector::ExploreRecursive {
+            limit: RecursionLimit::Depth(3),
+            sequence: Box::new(Selector::ExploreFields(
+                vec![("next".to_string(), Selector::ExploreRecursiveEdge)]
+                    .into_iter()
+                    .collect()
+            )),
+        }
+    );
+    assert_eq!(
+        selector(
+            r#"{"|": [{"i": {"i": 1, ">": {".": {}}}}, {"r": {"^": 0, "$": 2, ">": {".": {}}}}]}"#
+        ),
+        Selector::ExploreUnion(vec![
+            Selector::ExploreIndex(1, Box::new(Selector::Matcher)),
+            Selector::ExploreRange(0, 2, Box::new(Selector::Matcher)),
+        ])
+    );
+
+    for diag in [
+        r#"{"x": {}}"#,
+        r#"{".": {}, "a": {}}"#,
+        r#"{"a": {}}"#,
+        r#"{"i": {"i": -1, ">": {".": {}}}}"#,
+        r#"{"R": {"l": {"always": {}}, ":>": {".": {}}}}"#,
+        "[]",
+    ] {
+        assert!(
+            matches!(
+                Selector::from_slice(&from_diag(diag).unwrap()),
+                Err(TraversalError::InvalidSelector(_))
+            ),
+            "{}",
+            diag
+        );
+    }
+}
+
+#[test]
+fn test_fields_across_blocks() {
+    let mut store = MemoryBlockstore::new();
+    let [a, b, _] = chain(&mut store);
+
+    let traversal = select(
+        &store,
+        &a,
+        &selector(r#"{"f": {"f>": {"next": {"f": {"f>": {"name": {".": {}}}}}}}}"#),
+    )
+    .unwrap();
+    assert_eq!(paths(&traversal), ["next/name"]);
+    assert_eq!(traversal.matches[0].cid, b);
+    assert_eq!(traversal.matches[0].node().as_str(), Some("b"));
+    assert_eq!(traversal.blocks, [a, b]);
+}
+
+#[test]
+fn test_recursive() {
+    let mut store = MemoryBlockstore::new();
+    let [a, b, c] = chain(&mut store);
+
+    let names = |limit: &str| {
+        let traversal = select(
+            &store,
+            &a,
+            &selector(&format!(
+                r#"{{"R": {{"l": {}, ":>": {{"f": {{"f>": {{"name": {{".": {{}}}}, "next": {{"@": {{}}}}}}}}}}}}}}"#,
+                limit
+            )),
+        )
+        .unwrap();
+        let names: Vec<_> = traversal
+            .matches
+            .iter()
+            .map(|matched| matched.node().as_str().unwrap().to_string())
+            .collect();
+        (names, paths(&traversal), traversal.blocks)
+    };
+
+    let (all, all_paths, blocks) = names(r#"{"none": {}}"#);
+    assert_eq!(all, ["a", "b", "c"]);
+    assert_eq!(all_paths, ["name", "next/name", "next/next/name"]);
+    assert_eq!(blocks, [a, b, c]);
+
+    let (limited, _, blocks) = names(r#"{"depth": 2}"#);
+    assert_eq!(limited, ["a", "b"]);
+    // The last link is followed before the edge is reached, where the limit stops the recursion.
+    assert_eq!(blocks, [a, b, c]);
+}
+
+#[test]
+fn test_explore_all_and_missing_blocks() {
+    let mut store = MemoryBlockstore::new();
+    let missing = Cid::new_v1(DAG_CBOR_CODEC, Code::Sha2_256.digest(b"missing"));
+    let root = put(&mut store, r#"{"list": [1, 2, 3], "other": "x"}"#);
+
+    let traversal = select(
+        &store,
+        &root,
+        &selector(r#"{"a": {">": {"r": {"^": 1, "$": 5, ">": {".": {}}}}}}"#),
+    )
+    .unwrap();
+    assert_eq!(paths(&traversal), ["list/1", "list/2"]);
+    assert_eq!(traversal.matches[1].node().as_integer(), Some(3));
+
+    let root = put(&mut store, &format!("[42({})]", missing));
+    assert!(select(&store, &root, &selector(r#"{"a": {">": {".": {}}}}"#)).is_err());
+    // Links that aren't explored are not loaded.
+    let traversal = select(&store, &root, &selector(r#"{".": {}}"#)).unwrap();
+    assert_eq!(traversal.blocks, [root]);
+}
+
+#[test]
+fn test_endless_recursion() {
+    let mut store = MemoryBlockstore::new();
+    let root = put(&mut store, "[1]");
+
+    // A recursive edge that is applied to the same node over and over again.
+    let endless = selector(r#"{"R": {"l": {"none": {}}, ":>": {"@": {}}}}"#);
+    assert!(matches!(
+        select(&store, &root, &endless),
+        Err(TraversalError::InvalidSelector(_))
+    ));
+
+    // A limited recursion ends even without exploring a child.
+    let limited = selector(r#"{"R": {"l": {"depth": 3}, ":>": {"|": [{".": {}}, {"@": {}}]}}}"#);
+    let traversal = select(&store, &root, &limited).unwrap();
+    assert_eq!(traversal.matches.len(), 3);
+}
+
+#[test]
+fn test_long_chain() {
+    let mut store = MemoryBlockstore::new();
+    let first = put(&mut store, "[1]");
+    let mut next = first;
+    for _ in 0..10_000 {
+        next = put(&mut store, &format!("[42({})]", next));
+    }
+    let all = selector(r#"{"R": {"l": {"none": {}}, ":>": {"a": {">": {"@": {}}}}}}"#);
+    let traversal = select(&store, &next, &all).unwrap();
+    assert_eq!(traversal.blocks.len(), 10_001);
+    assert_eq!(traversal.blocks.last(), Some(&first));
+}
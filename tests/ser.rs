@@ -1,5 +1,11 @@
 use serde_bytes::{ByteBuf, Bytes};
-use serde_ipld_dagcbor::{from_slice, to_vec};
+use serde_derive::Serialize;
+use serde_ipld_dagcbor::{
+    diag::{from_diag, to_diag},
+    from_slice,
+    ser::{to_vec_with_options, EncodeOptions},
+    to_vec,
+};
 use std::collections::BTreeMap;
 
 #[test]
@@ -11,7 +17,10 @@ fn test_string() {
 #[test]
 fn test_list() {
     let value = vec![1, 2, 3];
-    assert_eq!(&to_vec(&value).unwrap()[..], b"\x83\x01\x02\x03");
+    assert_eq!(
+        &to_vec(&value).unwrap()[..],
+        from_diag("[1, 2, 3]").unwrap()
+    );
 }
 
 #[test]
@@ -30,13 +39,13 @@ fn test_object() {
 #[test]
 fn test_float() {
     let vec = to_vec(&12.3f64).unwrap();
-    assert_eq!(vec, b"\xfb@(\x99\x99\x99\x99\x99\x9a");
+    assert_eq!(vec, from_diag("12.3").unwrap());
 }
 
 #[test]
 fn test_f32() {
     let vec = to_vec(&4000.5f32).unwrap();
-    assert_eq!(vec, b"\xfb\x40\xaf\x41\x00\x00\x00\x00\x00");
+    assert_eq!(vec, from_diag("4000.5").unwrap());
 }
 
 #[test]
@@ -61,19 +70,19 @@ fn test_nan() {
 fn test_integer() {
     // u8
     let vec = to_vec(&24).unwrap();
-    assert_eq!(vec, b"\x18\x18");
+    assert_eq!(vec, from_diag("24").unwrap());
     // i8
     let vec = to_vec(&-5).unwrap();
-    assert_eq!(vec, b"\x24");
+    assert_eq!(vec, from_diag("-5").unwrap());
     // i16
     let vec = to_vec(&-300).unwrap();
-    assert_eq!(vec, b"\x39\x01\x2b");
+    assert_eq!(vec, from_diag("-300").unwrap());
     // i32
     let vec = to_vec(&-23567997).unwrap();
-    assert_eq!(vec, b"\x3a\x01\x67\x9e\x7c");
+    assert_eq!(vec, from_diag("-23567997").unwrap());
     // u64
     let vec = to_vec(&::std::u64::MAX).unwrap();
-    assert_eq!(vec, b"\x1b\xff\xff\xff\xff\xff\xff\xff\xff");
+    assert_eq!(vec, from_diag("18446744073709551615").unwrap());
 }
 
 #[test]
@@ -124,3 +133,238 @@ fn test_byte_string() {
     // byte strings > 2^32 bytes have 9-byte headers, but they take too much RAM
     // to test in Travis.
 }
+
+mod first {
+    #[derive(serde_derive::Serialize)]
+    pub struct Item {
+        pub zz: u8,
+        pub a: u8,
+    }
+}
+
+mod second {
+    #[derive(serde_derive::Serialize)]
+    pub struct Item {
+        pub b: u8,
+        pub a: u8,
+    }
+}
+
+#[derive(Serialize)]
+struct Entry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    long: Option<u8>,
+    id: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    x: Option<u8>,
+}
+
+#[derive(Serialize)]
+struct Sparse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    a: Option<u8>,
+    b: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    c: Option<u8>,
+}
+
+#[derive(Serialize)]
+struct Varying {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bb: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    a: Option<u8>,
+    c: u8,
+}
+
+/// Returns the diagnostic notation of the value with its struct fields in canonical order.
+fn to_canonical_diag<T: serde::Serialize>(value: &T) -> String {
+    let options = EncodeOptions {
+        canonical_struct_fields: true,
+        ..Default::default()
+    };
+    to_diag(&to_vec_with_options(value, &options).unwrap()).unwrap()
+}
+
+#[test]
+fn test_struct_field_order() {
+    // Fields are written in declaration order by default.
+    let items = vec![first::Item { zz: 1, a: 2 }, first::Item { zz: 3, a: 4 }];
+    assert_eq!(
+        to_diag(&to_vec(&items).unwrap()).unwrap(),
+        r#"[{"zz": 1, "a": 2}, {"zz": 3, "a": 4}]"#
+    );
+
+    // Fields are sorted canonically, also when the order is cached for repeated structs.
+    assert_eq!(
+        to_canonical_diag(&items),
+        r#"[{"a": 2, "zz": 1}, {"a": 4, "zz": 3}]"#
+    );
+    // The cached order is used by later serializations.
+    assert_eq!(
+        to_canonical_diag(&first::Item { zz: 5, a: 6 }),
+        r#"{"a": 6, "zz": 5}"#
+    );
+
+    // Structs with the same name and number of fields don't share their order.
+    let items = (
+        first::Item { zz: 1, a: 2 },
+        second::Item { b: 3, a: 4 },
+        first::Item { zz: 5, a: 6 },
+    );
+    assert_eq!(
+        to_canonical_diag(&items),
+        r#"[{"a": 2, "zz": 1}, {"a": 4, "b": 3}, {"a": 6, "zz": 5}]"#
+    );
+
+    // Skipped fields change the shape of a struct.
+    let entries = vec![
+        Entry {
+            long: Some(1),
+            id: 2,
+            x: None,
+        },
+        Entry {
+            long: None,
+            id: 3,
+            x: Some(4),
+        },
+        Entry {
+            long: Some(5),
+            id: 6,
+            x: None,
+        },
+    ];
+    assert_eq!(
+        to_canonical_diag(&entries),
+        r#"[{"id": 2, "long": 1}, {"x": 4, "id": 3}, {"id": 6, "long": 5}]"#
+    );
+
+    // Fields declared in canonical order stay in order.
+    let sparse = vec![
+        Sparse {
+            a: Some(1),
+            b: 2,
+            c: None,
+        },
+        Sparse {
+            a: Some(3),
+            b: 4,
+            c: None,
+        },
+        Sparse {
+            a: None,
+            b: 5,
+            c: Some(6),
+        },
+    ];
+    assert_eq!(
+        to_canonical_diag(&sparse),
+        r#"[{"a": 1, "b": 2}, {"a": 3, "b": 4}, {"b": 5, "c": 6}]"#
+    );
+
+    // Different keys with the same number of fields.
+    let varying = vec![
+        Varying {
+            bb: None,
+            a: Some(1),
+            c: 2,
+        },
+        Varying {
+            bb: Some(3),
+            a: None,
+            c: 4,
+        },
+    ];
+    assert_eq!(
+        to_canonical_diag(&varying),
+        r#"[{"a": 1, "c": 2}, {"c": 4, "bb": 3}]"#
+    );
+
+    // Null fields are omitted from the cached order.
+    let options = EncodeOptions {
+        canonical_struct_fields: true,
+        omit_null_fields: true,
+        ..Default::default()
+    };
+    let encoded = to_vec_with_options(
+        &(
+            Varying {
+                bb: Some(1),
+                a: None,
+                c: 2,
+            },
+            Some(3),
+        ),
+        &options,
+    );
+    assert_eq!(
+        to_diag(&encoded.unwrap()).unwrap(),
+        r#"[{"c": 2, "bb": 1}, 3]"#
+    );
+}
+
+#[test]
+fn test_struct_field_order_threads() {
+    // The cached orders are shared between threads.
+    let threads: Vec<_> = (0..4u8)
+        .map(|i| {
+            std::thread::spawn(move || {
+                let entry = Entry {
+                    long: Some(i),
+                    id: i,
+                    x: Some(i),
+                };
+                (0..100)
+                    .map(|_| to_canonical_diag(&entry))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    for (i, thread) in threads.into_iter().enumerate() {
+        let expected = format!(r#"{{"x": {i}, "id": {i}, "long": {i}}}"#);
+        assert!(thread.join().unwrap().iter().all(|diag| *diag == expected));
+    }
+}
+
+#[test]
+fn test_struct_field_error() {
+    #[derive(Serialize)]
+    struct Reading {
+        value: f64,
+    }
+
+    // Errors of field values are passed through.
+    let error = to_vec(&Reading { value: f64::NAN });
+    assert_eq!(
+        error.unwrap_err().to_string(),
+        to_vec(&f64::NAN).unwrap_err().to_string()
+    );
+}
+
+#[test]
+fn test_map_key_order() {
+    // Already sorted input.
+    let map = BTreeMap::from([("a", 1), ("b", 2), ("c", 3)]);
+    assert_eq!(
+        to_diag(&to_vec(&map).unwrap()).unwrap(),
+        r#"{"a": 1, "b": 2, "c": 3}"#
+    );
+
+    // Shorter keys sort first.
+    let map = BTreeMap::from([("aa", 1), ("b", 2), ("ab", 3), ("a", 4)]);
+    assert_eq!(
+        to_diag(&to_vec(&map).unwrap()).unwrap(),
+        r#"{"a": 4, "b": 2, "aa": 1, "ab": 3}"#
+    );
+
+    // Unordered input of any size gives the same result.
+    let map: BTreeMap<String, u32> = (0..1000).map(|i| (i.to_string(), i)).collect();
+    let unordered: std::collections::HashMap<_, _> = map.clone().into_iter().collect();
+    let encoded = to_vec(&unordered).unwrap();
+    assert_eq!(encoded, to_vec(&map).unwrap());
+    assert_eq!(from_slice::<BTreeMap<String, u32>>(&encoded).unwrap(), map);
+    assert!(to_diag(&encoded)
+        .unwrap()
+        .starts_with(r#"{"0": 0, "1": 1,"#));
+}
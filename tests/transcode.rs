@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use cid::Cid;
+use libipld_core::ipld::Ipld;
+use serde_ipld_dagcbor::diag::from_diag;
+use serde_ipld_dagcbor::transcode::{transcode_from_dag_json, transcode_to_dag_json};
+use serde_ipld_dagcbor::{json, DecodeError, EncodeError, TranscodeError};
+
+fn fixture() -> Ipld {
+    let cid = Cid::try_from("bafyreih4jspvxgh5xrp3wboocaajx5ym4ky7ok3gs5dsdkizgnegtanfxu").unwrap();
+    let mut nested = BTreeMap::new();
+    nested.insert("bytes".to_string(), Ipld::Bytes(vec![0, 1, 2, 3]));
+    nested.insert("link".to_string(), Ipld::Link(cid));
+    let mut map = BTreeMap::new();
+    map.insert("nested".to_string(), Ipld::Map(nested));
+    map.insert("zz".to_string(), Ipld::Bool(true));
+    map.insert("a".to_string(), Ipld::Null);
+    map.insert("float".to_string(), Ipld::Float(-0.25));
+    map.insert(
+        "list".to_string(),
+        Ipld::List(vec![
+            Ipld::Integer(1),
+            Ipld::Integer(-(1 << 64)),
+            Ipld::Integer(u64::MAX.into()),
+            Ipld::String("escaped \"string\"".to_string()),
+            Ipld::List(vec![]),
+        ]),
+    );
+    Ipld::Map(map)
+}
+
+#[test]
+fn test_to_dag_json() {
+    let ipld = fixture();
+    let cbor = serde_ipld_dagcbor::to_vec(&ipld).unwrap();
+    let mut transcoded = Vec::new();
+    transcode_to_dag_json(&cbor[..], &mut transcoded).unwrap();
+    assert_eq!(transcoded, json::to_vec(&ipld).unwrap());
+}
+
+#[test]
+fn test_from_dag_json() {
+    let ipld = fixture();
+    let encoded = json::to_vec(&ipld).unwrap();
+    let mut transcoded = Vec::new();
+    transcode_from_dag_json(&encoded[..], &mut transcoded).unwrap();
+    assert_eq!(transcoded, serde_ipld_dagcbor::to_vec(&ipld).unwrap());
+}
+
+#[test]
+fn test_from_dag_json_sorts_keys() {
+    let input = br#" { "bb": [ 1, 2 ], "a": {"ccc": 1, "b": 2}, "c": "" } "#;
+    let mut transcoded = Vec::new();
+    transcode_from_dag_json(&input[..], &mut transcoded).unwrap();
+    let ipld: Ipld = json::from_slice(input).unwrap();
+    assert_eq!(transcoded, serde_ipld_dagcbor::to_vec(&ipld).unwrap());
+    // Length-first order: "a", "c", "bb".
+    assert_eq!(&transcoded[..3], b"\xa3\x61a");
+
+    let mut duplicate = Vec::new();
+    assert!(transcode_from_dag_json(&br#"{"a":1,"a":2}"#[..], &mut duplicate).is_err());
+}
+
+#[test]
+fn test_reordered_nested_entries() {
+    // The values of entries that change their position contain maps, lists and links themselves.
+    let input = br#"{"bb": {"ccc": [1, {"/": "bafyreih4jspvxgh5xrp3wboocaajx5ym4ky7ok3gs5dsdkizgnegtanfxu"}], "a": {"/": {"bytes": "AQI"}}}, "a": [[1, []], {"zz": 1, "b": [2]}], "c": null}"#;
+    let ipld: Ipld = json::from_slice(input).unwrap();
+    let mut cbor = Vec::new();
+    transcode_from_dag_json(input, &mut cbor).unwrap();
+    assert_eq!(cbor, serde_ipld_dagcbor::to_vec(&ipld).unwrap());
+
+    let mut transcoded = Vec::new();
+    transcode_to_dag_json(&cbor, &mut transcoded).unwrap();
+    assert_eq!(transcoded, json::to_vec(&ipld).unwrap());
+}
+
+#[test]
+fn test_indefinite_length() {
+    let cbor = from_diag(r#"{_ "a": [_ 1, 2]}"#).unwrap();
+    let mut transcoded = Vec::new();
+    transcode_to_dag_json(&cbor[..], &mut transcoded).unwrap();
+    assert_eq!(transcoded, br#"{"a":[1,2]}"#);
+}
+
+#[test]
+fn test_errors() {
+    let mut output = Vec::new();
+    assert!(matches!(
+        transcode_to_dag_json(&b"\x01\x02"[..], &mut output),
+        Err(TranscodeError::Decode(DecodeError::TrailingData))
+    ));
+    // {"/": 1} cannot be represented in DAG-JSON.
+    assert!(matches!(
+        transcode_to_dag_json(&b"\xa1\x61/\x01"[..], &mut output),
+        Err(TranscodeError::Encode(EncodeError::Msg(_)))
+    ));
+    // Unsupported CBOR tag, reported in full.
+    match transcode_to_dag_json(&b"\xd9\x01\x02\x01"[..], &mut output) {
+        Err(TranscodeError::Decode(DecodeError::Msg(message))) => assert!(message.contains("258")),
+        result => panic!("unexpected result: {:?}", result),
+    }
+    // `undefined` is not part of the IPLD data model.
+    assert!(matches!(
+        transcode_to_dag_json(&b"\xf7"[..], &mut output),
+        Err(TranscodeError::Decode(DecodeError::Unsupported {
+            byte: 0xf7
+        }))
+    ));
+    assert!(matches!(
+        transcode_from_dag_json(&b"18446744073709551616"[..], &mut output),
+        Err(TranscodeError::Decode(DecodeError::Overflow { .. }))
+    ));
+    assert!(matches!(
+        transcode_from_dag_json(&b"[1, 2"[..], &mut output),
+        Err(TranscodeError::Decode(DecodeError::Eof))
+    ));
+}
+
+#[test]
+fn test_depth_limit() {
+    let nested = "[".repeat(1000) + &"]".repeat(1000);
+    let mut output = Vec::new();
+    assert!(matches!(
+        transcode_from_dag_json(nested.as_bytes(), &mut output),
+        Err(TranscodeError::Decode(DecodeError::DepthLimit))
+    ));
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "hash")]
+
+use cid::multihash::{Code, MultihashDigest};
+use cid::Cid;
+use serde_ipld_dagcbor::blockstore::{Blockstore, MemoryBlockstore};
+use serde_ipld_dagcbor::walk::{Order, Walker};
+use serde_ipld_dagcbor::BlockstoreError;
+
+const RAW_CODEC: u64 = 0x55;
+
+/// Creates the DAG root -> [a, b], a -> [c, raw], b -> [c].
+fn dag(store: &mut MemoryBlockstore) -> [Cid; 5] {
+    let raw = Cid::new_v1(RAW_CODEC, Code::Sha2_256.digest(b"raw"));
+    store.put(&raw, b"raw").unwrap();
+    let c = store.put_value(&"c").unwrap();
+    let a = store.put_value(&(c, raw)).unwrap();
+    let b = store.put_value(&vec![c]).unwrap();
+    let root = store.put_value(&[a, b]).unwrap();
+    [root, a, b, c, raw]
+}
+
+fn walk(walker: Walker<MemoryBlockstore>) -> Vec<Cid> {
+    walker.map(|block| block.unwrap().0).collect()
+}
+
+#[test]
+fn test_order() {
+    let mut store = MemoryBlockstore::new();
+    let [root, a, b, c, raw] = dag(&mut store);
+
+    assert_eq!(walk(Walker::new(&store, root)), [root, a, b, c, raw]);
+
+    let mut walker = Walker::new(&store, root);
+    walker.set_order(Order::DepthFirst);
+    assert_eq!(walk(walker), [root, a, c, raw, b]);
+
+    // The yielded data is the data of the block.
+    let (cid, data) = Walker::new(&store, a).nth(2).unwrap().unwrap();
+    assert_eq!(cid, raw);
+    assert_eq!(data, b"raw");
+}
+
+#[test]
+fn test_max_depth_and_codecs() {
+    let mut store = MemoryBlockstore::new();
+    let [root, a, b, c, _] = dag(&mut store);
+
+    let mut walker = Walker::new(&store, root);
+    walker.set_max_depth(Some(1));
+    assert_eq!(walk(walker), [root, a, b]);
+
+    let mut walker = Walker::new(&store, root);
+    walker.set_max_depth(Some(0));
+    assert_eq!(walk(walker), [root]);
+
+    let mut walker = Walker::new(&store, root);
+    walker.set_codecs(Some(vec![serde_ipld_dagcbor::DAG_CBOR_CODEC]));
+    assert_eq!(walk(walker), [root, a, b, c]);
+    let mut walker = Walker::new(&store, root);
+    walker.set_codecs(Some(vec![RAW_CODEC]));
+    assert_eq!(walk(walker), []);
+}
+
+#[test]
+fn test_cycle() {
+    // Hashes prevent cycles, but a store might contain blocks that don't match their CID.
+    let mut store = MemoryBlockstore::new();
+    let first = Cid::new_v1(
+        serde_ipld_dagcbor::DAG_CBOR_CODEC,
+        Code::Sha2_256.digest(b"1"),
+    );
+    let second = Cid::new_v1(
+        serde_ipld_dagcbor::DAG_CBOR_CODEC,
+        Code::Sha2_256.digest(b"2"),
+    );
+    store
+        .put(
+            &first,
+            &serde_ipld_dagcbor::to_vec(&[second, first]).unwrap(),
+        )
+        .unwrap();
+    store
+        .put(&second, &serde_ipld_dagcbor::to_vec(&[first]).unwrap())
+        .unwrap();
+
+    let mut walker = Walker::new(&store, first);
+    walker.set_order(Order::DepthFirst);
+    assert_eq!(walk(walker), [first, second]);
+}
+
+#[test]
+fn test_errors() {
+    let mut store = MemoryBlockstore::new();
+    let [root, a, b, c, raw] = dag(&mut store);
+    store.delete(&a).unwrap();
+
+    let results: Vec<_> = Walker::new(&store, root).collect();
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0].as_ref().unwrap().0, root);
+    assert!(matches!(&results[1], Err(BlockstoreError::NotFound(cid)) if *cid == a));
+    assert_eq!(results[2].as_ref().unwrap().0, b);
+    // The raw block is only reachable through the missing one.
+    assert_eq!(results[3].as_ref().unwrap().0, c);
+    assert!(!results.iter().flatten().any(|(cid, _)| *cid == raw));
+
+    let invalid = Cid::new_v1(
+        serde_ipld_dagcbor::DAG_CBOR_CODEC,
+        Code::Sha2_256.digest(b"x"),
+    );
+    store.put(&invalid, b"\x82\x01").unwrap();
+    let results: Vec<_> = Walker::new(&store, invalid).collect();
+    assert!(matches!(&results[..], [Err(BlockstoreError::Decode(_))]));
+}